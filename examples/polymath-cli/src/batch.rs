@@ -0,0 +1,304 @@
+//!
+//! # Batch
+//!
+//! Converts every line of the given files (or stdin) as a separate formula.
+//! With `--json` each line is a JSON Lines record, either a string or an
+//! object with an `asciimath` field. Objects are written back with the
+//! converted formula added as `output`, records that can't be read become
+//! `{"error": ...}` so that output lines match input records. With
+//! `--output-dir` the outputs mirror the relative paths of the inputs.
+//!
+
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Component, Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::ValueEnum;
+use polymath_rs::{diagnose, diagnostics::line_column, mathml::Options, render};
+use serde_json::{json, Value};
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Format {
+    #[value(name = "mathml")]
+    MathMl,
+    Latex,
+    Text,
+}
+
+impl From<Format> for polymath_rs::Format {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::MathMl => polymath_rs::Format::MathMl,
+            Format::Latex => polymath_rs::Format::Latex,
+            Format::Text => polymath_rs::Format::Text,
+        }
+    }
+}
+
+impl Format {
+    pub fn render(self, asciimath: &str) -> String {
+        render(asciimath, self.into(), &Options::default())
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::MathMl => "mathml",
+            Format::Latex => "tex",
+            Format::Text => "txt",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Format::MathMl => "application/mathml+xml; charset=utf-8",
+            Format::Latex => "application/x-latex; charset=utf-8",
+            Format::Text => "text/plain; charset=utf-8",
+        }
+    }
+}
+
+pub struct Batch {
+    pub files: Vec<PathBuf>,
+    pub json: bool,
+    pub output_dir: Option<PathBuf>,
+    pub format: Format,
+}
+
+impl Batch {
+    pub fn run(&self) -> ExitCode {
+        let mut failed = false;
+        let mut outputs = HashSet::new();
+
+        let result = if self.files.is_empty() {
+            self.open_output(Path::new("stdin"), &mut outputs)
+                .and_then(|mut output| {
+                    self.convert_input("<stdin>", io::stdin().lock(), &mut output, &mut failed)
+                })
+        } else {
+            self.files.iter().try_for_each(|path| {
+                let file = File::open(path).map_err(|error| {
+                    io::Error::new(error.kind(), format!("{}: {error}", path.display()))
+                })?;
+                let mut output = self.open_output(path, &mut outputs)?;
+                self.convert_input(
+                    &path.display().to_string(),
+                    BufReader::new(file),
+                    &mut output,
+                    &mut failed,
+                )
+            })
+        };
+
+        match result {
+            Ok(()) if !failed => ExitCode::SUCCESS,
+            Ok(()) => ExitCode::FAILURE,
+            Err(error) => {
+                eprintln!("error: {error}");
+                ExitCode::FAILURE
+            }
+        }
+    }
+
+    ///
+    /// Stdout, or the file in the output directory for the input `path`.
+    /// Inputs that would end up in the same file are rejected.
+    ///
+    fn open_output(
+        &self,
+        path: &Path,
+        outputs: &mut HashSet<PathBuf>,
+    ) -> io::Result<Box<dyn Write>> {
+        let Some(dir) = &self.output_dir else {
+            return Ok(Box::new(io::stdout().lock()));
+        };
+
+        let target = dir.join(output_path(path, self.format)?);
+        if !outputs.insert(target.clone()) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!(
+                    "{}: output {} has already been written for another input",
+                    path.display(),
+                    target.display()
+                ),
+            ));
+        }
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(Box::new(BufWriter::new(File::create(target)?)))
+    }
+
+    fn convert_input(
+        &self,
+        name: &str,
+        input: impl BufRead,
+        output: &mut dyn Write,
+        failed: &mut bool,
+    ) -> io::Result<()> {
+        for (index, line) in input.lines().enumerate() {
+            let line = line?;
+            let line_number = index + 1;
+
+            let converted = if self.json {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                self.convert_record(&line)
+                    .map(|(asciimath, record)| (asciimath, record.to_string()))
+            } else {
+                Ok((line.clone(), self.format.render(&line)))
+            };
+
+            match converted {
+                Ok((asciimath, converted)) => {
+                    for diagnostic in diagnose(&asciimath) {
+                        let (_, column) = line_column(&asciimath, diagnostic.span.start);
+                        eprintln!("{name}:{line_number}:{column}: {}", diagnostic.message);
+                        *failed = true;
+                    }
+                    writeln!(output, "{converted}")?;
+                }
+                Err(message) => {
+                    eprintln!("{name}:{line_number}: {message}");
+                    writeln!(output, "{}", json!({ "error": message }))?;
+                    *failed = true;
+                }
+            }
+        }
+
+        output.flush()
+    }
+
+    fn convert_record(&self, line: &str) -> Result<(String, Value), String> {
+        let record: Value =
+            serde_json::from_str(line).map_err(|error| format!("invalid record: {error}"))?;
+
+        match record {
+            Value::String(asciimath) => {
+                let output = Value::String(self.format.render(&asciimath));
+                Ok((asciimath, output))
+            }
+            Value::Object(mut object) => {
+                let asciimath = object
+                    .get("asciimath")
+                    .and_then(Value::as_str)
+                    .ok_or("record has no `asciimath` string")?
+                    .to_string();
+                object.insert(
+                    "output".to_string(),
+                    Value::String(self.format.render(&asciimath)),
+                );
+                Ok((asciimath, Value::Object(object)))
+            }
+            _ => Err("record is neither a string nor an object".to_string()),
+        }
+    }
+}
+
+///
+/// Path of the output for the input `path` relative to the output
+/// directory, the input path without its root and with the extension of
+/// `format`. Inputs outside the current directory can't be mirrored.
+///
+fn output_path(path: &Path, format: Format) -> io::Result<PathBuf> {
+    let mut relative = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::ParentDir => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "{}: inputs outside the current directory need an absolute path with --output-dir",
+                        path.display()
+                    ),
+                ))
+            }
+            Component::Prefix(_) | Component::RootDir | Component::CurDir => {}
+        }
+    }
+
+    Ok(relative.with_extension(format.extension()))
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs, path::Path, process};
+
+    use super::{output_path, Batch, Format};
+
+    fn batch(json: bool) -> Batch {
+        Batch {
+            files: Vec::new(),
+            json,
+            output_dir: None,
+            format: Format::Latex,
+        }
+    }
+
+    #[test]
+    fn test_json_lines() {
+        let input =
+            "\"a/b\"\n{\"asciimath\": \"x^2\", \"id\": 7}\n\n{\"id\": 8}\nnot json\n\"c\"\n";
+        let mut output = Vec::new();
+        let mut failed = false;
+
+        batch(true)
+            .convert_input("<test>", input.as_bytes(), &mut output, &mut failed)
+            .unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], "\"\\\\frac{a}{b}\"");
+        assert_eq!(lines[1], r#"{"asciimath":"x^2","id":7,"output":"x^{2}"}"#);
+        assert_eq!(lines[2], r#"{"error":"record has no `asciimath` string"}"#);
+        assert!(lines[3].starts_with(r#"{"error":"invalid record: "#));
+        assert_eq!(lines[4], "\"c\"");
+        assert!(failed);
+    }
+
+    #[test]
+    fn test_output_paths() {
+        let path = |input: &str| output_path(Path::new(input), Format::MathMl).ok();
+
+        assert_eq!(path("a/x.txt"), Some("a/x.mathml".into()));
+        assert_eq!(path("./b/x.txt"), Some("b/x.mathml".into()));
+        assert_eq!(path("/data/x"), Some("data/x.mathml".into()));
+        assert_eq!(path("../x.txt"), None);
+    }
+
+    #[test]
+    fn test_output_dir() {
+        let root = env::temp_dir().join(format!("polymath-batch-{}", process::id()));
+        for dir in ["a", "b"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            fs::write(root.join(dir).join("x.txt"), format!("{dir}/2")).unwrap();
+        }
+
+        let mut batch = batch(false);
+        batch.files = vec![root.join("a/x.txt"), root.join("b/x.txt")];
+        batch.output_dir = Some(root.join("out"));
+        batch.run();
+
+        let written = |dir: &str| {
+            let input = root.join(dir).join("x.tex");
+            let relative = input.strip_prefix("/").unwrap_or(&input);
+            fs::read_to_string(root.join("out").join(relative)).unwrap()
+        };
+        assert_eq!(written("a"), "\\frac{a}{2}\n");
+        assert_eq!(written("b"), "\\frac{b}{2}\n");
+
+        batch.files = vec![root.join("a/x.txt"), root.join("a/./x.txt")];
+        assert_eq!(batch.run(), process::ExitCode::FAILURE);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
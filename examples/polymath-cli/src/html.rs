@@ -0,0 +1,114 @@
+//!
+//! # HTML
+//!
+//! Renders a list of formulas into a standalone HTML5 page, each formula next
+//! to its source and diagnostics.
+//!
+
+use std::fmt::Write;
+
+use polymath_rs::{diagnose, to_math_ml};
+
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; }
+.formula { display: grid; grid-template-columns: 1fr 1fr; gap: 1em; padding: 1em 0; border-bottom: 1px solid #ddd; }
+.source { margin: 0; white-space: pre-wrap; }
+.diagnostics { grid-column: 1 / span 2; margin: 0; color: #b00; }";
+
+pub fn page(title: &str, formulas: &[String], css: Option<&str>) -> String {
+    let mut html = String::new();
+
+    // writing to a string never fails
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n\
+         <html>\n\
+         <head>\n\
+         <meta charset=\"utf-8\">\n\
+         <title>{}</title>\n\
+         <style>\n{STYLE}\n{}\n</style>\n\
+         </head>\n\
+         <body>\n\
+         <h1>{}</h1>\n",
+        escape(title),
+        css.unwrap_or_default(),
+        escape(title),
+    );
+
+    for formula in formulas {
+        let _ = write!(
+            html,
+            "<section class=\"formula\">\n\
+             <pre class=\"source\">{}</pre>\n\
+             <div class=\"output\">{}</div>\n",
+            escape(formula),
+            to_math_ml(formula),
+        );
+
+        let diagnostics = diagnose(formula);
+        if !diagnostics.is_empty() {
+            html.push_str("<ul class=\"diagnostics\">\n");
+            for diagnostic in diagnostics {
+                let _ = writeln!(
+                    html,
+                    "<li>{}..{}: {}</li>",
+                    diagnostic.span.start,
+                    diagnostic.span.end,
+                    escape(&diagnostic.message)
+                );
+            }
+            html.push_str("</ul>\n");
+        }
+
+        html.push_str("</section>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn escape(text: &str) -> String {
+    text.chars()
+        .fold(String::with_capacity(text.len()), |mut escaped, c| {
+            match c {
+                '&' => escaped.push_str("&amp;"),
+                '<' => escaped.push_str("&lt;"),
+                '>' => escaped.push_str("&gt;"),
+                '"' => escaped.push_str("&quot;"),
+                c => escaped.push(c),
+            }
+            escaped
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::page;
+
+    #[test]
+    fn test_page() {
+        let html = page(
+            "a < b",
+            &["x^2".to_string(), "(y".to_string()],
+            Some(".output { color: red; }"),
+        );
+
+        assert!(html.starts_with("<!DOCTYPE html>\n<html>\n"));
+        assert!(html.contains("<title>a &lt; b</title>"));
+        assert!(html.contains(".output { color: red; }\n</style>"));
+        assert!(html.contains(
+            "<section class=\"formula\">\n\
+             <pre class=\"source\">x^2</pre>\n\
+             <div class=\"output\"><math display=\"block\"><msup><mi>x</mi><mn>2</mn></msup></math></div>\n\
+             </section>\n"
+        ));
+        assert!(html.contains(
+            "<pre class=\"source\">(y</pre>\n\
+             <div class=\"output\"><math display=\"block\"><mi>(</mi><mi>y</mi></math></div>\n\
+             <ul class=\"diagnostics\">\n\
+             <li>0..1: unclosed bracket `(`</li>\n\
+             </ul>\n"
+        ));
+        assert!(html.ends_with("</section>\n</body>\n</html>\n"));
+    }
+}
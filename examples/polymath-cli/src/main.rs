@@ -1,5 +1,18 @@
-use std::{env::temp_dir, fs::File, io::Write};
+mod batch;
+mod html;
+mod repl;
+mod serve;
+mod watch;
 
+use std::{
+    env::temp_dir,
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use batch::{Batch, Format};
 use clap::{Parser, Subcommand};
 use rand::Rng;
 
@@ -16,9 +29,66 @@ enum Command {
         #[arg(short, long)]
         open: bool,
     },
+    /// Convert every line of the given files, or stdin, as a formula
+    Convert {
+        files: Vec<PathBuf>,
+        /// Read JSON Lines records instead of plain lines
+        #[arg(long)]
+        json: bool,
+        /// Write one file per input into this directory instead of stdout
+        #[arg(short, long)]
+        output_dir: Option<PathBuf>,
+        #[arg(short, long, value_enum, default_value_t = Format::MathMl)]
+        format: Format,
+    },
+    /// Read formulas interactively and inspect tokens, CST, AST and MathML
+    Repl,
+    /// Render formulas into a standalone HTML page
+    Html {
+        formulas: Vec<String>,
+        /// Read formulas from a file, one per line
+        #[arg(short, long)]
+        file: Option<PathBuf>,
+        /// Stylesheet to embed into the page
+        #[arg(long)]
+        css: Option<PathBuf>,
+        #[arg(long, default_value = "Polymath")]
+        title: String,
+        /// Write the page to this path instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Open the page in a browser, written to a temporary file if no output is given
+        #[arg(long)]
+        open: bool,
+    },
+    /// Re-render `.amath` files next to their source whenever they change
+    Watch {
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+        /// Write a `.html` page instead of `.mathml`
+        #[arg(long)]
+        html: bool,
+        /// Convert formulas between delimiters instead of one formula per line
+        #[arg(long)]
+        document: bool,
+    },
+    /// Serve conversions over HTTP on localhost
+    Serve {
+        #[arg(short, long, default_value_t = 8080)]
+        port: u16,
+        /// Maximum size of a request body in bytes
+        #[arg(long, default_value_t = 64 * 1024)]
+        max_body: usize,
+        /// Maximum number of tokens of a formula
+        #[arg(long, default_value_t = 1000)]
+        max_tokens: usize,
+        /// Maximum nesting of brackets and operators of a formula
+        #[arg(long, default_value_t = 64)]
+        max_depth: usize,
+    },
 }
 
-fn main() {
+fn main() -> ExitCode {
     let args = Args::parse();
 
     match args.command {
@@ -43,6 +113,119 @@ fn main() {
             } else {
                 println!("{mathml}");
             }
+
+            ExitCode::SUCCESS
+        }
+        Command::Convert {
+            files,
+            json,
+            output_dir,
+            format,
+        } => Batch {
+            files,
+            json,
+            output_dir,
+            format,
         }
+        .run(),
+        Command::Repl => match repl::run() {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("error: {error}");
+                ExitCode::FAILURE
+            }
+        },
+        Command::Html {
+            formulas,
+            file,
+            css,
+            title,
+            output,
+            open,
+        } => match write_html(formulas, file, css, &title, output, open) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("error: {error}");
+                ExitCode::FAILURE
+            }
+        },
+        Command::Serve {
+            port,
+            max_body,
+            max_tokens,
+            max_depth,
+        } => match serve::run(
+            port,
+            &serve::Limits {
+                max_body,
+                max_tokens,
+                max_depth,
+            },
+        ) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("error: {error}");
+                ExitCode::FAILURE
+            }
+        },
+        Command::Watch {
+            paths,
+            html,
+            document,
+        } => match (watch::Watch {
+            paths,
+            html,
+            document,
+        })
+        .run()
+        {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("error: {error}");
+                ExitCode::FAILURE
+            }
+        },
+    }
+}
+
+fn write_html(
+    mut formulas: Vec<String>,
+    file: Option<PathBuf>,
+    css: Option<PathBuf>,
+    title: &str,
+    output: Option<PathBuf>,
+    open: bool,
+) -> std::io::Result<()> {
+    if let Some(file) = file {
+        formulas.extend(
+            fs::read_to_string(file)?
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(str::to_string),
+        );
     }
+
+    let css = css.map(fs::read_to_string).transpose()?;
+    let page = html::page(title, &formulas, css.as_deref());
+
+    let output = match output {
+        Some(output) => output,
+        None if open => temp_dir().join(format!("{}.html", rand::thread_rng().gen::<i64>())),
+        None => {
+            print!("{page}");
+            return Ok(());
+        }
+    };
+
+    fs::write(&output, page)?;
+
+    if open {
+        open_file(&output)?;
+    }
+
+    Ok(())
+}
+
+fn open_file(path: &Path) -> std::io::Result<()> {
+    open::that(format!("file://{}", path.canonicalize()?.display()))
 }
@@ -0,0 +1,233 @@
+//!
+//! # REPL
+//!
+//! Reads formulas interactively and prints the output of the pipeline stages
+//! that are switched on. `:<stage>` toggles a stage, `:<stage> <formula>`
+//! prints a single stage once.
+//!
+
+use std::{
+    env,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use polymath_rs::{
+    ast::predictive::convert, cst::predictive::parse, diagnose, mathml::to_mathml,
+    tokens::tokenize, transformations::transform,
+};
+use rustyline::{error::ReadlineError, DefaultEditor};
+
+const HELP: &str = "\
+:tokens [formula]  toggle the token list, or print it for a formula
+:cst [formula]     toggle the concrete syntax tree
+:ast [formula]     toggle the transformed abstract syntax tree
+:mathml [formula]  toggle the MathML output
+:help              show this help
+:quit              exit (or Ctrl-D)";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    Tokens,
+    Cst,
+    Ast,
+    MathMl,
+}
+
+impl Stage {
+    const ALL: [Stage; 4] = [Stage::Tokens, Stage::Cst, Stage::Ast, Stage::MathMl];
+
+    fn name(self) -> &'static str {
+        match self {
+            Stage::Tokens => "tokens",
+            Stage::Cst => "cst",
+            Stage::Ast => "ast",
+            Stage::MathMl => "mathml",
+        }
+    }
+}
+
+///
+/// The stages switched on, along with the handling of a single input line.
+///
+struct Session {
+    stages: Vec<Stage>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            stages: vec![Stage::MathMl],
+        }
+    }
+}
+
+impl Session {
+    ///
+    /// Runs a command or prints the stages for a formula, returns `false`
+    /// when the session should end.
+    ///
+    fn handle(&mut self, line: &str, out: &mut impl Write) -> io::Result<bool> {
+        let Some(command) = line.strip_prefix(':') else {
+            print_stages(line, &self.stages, out)?;
+            return Ok(true);
+        };
+
+        let (name, formula) = command.split_once(' ').unwrap_or((command, ""));
+
+        match (
+            name,
+            Stage::ALL.into_iter().find(|stage| stage.name() == name),
+        ) {
+            ("quit" | "q", _) => return Ok(false),
+            ("help" | "h", _) => writeln!(out, "{HELP}")?,
+            (_, Some(stage)) if !formula.trim().is_empty() => print_stages(formula, &[stage], out)?,
+            (_, Some(stage)) => {
+                if let Some(pos) = self.stages.iter().position(|s| *s == stage) {
+                    self.stages.remove(pos);
+                    writeln!(out, "{} off", stage.name())?;
+                } else {
+                    self.stages.push(stage);
+                    writeln!(out, "{} on", stage.name())?;
+                }
+            }
+            _ => writeln!(
+                out,
+                "unknown command `:{name}`, type :help for a list of commands"
+            )?,
+        }
+
+        Ok(true)
+    }
+}
+
+pub fn run() -> rustyline::Result<()> {
+    let mut editor = DefaultEditor::new()?;
+    let history = history_path();
+    let mut session = Session::default();
+
+    if let Some(history) = &history {
+        // there is no history on the first start
+        let _ = editor.load_history(history);
+    }
+
+    println!("polymath repl, type :help for a list of commands");
+
+    loop {
+        let line = match editor.readline("> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(error) => return Err(error),
+        };
+
+        if line.trim().is_empty() {
+            continue;
+        }
+        editor.add_history_entry(line.as_str())?;
+
+        if !session.handle(&line, &mut io::stdout().lock())? {
+            break;
+        }
+    }
+
+    if let Some(history) = &history {
+        editor.save_history(history)?;
+    }
+
+    Ok(())
+}
+
+fn history_path() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".polymath_history"))
+}
+
+fn print_stages(formula: &str, stages: &[Stage], out: &mut impl Write) -> io::Result<()> {
+    let tokens = tokenize(formula);
+    let cst = parse(&tokens);
+    let ast = transform(convert(&cst));
+
+    // keep the pipeline order regardless of the order stages were enabled in
+    for stage in Stage::ALL
+        .into_iter()
+        .filter(|stage| stages.contains(stage))
+    {
+        if stages.len() > 1 {
+            writeln!(out, "-- {}", stage.name())?;
+        }
+
+        match stage {
+            Stage::Tokens => writeln!(out, "{tokens:#?}")?,
+            Stage::Cst => writeln!(out, "{cst:#?}")?,
+            Stage::Ast => writeln!(out, "{ast:#?}")?,
+            Stage::MathMl => writeln!(out, "{}", to_mathml(&ast))?,
+        }
+    }
+
+    for diagnostic in diagnose(formula) {
+        writeln!(
+            out,
+            "{}..{}: {}",
+            diagnostic.span.start, diagnostic.span.end, diagnostic.message
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::Session;
+
+    ///
+    /// Feeds `script` line by line, returns the output and whether the
+    /// session ended.
+    ///
+    fn session(script: &str) -> (String, bool) {
+        let mut session = Session::default();
+        let mut out = Vec::new();
+
+        for line in script.lines() {
+            if !session.handle(line, &mut out).unwrap() {
+                return (String::from_utf8(out).unwrap(), true);
+            }
+        }
+
+        (String::from_utf8(out).unwrap(), false)
+    }
+
+    #[test]
+    fn test_formulas() {
+        assert_eq!(
+            session("a/b"),
+            (
+                "<math display=\"block\"><mfrac><mi>a</mi><mi>b</mi></mfrac></math>\n".to_string(),
+                false
+            )
+        );
+        assert_eq!(
+            session("(a").0,
+            "<math display=\"block\"><mi>(</mi><mi>a</mi></math>\n0..1: unclosed bracket `(`\n"
+        );
+    }
+
+    #[test]
+    fn test_commands() {
+        let (out, ended) = session(":mathml\nx\n:tokens\n:mathml\nx\n:mathml y\n:nope\n:q\nz");
+
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], "mathml off");
+        assert_eq!(lines[1], "tokens on");
+        assert_eq!(lines[2], "mathml on");
+        assert_eq!(lines[3], "-- tokens");
+        assert!(out.contains("-- mathml\n<math display=\"block\"><mi>x</mi></math>\n"));
+        assert!(out.contains("\n<math display=\"block\"><mi>y</mi></math>\n"));
+        assert!(out.ends_with("unknown command `:nope`, type :help for a list of commands\n"));
+        assert!(ended);
+    }
+
+    #[test]
+    fn test_help() {
+        assert!(session(":help").0.starts_with(":tokens [formula]"));
+    }
+}
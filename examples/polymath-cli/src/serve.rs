@@ -0,0 +1,386 @@
+//!
+//! # Serve
+//!
+//! Minimal HTTP rendering service bound to localhost.
+//!
+//! - `POST /render?format=mathml&display=inline` renders the AsciiMath body,
+//!   formats are `mathml`, `latex` and `text`
+//! - `POST /render/batch` renders a JSON array of formulas, each either a
+//!   string or an object `{ "asciimath": "...", "format": ..., "display": ... }`
+//! - `GET /health` answers `ok`
+//!
+
+use std::io::Read;
+
+use clap::ValueEnum;
+use polymath_rs::{
+    diagnose,
+    mathml::{Display, Options},
+    tokens::{tokenize, types::TokenType, Token},
+};
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Server};
+
+use crate::batch::Format;
+
+pub struct Limits {
+    /// Maximum size of a request body in bytes
+    pub max_body: usize,
+    /// Maximum number of tokens of a single formula, the parser recurses
+    /// once per expression of a sequence
+    pub max_tokens: usize,
+    /// Maximum nesting of brackets and operators of a single formula
+    pub max_depth: usize,
+}
+
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Response {
+    fn ok(content_type: &'static str, body: String) -> Self {
+        Self {
+            status: 200,
+            content_type,
+            body,
+        }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: message.into(),
+        }
+    }
+}
+
+pub fn run(port: u16, limits: &Limits) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let server = Server::http(("127.0.0.1", port))?;
+    eprintln!("listening on http://{}", server.server_addr());
+
+    for request in server.incoming_requests() {
+        // clients going away mid-response must not stop the server
+        if let Err(error) = respond(request, limits) {
+            eprintln!("error: {error}");
+        }
+    }
+
+    Ok(())
+}
+
+fn respond(mut request: Request, limits: &Limits) -> std::io::Result<()> {
+    let response = match request.body_length() {
+        Some(length) if length > limits.max_body => Response::error(413, "request body too large"),
+        _ => {
+            let mut body = Vec::new();
+            let read = request
+                .as_reader()
+                .take(limits.max_body as u64 + 1)
+                .read_to_end(&mut body);
+
+            match (read, String::from_utf8(body)) {
+                (Err(error), _) => Response::error(400, error.to_string()),
+                (_, Err(_)) => Response::error(400, "request body is not valid utf-8"),
+                (Ok(_), Ok(body)) if body.len() > limits.max_body => {
+                    Response::error(413, "request body too large")
+                }
+                (Ok(_), Ok(body)) => handle(request.method(), request.url(), &body, limits),
+            }
+        }
+    };
+
+    let content_type = Header::from_bytes("Content-Type", response.content_type)
+        .expect("content types are valid header values");
+
+    request.respond(
+        tiny_http::Response::from_string(response.body)
+            .with_status_code(response.status)
+            .with_header(content_type),
+    )
+}
+
+pub fn handle(method: &Method, url: &str, body: &str, limits: &Limits) -> Response {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+
+    match (method, path) {
+        (Method::Get, "/health") => Response::ok("text/plain; charset=utf-8", "ok".to_string()),
+        (Method::Post, "/render") => {
+            let mut format = Format::MathMl;
+            let mut display = Display::default();
+
+            for (key, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+                let parsed = percent_decode(key)
+                    .and_then(|key| Ok((key, percent_decode(value)?)))
+                    .and_then(|(key, value)| match key.as_str() {
+                        "format" => parse_format(&value).map(|value| format = value),
+                        "display" => parse_display(&value).map(|value| display = value),
+                        _ => Err(format!("unknown parameter `{key}`")),
+                    });
+
+                if let Err(message) = parsed {
+                    return Response::error(400, message);
+                }
+            }
+
+            match render(body, format, display, limits) {
+                Ok(output) => Response::ok(format.content_type(), output),
+                Err(response) => response,
+            }
+        }
+        (Method::Post, "/render/batch") => match serde_json::from_str(body) {
+            Ok(Value::Array(formulas)) => {
+                let results = formulas
+                    .iter()
+                    .map(|formula| render_record(formula, limits))
+                    .collect::<Vec<_>>();
+                Response::ok("application/json", Value::Array(results).to_string())
+            }
+            Ok(_) => Response::error(400, "expected a JSON array of formulas"),
+            Err(error) => Response::error(400, format!("invalid JSON: {error}")),
+        },
+        (_, "/health" | "/render" | "/render/batch") => Response::error(405, "method not allowed"),
+        _ => Response::error(404, "not found"),
+    }
+}
+
+fn render_record(record: &Value, limits: &Limits) -> Value {
+    let option = |key: &str| record.get(key).and_then(Value::as_str);
+
+    let rendered = match record.as_str().or_else(|| option("asciimath")) {
+        Some(asciimath) => option("format")
+            .map_or(Ok(Format::MathMl), parse_format)
+            .and_then(|format| {
+                let display = option("display").map_or(Ok(Display::default()), parse_display)?;
+                render(asciimath, format, display, limits).map_err(|response| response.body)
+            })
+            .map(|output| (asciimath, output)),
+        None => Err("expected a string or an object with an `asciimath` string".to_string()),
+    };
+
+    match rendered {
+        Ok((asciimath, output)) => json!({
+            "output": output,
+            "diagnostics": diagnose(asciimath)
+                .into_iter()
+                .map(|diagnostic| json!({
+                    "start": diagnostic.span.start,
+                    "end": diagnostic.span.end,
+                    "message": diagnostic.message,
+                }))
+                .collect::<Vec<_>>(),
+        }),
+        Err(message) => json!({ "error": message }),
+    }
+}
+
+///
+/// Renders `asciimath` if it is within the limits, the error response
+/// otherwise.
+///
+fn render(
+    asciimath: &str,
+    format: Format,
+    display: Display,
+    limits: &Limits,
+) -> Result<String, Response> {
+    let tokens = tokenize(asciimath);
+    if tokens.len() > limits.max_tokens {
+        return Err(Response::error(
+            413,
+            format!(
+                "formula has {} tokens, at most {} are allowed",
+                tokens.len(),
+                limits.max_tokens
+            ),
+        ));
+    }
+
+    let depth = depth(&tokens);
+    if depth > limits.max_depth {
+        return Err(Response::error(
+            422,
+            format!(
+                "formula is nested {depth} levels deep, at most {} are allowed",
+                limits.max_depth
+            ),
+        ));
+    }
+
+    Ok(polymath_rs::render(
+        asciimath,
+        format.into(),
+        &Options {
+            display,
+            ..Default::default()
+        },
+    ))
+}
+
+///
+/// Estimates how deep the parser has to recurse: open brackets plus chains
+/// of operators taking the following expression as an argument.
+///
+fn depth(tokens: &[Token]) -> usize {
+    let mut brackets = 0usize;
+    let mut operators = 0;
+    let mut max = 0;
+
+    for token in tokens {
+        match token.token_type {
+            TokenType::LBrace(_) => brackets += 1,
+            TokenType::RBrace(_) => brackets = brackets.saturating_sub(1),
+            TokenType::UnaryOperator(_)
+            | TokenType::BinaryOperator(_)
+            | TokenType::Division
+            | TokenType::Underscorce
+            | TokenType::Hat => operators += 1,
+            _ => operators = 0,
+        }
+        max = max.max(brackets + operators);
+    }
+
+    max
+}
+
+///
+/// Decodes `%XX` escapes and `+` for spaces of a query component.
+///
+fn percent_decode(component: &str) -> Result<String, String> {
+    let invalid = || format!("invalid percent encoding in `{component}`");
+    let mut bytes = Vec::with_capacity(component.len());
+    let mut rest = component.bytes();
+
+    while let Some(byte) = rest.next() {
+        match byte {
+            b'%' => {
+                let hex = [rest.next(), rest.next()];
+                let hex = hex
+                    .iter()
+                    .map(|digit| digit.and_then(|digit| (digit as char).to_digit(16)))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(invalid)?;
+                bytes.push((hex[0] * 16 + hex[1]) as u8);
+            }
+            b'+' => bytes.push(b' '),
+            byte => bytes.push(byte),
+        }
+    }
+
+    String::from_utf8(bytes).map_err(|_| invalid())
+}
+
+fn parse_format(value: &str) -> Result<Format, String> {
+    Format::from_str(value, true).map_err(|_| format!("unknown format `{value}`"))
+}
+
+fn parse_display(value: &str) -> Result<Display, String> {
+    match value {
+        "inline" => Ok(Display::Inline),
+        "block" => Ok(Display::Block),
+        _ => Err(format!("unknown display `{value}`")),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tiny_http::Method;
+
+    use super::{handle, Limits};
+
+    const LIMITS: Limits = Limits {
+        max_body: 64,
+        max_tokens: 16,
+        max_depth: 4,
+    };
+
+    #[test]
+    fn test_render() {
+        let response = handle(&Method::Post, "/render?display=inline", "a/b", &LIMITS);
+
+        assert_eq!(response.status, 200);
+        assert_eq!(
+            response.body,
+            "<math display=\"inline\"><mfrac><mi>a</mi><mi>b</mi></mfrac></math>"
+        );
+
+        let response = handle(&Method::Post, "/render?format=latex", "a/b", &LIMITS);
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, "\\frac{a}{b}");
+
+        let response = handle(
+            &Method::Post,
+            "/render?format=la%74ex&display=in%6Cine",
+            "a/b",
+            &LIMITS,
+        );
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, "\\frac{a}{b}");
+
+        let response = handle(&Method::Post, "/render?format=png", "a", &LIMITS);
+        assert_eq!(response.status, 400);
+
+        let response = handle(&Method::Post, "/render?format=%zz", "a", &LIMITS);
+        assert_eq!(response.status, 400);
+
+        let response = handle(&Method::Post, "/render", "(((((a)))))", &LIMITS);
+        assert_eq!(response.status, 422);
+
+        let response = handle(
+            &Method::Post,
+            "/render",
+            "sqrt sqrt sqrt sqrt sqrt a",
+            &LIMITS,
+        );
+        assert_eq!(response.status, 422);
+    }
+
+    #[test]
+    fn test_long_formula() {
+        let limits = Limits {
+            max_body: 64 * 1024,
+            ..LIMITS
+        };
+
+        // flat formulas make the parser recurse once per element
+        let response = handle(&Method::Post, "/render", &"a ".repeat(15_000), &limits);
+        assert_eq!(response.status, 413);
+
+        let response = handle(
+            &Method::Post,
+            "/render/batch",
+            &format!("[{:?}]", "a+".repeat(10)),
+            &limits,
+        );
+        assert_eq!(response.status, 200);
+        assert_eq!(
+            response.body,
+            r#"[{"error":"formula has 20 tokens, at most 16 are allowed"}]"#
+        );
+    }
+
+    #[test]
+    fn test_batch() {
+        let response = handle(
+            &Method::Post,
+            "/render/batch",
+            r#"["x", {"asciimath": "(y", "display": "inline"}, 1]"#,
+            &LIMITS,
+        );
+
+        assert_eq!(response.status, 200);
+        assert_eq!(
+            response.body,
+            r#"[{"diagnostics":[],"output":"<math display=\"block\"><mi>x</mi></math>"},{"diagnostics":[{"end":1,"message":"unclosed bracket `(`","start":0}],"output":"<math display=\"inline\"><mi>(</mi><mi>y</mi></math>"},{"error":"expected a string or an object with an `asciimath` string"}]"#
+        );
+    }
+
+    #[test]
+    fn test_routes() {
+        assert_eq!(handle(&Method::Get, "/health", "", &LIMITS).status, 200);
+        assert_eq!(handle(&Method::Get, "/render", "", &LIMITS).status, 405);
+        assert_eq!(handle(&Method::Get, "/", "", &LIMITS).status, 404);
+    }
+}
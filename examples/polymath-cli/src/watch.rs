@@ -0,0 +1,227 @@
+//!
+//! # Watch
+//!
+//! Re-renders files whenever they change. Directories are watched
+//! recursively for `.amath` files, files given explicitly are watched
+//! regardless of their extension. Output is written next to the source,
+//! `formulas.amath` becomes `formulas.mathml` or `formulas.html`.
+//!
+
+use std::{
+    collections::BTreeSet,
+    fs, io, iter,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+
+use notify::{RecursiveMode, Watcher};
+use polymath_rs::{
+    diagnose,
+    diagnostics::line_column,
+    document::{self, Options},
+    to_math_ml,
+};
+
+use crate::html;
+
+pub const EXTENSION: &str = "amath";
+
+/// Editors tend to write files in several steps
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+pub struct Watch {
+    pub paths: Vec<PathBuf>,
+    pub html: bool,
+    pub document: bool,
+}
+
+impl Watch {
+    pub fn run(&self) -> notify::Result<()> {
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+
+        for path in &self.paths {
+            watcher.watch(path, RecursiveMode::Recursive)?;
+            self.sources(path)?
+                .iter()
+                .for_each(|source| self.render(source));
+        }
+
+        eprintln!("watching for changes, press Ctrl-C to stop");
+
+        while let Ok(event) = receiver.recv() {
+            let debounced = iter::from_fn(|| receiver.recv_timeout(DEBOUNCE).ok());
+            self.rebuild(iter::once(event).chain(debounced));
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// Renders every source the events created or modified once.
+    ///
+    fn rebuild(&self, events: impl IntoIterator<Item = notify::Result<notify::Event>>) {
+        let mut changed = BTreeSet::new();
+
+        for event in events {
+            match event {
+                Ok(event) if event.kind.is_create() || event.kind.is_modify() => {
+                    changed.extend(event.paths.into_iter().filter(|path| self.is_source(path)))
+                }
+                Ok(_) => {}
+                Err(error) => eprintln!("error: {error}"),
+            }
+        }
+
+        changed.iter().for_each(|source| self.render(source));
+    }
+
+    fn is_source(&self, path: &Path) -> bool {
+        path.is_file()
+            && (path
+                .extension()
+                .is_some_and(|extension| extension == EXTENSION)
+                || self.paths.iter().any(|watched| {
+                    watched.is_file() && watched.canonicalize().ok() == path.canonicalize().ok()
+                }))
+    }
+
+    fn sources(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        if path.is_file() {
+            return Ok(vec![path.to_path_buf()]);
+        }
+
+        let mut sources = Vec::new();
+        for entry in fs::read_dir(path)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                sources.extend(self.sources(&path)?);
+            } else if self.is_source(&path) {
+                sources.push(path);
+            }
+        }
+        Ok(sources)
+    }
+
+    fn render(&self, source: &Path) {
+        let text = match fs::read_to_string(source) {
+            Ok(text) => text,
+            Err(error) => {
+                eprintln!("{}: {error}", source.display());
+                return;
+            }
+        };
+
+        let (output, mut diagnostics) = if self.document {
+            let document = document::convert(&text, &Options::default());
+            let diagnostics = document
+                .diagnostics
+                .iter()
+                .chain(document.formulas.iter().flat_map(|f| &f.diagnostics))
+                .map(|diagnostic| {
+                    let (line, column) = line_column(&text, diagnostic.span.start);
+                    (line, column, diagnostic.message.clone())
+                })
+                .collect::<Vec<_>>();
+
+            (document.text, diagnostics)
+        } else {
+            let formulas = text.lines().collect::<Vec<_>>();
+            let diagnostics = formulas
+                .iter()
+                .enumerate()
+                .flat_map(|(index, formula)| {
+                    diagnose(formula).into_iter().map(move |diagnostic| {
+                        let (_, column) = line_column(formula, diagnostic.span.start);
+                        (index + 1, column, diagnostic.message)
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let output = if self.html {
+                let formulas = formulas
+                    .iter()
+                    .filter(|formula| !formula.trim().is_empty())
+                    .map(|formula| formula.to_string())
+                    .collect::<Vec<_>>();
+                html::page(&source.display().to_string(), &formulas, None)
+            } else {
+                formulas
+                    .iter()
+                    .map(|formula| to_math_ml(formula) + "\n")
+                    .collect()
+            };
+
+            (output, diagnostics)
+        };
+
+        diagnostics.sort();
+        for (line, column, message) in &diagnostics {
+            eprintln!("{}:{line}:{column}: {message}", source.display());
+        }
+
+        // documents keep their markup, so they are always written as html
+        let extension = if self.html || self.document {
+            "html"
+        } else {
+            "mathml"
+        };
+        let output_path = source.with_extension(extension);
+
+        if output_path == source {
+            eprintln!("{}: would overwrite its own source", source.display());
+            return;
+        }
+
+        match fs::write(&output_path, output) {
+            Ok(()) => eprintln!(
+                "{} -> {} ({} diagnostics)",
+                source.display(),
+                output_path.display(),
+                diagnostics.len()
+            ),
+            Err(error) => eprintln!("{}: {error}", output_path.display()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs, process};
+
+    use notify::{
+        event::{DataChange, ModifyKind},
+        Event, EventKind,
+    };
+
+    use super::Watch;
+
+    #[test]
+    fn test_rebuild() {
+        let dir = env::temp_dir().join(format!("polymath-watch-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("formulas.amath");
+        let ignored = dir.join("notes.txt");
+        fs::write(&source, "a/b\n").unwrap();
+        fs::write(&ignored, "c").unwrap();
+
+        let watch = Watch {
+            paths: vec![dir.clone()],
+            html: false,
+            document: false,
+        };
+        let change = Event::new(EventKind::Modify(ModifyKind::Data(DataChange::Content)))
+            .add_path(source.clone())
+            .add_path(ignored.clone());
+        watch.rebuild([Ok(change)]);
+
+        assert_eq!(
+            fs::read_to_string(dir.join("formulas.mathml")).unwrap(),
+            "<math display=\"block\"><mfrac><mi>a</mi><mi>b</mi></mfrac></math>\n"
+        );
+        assert!(!dir.join("notes.mathml").exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,203 @@
+#ifndef POLYMATH_H
+#define POLYMATH_H
+
+/* Generated by cbindgen from polymath-c with `cargo xtask polymath-c header`, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum PolymathError {
+  POLYMATH_ERROR_OK = 0,
+  // A required pointer argument was null
+  POLYMATH_ERROR_NULL_POINTER = 1,
+  // The input is not valid UTF-8
+  POLYMATH_ERROR_INVALID_UTF8 = 2,
+  // The conversion panicked, this is a bug in polymath
+  POLYMATH_ERROR_PANIC = 3,
+  // A diagnostic index was not below the diagnostics count
+  POLYMATH_ERROR_INDEX_OUT_OF_BOUNDS = 4,
+  // An enumeration argument has none of its values
+  POLYMATH_ERROR_INVALID_ARGUMENT = 5,
+} PolymathError;
+
+typedef enum PolymathFormat {
+  POLYMATH_FORMAT_MATH_ML = 0,
+  POLYMATH_FORMAT_LATEX = 1,
+  // Plain Unicode text
+  POLYMATH_FORMAT_TEXT = 2,
+} PolymathFormat;
+
+typedef enum PolymathDisplay {
+  POLYMATH_DISPLAY_BLOCK = 0,
+  POLYMATH_DISPLAY_INLINE = 1,
+} PolymathDisplay;
+
+typedef struct PolymathDiagnostics PolymathDiagnostics;
+
+typedef struct PolymathOptions PolymathOptions;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+//
+// Human readable description of `error`. The returned string is static and
+// must not be freed.
+//
+const char *polymath_error_message(enum PolymathError error);
+
+//
+// Converts the `len` bytes at `input` to `format`, a [PolymathFormat]
+// value, and stores the NUL terminated result in `output`, its length
+// without the terminator in `output_len` unless that is null. `options`
+// may be null to use the defaults. The input does not need to be NUL
+// terminated and may contain NUL bytes, which XML cannot represent, they
+// are rendered as U+FFFD. On error `output` is set to null.
+//
+// # Safety
+//
+// `input` must point to at least `len` readable bytes, `options` must be
+// null or a live handle, `output` and `output_len` must be valid for
+// writes. The string stored in `output` must be released with
+// [polymath_free_string].
+//
+enum PolymathError polymath_render(const char *input,
+                                   size_t len,
+                                   uint32_t format,
+                                   const struct PolymathOptions *options,
+                                   char **output,
+                                   size_t *output_len);
+
+//
+// Converts the `len` bytes at `input` to MathML with the default options,
+// see [polymath_render].
+//
+// # Safety
+//
+// `input` must point to at least `len` readable bytes, `output` must be
+// valid for writes. The string stored in `output` must be released with
+// [polymath_free_string].
+//
+enum PolymathError polymath_to_math_ml(const char *input, size_t len, char **output);
+
+//
+// Releases a string returned by this library, null is ignored.
+//
+// # Safety
+//
+// `string` must be null or a pointer returned by this library that has not
+// been freed yet.
+//
+void polymath_free_string(char *string);
+
+//
+// Converts the NUL terminated `input` to MathML, returns null on error.
+// Prefer [polymath_to_math_ml], which reports why a conversion failed.
+//
+// # Safety
+//
+// `input` must be null or a NUL terminated string. The result must be
+// released with [polymath_free_string].
+//
+char *to_math_ml(const char *input);
+
+//
+// Checks the `len` bytes at `input` and stores the diagnostics in
+// `output`, on error it is set to null. Release them with
+// [polymath_diagnostics_free].
+//
+// # Safety
+//
+// `input` must point to at least `len` readable bytes, `output` must be
+// valid for writes.
+//
+enum PolymathError polymath_diagnose(const char *input,
+                                     size_t len,
+                                     struct PolymathDiagnostics **output);
+
+//
+// Number of diagnostics, 0 for null.
+//
+// # Safety
+//
+// `diagnostics` must be null or a live handle.
+//
+size_t polymath_diagnostics_count(const struct PolymathDiagnostics *diagnostics);
+
+//
+// Stores the byte range of the diagnostic at `index` in `start` and `end`.
+//
+// # Safety
+//
+// `diagnostics` must be null or a live handle, `start` and `end` must be
+// valid for writes.
+//
+enum PolymathError polymath_diagnostics_span(const struct PolymathDiagnostics *diagnostics,
+                                             size_t index,
+                                             size_t *start,
+                                             size_t *end);
+
+//
+// Message of the diagnostic at `index`, null if there is none. The string
+// is owned by `diagnostics` and lives as long as the handle.
+//
+// # Safety
+//
+// `diagnostics` must be null or a live handle.
+//
+const char *polymath_diagnostics_message(const struct PolymathDiagnostics *diagnostics,
+                                         size_t index);
+
+//
+// Releases diagnostics created by [polymath_diagnose], null is ignored.
+//
+// # Safety
+//
+// `diagnostics` must be null or a handle that has not been freed yet.
+//
+void polymath_diagnostics_free(struct PolymathDiagnostics *diagnostics);
+
+//
+// Creates options with the defaults, block display without source
+// positions. Release them with [polymath_options_free].
+//
+struct PolymathOptions *polymath_options_new(void);
+
+//
+// Releases options created by [polymath_options_new], null is ignored.
+//
+// # Safety
+//
+// `options` must be null or a handle that has not been freed yet.
+//
+void polymath_options_free(struct PolymathOptions *options);
+
+//
+// Whether MathML is rendered as a block or inline with surrounding text,
+// `display` is a [PolymathDisplay] value.
+//
+// # Safety
+//
+// `options` must be null or a live handle.
+//
+enum PolymathError polymath_options_set_display(struct PolymathOptions *options, uint32_t display);
+
+//
+// Whether MathML elements carry the byte range of their source in
+// `data-src-start` and `data-src-end`.
+//
+// # Safety
+//
+// `options` must be null or a live handle.
+//
+enum PolymathError polymath_options_set_source_positions(struct PolymathOptions *options,
+                                                         bool source_positions);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* POLYMATH_H */
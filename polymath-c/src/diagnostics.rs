@@ -0,0 +1,138 @@
+//!
+//! # Diagnostics
+//!
+//! Opaque list of the problems found in a formula, created with
+//! [polymath_diagnose]. Spans are byte offsets into the input.
+//!
+
+use std::{
+    ffi::{c_char, CString},
+    ops::Range,
+    ptr,
+};
+
+use polymath_rs::diagnose;
+
+use crate::{c_string, guard, input_str, PolymathError};
+
+pub struct PolymathDiagnostics(Vec<(Range<usize>, CString)>);
+
+///
+/// Checks the `len` bytes at `input` and stores the diagnostics in
+/// `output`, on error it is set to null. Release them with
+/// [polymath_diagnostics_free].
+///
+/// # Safety
+///
+/// `input` must point to at least `len` readable bytes, `output` must be
+/// valid for writes.
+///
+#[no_mangle]
+pub unsafe extern "C" fn polymath_diagnose(
+    input: *const c_char,
+    len: usize,
+    output: *mut *mut PolymathDiagnostics,
+) -> PolymathError {
+    if output.is_null() {
+        return PolymathError::NullPointer;
+    }
+    *output = ptr::null_mut();
+
+    let input = match input_str(input, len) {
+        Ok(input) => input,
+        Err(error) => return error,
+    };
+
+    match guard(|| diagnose(input)) {
+        Ok(diagnostics) => {
+            let diagnostics = diagnostics
+                .into_iter()
+                .map(|diagnostic| (diagnostic.span, c_string(diagnostic.message)))
+                .collect();
+            *output = Box::into_raw(Box::new(PolymathDiagnostics(diagnostics)));
+            PolymathError::Ok
+        }
+        Err(error) => error,
+    }
+}
+
+///
+/// Number of diagnostics, 0 for null.
+///
+/// # Safety
+///
+/// `diagnostics` must be null or a live handle.
+///
+#[no_mangle]
+pub unsafe extern "C" fn polymath_diagnostics_count(
+    diagnostics: *const PolymathDiagnostics,
+) -> usize {
+    diagnostics
+        .as_ref()
+        .map_or(0, |diagnostics| diagnostics.0.len())
+}
+
+///
+/// Stores the byte range of the diagnostic at `index` in `start` and `end`.
+///
+/// # Safety
+///
+/// `diagnostics` must be null or a live handle, `start` and `end` must be
+/// valid for writes.
+///
+#[no_mangle]
+pub unsafe extern "C" fn polymath_diagnostics_span(
+    diagnostics: *const PolymathDiagnostics,
+    index: usize,
+    start: *mut usize,
+    end: *mut usize,
+) -> PolymathError {
+    let Some(diagnostics) = diagnostics.as_ref() else {
+        return PolymathError::NullPointer;
+    };
+    if start.is_null() || end.is_null() {
+        return PolymathError::NullPointer;
+    }
+
+    match diagnostics.0.get(index) {
+        Some((span, _)) => {
+            *start = span.start;
+            *end = span.end;
+            PolymathError::Ok
+        }
+        None => PolymathError::IndexOutOfBounds,
+    }
+}
+
+///
+/// Message of the diagnostic at `index`, null if there is none. The string
+/// is owned by `diagnostics` and lives as long as the handle.
+///
+/// # Safety
+///
+/// `diagnostics` must be null or a live handle.
+///
+#[no_mangle]
+pub unsafe extern "C" fn polymath_diagnostics_message(
+    diagnostics: *const PolymathDiagnostics,
+    index: usize,
+) -> *const c_char {
+    diagnostics
+        .as_ref()
+        .and_then(|diagnostics| diagnostics.0.get(index))
+        .map_or(ptr::null(), |(_, message)| message.as_ptr())
+}
+
+///
+/// Releases diagnostics created by [polymath_diagnose], null is ignored.
+///
+/// # Safety
+///
+/// `diagnostics` must be null or a handle that has not been freed yet.
+///
+#[no_mangle]
+pub unsafe extern "C" fn polymath_diagnostics_free(diagnostics: *mut PolymathDiagnostics) {
+    if !diagnostics.is_null() {
+        drop(Box::from_raw(diagnostics));
+    }
+}
@@ -1,47 +1,395 @@
-use std::ffi::{CStr, CString};
+//!
+//! # polymath-c
+//!
+//! C bindings, the header `polymath.h` is generated by `cargo xtask polymath-c header`.
+//! Strings returned by the library are owned by the caller and have to be
+//! released with [polymath_free_string], handles with their own `_free`
+//! function.
+//!
 
+mod diagnostics;
+mod options;
+
+use std::{
+    ffi::{c_char, CStr, CString},
+    panic::{self, UnwindSafe},
+    ptr, slice,
+};
+
+use polymath_rs::{mathml, Format};
+
+pub use diagnostics::*;
+pub use options::*;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolymathError {
+    Ok = 0,
+    /// A required pointer argument was null
+    NullPointer = 1,
+    /// The input is not valid UTF-8
+    InvalidUtf8 = 2,
+    /// The conversion panicked, this is a bug in polymath
+    Panic = 3,
+    /// A diagnostic index was not below the diagnostics count
+    IndexOutOfBounds = 4,
+    /// An enumeration argument has none of its values
+    InvalidArgument = 5,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolymathFormat {
+    MathMl = 0,
+    Latex = 1,
+    /// Plain Unicode text
+    Text = 2,
+}
+
+impl PolymathFormat {
+    ///
+    /// The format for a value passed in from C, which may be any integer.
+    ///
+    fn from_raw(format: u32) -> Option<Format> {
+        match format {
+            format if format == PolymathFormat::MathMl as u32 => Some(Format::MathMl),
+            format if format == PolymathFormat::Latex as u32 => Some(Format::Latex),
+            format if format == PolymathFormat::Text as u32 => Some(Format::Text),
+            _ => None,
+        }
+    }
+}
+
+impl PolymathError {
+    fn message(self) -> &'static CStr {
+        match self {
+            PolymathError::Ok => c"no error",
+            PolymathError::NullPointer => c"a required pointer argument was null",
+            PolymathError::InvalidUtf8 => c"input is not valid UTF-8",
+            PolymathError::Panic => c"conversion panicked",
+            PolymathError::IndexOutOfBounds => c"diagnostic index out of bounds",
+            PolymathError::InvalidArgument => c"invalid enumeration value",
+        }
+    }
+}
+
+///
+/// Human readable description of `error`. The returned string is static and
+/// must not be freed.
+///
+#[no_mangle]
+pub extern "C" fn polymath_error_message(error: PolymathError) -> *const c_char {
+    error.message().as_ptr()
+}
+
+///
+/// Converts the `len` bytes at `input` to `format`, a [PolymathFormat]
+/// value, and stores the NUL terminated result in `output`, its length
+/// without the terminator in `output_len` unless that is null. `options`
+/// may be null to use the defaults. The input does not need to be NUL
+/// terminated and may contain NUL bytes, which XML cannot represent, they
+/// are rendered as U+FFFD. On error `output` is set to null.
 ///
 /// # Safety
 ///
+/// `input` must point to at least `len` readable bytes, `options` must be
+/// null or a live handle, `output` and `output_len` must be valid for
+/// writes. The string stored in `output` must be released with
+/// [polymath_free_string].
+///
 #[no_mangle]
-pub unsafe extern "C" fn to_math_ml(input: *const u8) -> *const u8 {
-    if !input.is_null() {
-        let c_string = CStr::from_ptr(input as *const i8).to_str();
+pub unsafe extern "C" fn polymath_render(
+    input: *const c_char,
+    len: usize,
+    format: u32,
+    options: *const PolymathOptions,
+    output: *mut *mut c_char,
+    output_len: *mut usize,
+) -> PolymathError {
+    if output.is_null() {
+        return PolymathError::NullPointer;
+    }
+    *output = ptr::null_mut();
 
-        if let Ok(c_string) = c_string {
-            let mathml = polymath_rs::to_math_ml(c_string);
+    let Some(format) = PolymathFormat::from_raw(format) else {
+        return PolymathError::InvalidArgument;
+    };
+    let input = match input_str(input, len) {
+        Ok(input) => input,
+        Err(error) => return error,
+    };
+    let options = options
+        .as_ref()
+        .map_or_else(mathml::Options::default, |options| options.0.clone());
 
-            if let Ok(c_string) = CString::new(mathml.as_str()) {
-                c_string.into_raw() as *const u8
-            } else {
-                [0].as_ptr() as *const u8
+    match guard(|| polymath_rs::render(input, format, &options)) {
+        Ok(rendered) => {
+            let rendered = c_string(rendered);
+            if !output_len.is_null() {
+                *output_len = rendered.as_bytes().len();
             }
-        } else {
-            [0].as_ptr() as *const u8
+            *output = rendered.into_raw();
+            PolymathError::Ok
         }
-    } else {
-        [0].as_ptr() as *const u8
+        Err(error) => error,
+    }
+}
+
+///
+/// Converts the `len` bytes at `input` to MathML with the default options,
+/// see [polymath_render].
+///
+/// # Safety
+///
+/// `input` must point to at least `len` readable bytes, `output` must be
+/// valid for writes. The string stored in `output` must be released with
+/// [polymath_free_string].
+///
+#[no_mangle]
+pub unsafe extern "C" fn polymath_to_math_ml(
+    input: *const c_char,
+    len: usize,
+    output: *mut *mut c_char,
+) -> PolymathError {
+    polymath_render(
+        input,
+        len,
+        PolymathFormat::MathMl as u32,
+        ptr::null(),
+        output,
+        ptr::null_mut(),
+    )
+}
+
+///
+/// Releases a string returned by this library, null is ignored.
+///
+/// # Safety
+///
+/// `string` must be null or a pointer returned by this library that has not
+/// been freed yet.
+///
+#[no_mangle]
+pub unsafe extern "C" fn polymath_free_string(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+///
+/// Converts the NUL terminated `input` to MathML, returns null on error.
+/// Prefer [polymath_to_math_ml], which reports why a conversion failed.
+///
+/// # Safety
+///
+/// `input` must be null or a NUL terminated string. The result must be
+/// released with [polymath_free_string].
+///
+#[no_mangle]
+pub unsafe extern "C" fn to_math_ml(input: *const c_char) -> *mut c_char {
+    if input.is_null() {
+        return ptr::null_mut();
     }
+
+    let input = CStr::from_ptr(input);
+    let mut output = ptr::null_mut();
+    polymath_to_math_ml(input.as_ptr(), input.to_bytes().len(), &mut output);
+    output
+}
+
+pub(crate) fn c_string(string: String) -> CString {
+    CString::new(string.replace('\0', "\u{FFFD}")).expect("NUL bytes have been replaced")
+}
+
+///
+/// Runs `f`, turning a panic into an error instead of unwinding into C.
+///
+pub(crate) fn guard<T>(f: impl FnOnce() -> T + UnwindSafe) -> Result<T, PolymathError> {
+    panic::catch_unwind(f).map_err(|_| PolymathError::Panic)
+}
+
+pub(crate) unsafe fn input_str<'a>(
+    input: *const c_char,
+    len: usize,
+) -> Result<&'a str, PolymathError> {
+    if len == 0 {
+        return Ok("");
+    }
+    if input.is_null() {
+        return Err(PolymathError::NullPointer);
+    }
+
+    std::str::from_utf8(slice::from_raw_parts(input as *const u8, len))
+        .map_err(|_| PolymathError::InvalidUtf8)
 }
 
 #[cfg(test)]
 mod test {
-    use std::ffi::CString;
+    use std::{
+        ffi::{c_char, CStr, CString},
+        ptr,
+    };
 
-    use crate::to_math_ml;
+    use crate::{
+        polymath_diagnose, polymath_diagnostics_count, polymath_diagnostics_free,
+        polymath_diagnostics_message, polymath_diagnostics_span, polymath_error_message,
+        polymath_free_string, polymath_options_free, polymath_options_new,
+        polymath_options_set_display, polymath_render, polymath_to_math_ml, to_math_ml,
+        PolymathDisplay, PolymathError, PolymathFormat,
+    };
+
+    unsafe fn convert(input: &[u8]) -> (PolymathError, Option<String>) {
+        let mut output = ptr::null_mut();
+        let error = polymath_to_math_ml(input.as_ptr() as *const c_char, input.len(), &mut output);
+        let mathml = (!output.is_null()).then(|| CStr::from_ptr(output).to_str().unwrap().into());
+        polymath_free_string(output);
+        (error, mathml)
+    }
 
     #[test]
     fn test_expression() {
         unsafe {
-            let mathml = CString::from_raw(to_math_ml(
-                CString::new("i  1 sum_{i 1}^{n} i^{3}")
-                    .unwrap()
-                    .as_bytes()
-                    .as_ptr(),
-            ) as *mut i8);
-
-            println!("{:?}", mathml.to_str().unwrap());
-            println!("{:?}", String::from_utf8(vec![44, 91, 0, 4]).unwrap());
+            let input = CString::new("i  1 sum_{i 1}^{n} i^{3}").unwrap();
+            let mathml = to_math_ml(input.as_ptr());
+
+            assert!(CStr::from_ptr(mathml)
+                .to_str()
+                .unwrap()
+                .starts_with("<math display=\"block\">"));
+            polymath_free_string(mathml);
+
+            assert!(to_math_ml(ptr::null()).is_null());
+        }
+    }
+
+    #[test]
+    fn test_explicit_length() {
+        unsafe {
+            let (error, mathml) = convert(&b"a/b trailing"[..3]);
+            assert_eq!(error, PolymathError::Ok);
+            assert_eq!(
+                mathml.unwrap(),
+                "<math display=\"block\"><mfrac><mi>a</mi><mi>b</mi></mfrac></math>"
+            );
+
+            let (error, mathml) = convert(b"a\0b");
+            assert_eq!(error, PolymathError::Ok);
+            assert_eq!(
+                mathml.unwrap(),
+                "<math display=\"block\"><mi>a</mi><mi>\u{FFFD}</mi><mi>b</mi></math>"
+            );
+
+            let (error, mathml) = convert(b"");
+            assert_eq!(error, PolymathError::Ok);
+            assert!(mathml.is_some());
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        unsafe {
+            assert_eq!(convert(b"\xff").0, PolymathError::InvalidUtf8);
+            assert_eq!(convert(b"\xff").1, None);
+
+            let mut output = ptr::null_mut();
+            assert_eq!(
+                polymath_to_math_ml(ptr::null(), 1, &mut output),
+                PolymathError::NullPointer
+            );
+            assert_eq!(
+                polymath_to_math_ml(c"a".as_ptr(), 1, ptr::null_mut()),
+                PolymathError::NullPointer
+            );
+
+            let message = CStr::from_ptr(polymath_error_message(PolymathError::InvalidUtf8));
+            assert_eq!(message.to_str().unwrap(), "input is not valid UTF-8");
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        unsafe {
+            let options = polymath_options_new();
+            polymath_options_set_display(options, PolymathDisplay::Inline as u32);
+
+            let input = b"x^2";
+            let mut output = ptr::null_mut();
+            let mut output_len = 0;
+            let mut render = |format, options| {
+                let error = polymath_render(
+                    input.as_ptr() as *const c_char,
+                    input.len(),
+                    format,
+                    options,
+                    &mut output,
+                    &mut output_len,
+                );
+                assert_eq!(error, PolymathError::Ok);
+
+                let rendered = CStr::from_ptr(output).to_str().unwrap().to_string();
+                assert_eq!(rendered.len(), output_len);
+                polymath_free_string(output);
+                rendered
+            };
+
+            assert_eq!(
+                render(PolymathFormat::MathMl as u32, options),
+                "<math display=\"inline\"><msup><mi>x</mi><mn>2</mn></msup></math>"
+            );
+            assert_eq!(render(PolymathFormat::Latex as u32, ptr::null()), "x^{2}");
+            assert_eq!(render(PolymathFormat::Text as u32, ptr::null()), "x^2");
+
+            assert_eq!(
+                polymath_render(
+                    input.as_ptr() as *const c_char,
+                    input.len(),
+                    3,
+                    ptr::null(),
+                    &mut output,
+                    ptr::null_mut(),
+                ),
+                PolymathError::InvalidArgument
+            );
+            assert!(output.is_null());
+            assert_eq!(
+                polymath_options_set_display(options, 7),
+                PolymathError::InvalidArgument
+            );
+
+            polymath_options_free(options);
+        }
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        unsafe {
+            let input = b"(a + b/";
+            let mut diagnostics = ptr::null_mut();
+            assert_eq!(
+                polymath_diagnose(
+                    input.as_ptr() as *const c_char,
+                    input.len(),
+                    &mut diagnostics
+                ),
+                PolymathError::Ok
+            );
+            assert_eq!(polymath_diagnostics_count(diagnostics), 2);
+
+            let (mut start, mut end) = (0, 0);
+            polymath_diagnostics_span(diagnostics, 0, &mut start, &mut end);
+            assert_eq!((start, end), (0, 1));
+            assert_eq!(
+                CStr::from_ptr(polymath_diagnostics_message(diagnostics, 0)).to_str(),
+                Ok("unclosed bracket `(`")
+            );
+
+            assert_eq!(
+                polymath_diagnostics_span(diagnostics, 2, &mut start, &mut end),
+                PolymathError::IndexOutOfBounds
+            );
+            assert!(polymath_diagnostics_message(diagnostics, 2).is_null());
+
+            polymath_diagnostics_free(diagnostics);
         }
     }
 }
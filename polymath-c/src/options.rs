@@ -0,0 +1,93 @@
+//!
+//! # Options
+//!
+//! Opaque handle for the rendering options, created with
+//! [polymath_options_new] and configured through setters so the layout can
+//! grow without breaking the ABI.
+//!
+
+use polymath_rs::mathml::{self, Display};
+
+use crate::PolymathError;
+
+pub struct PolymathOptions(pub(crate) mathml::Options);
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolymathDisplay {
+    Block = 0,
+    Inline = 1,
+}
+
+///
+/// Creates options with the defaults, block display without source
+/// positions. Release them with [polymath_options_free].
+///
+#[no_mangle]
+pub extern "C" fn polymath_options_new() -> *mut PolymathOptions {
+    Box::into_raw(Box::new(PolymathOptions(mathml::Options::default())))
+}
+
+///
+/// Releases options created by [polymath_options_new], null is ignored.
+///
+/// # Safety
+///
+/// `options` must be null or a handle that has not been freed yet.
+///
+#[no_mangle]
+pub unsafe extern "C" fn polymath_options_free(options: *mut PolymathOptions) {
+    if !options.is_null() {
+        drop(Box::from_raw(options));
+    }
+}
+
+///
+/// Whether MathML is rendered as a block or inline with surrounding text,
+/// `display` is a [PolymathDisplay] value.
+///
+/// # Safety
+///
+/// `options` must be null or a live handle.
+///
+#[no_mangle]
+pub unsafe extern "C" fn polymath_options_set_display(
+    options: *mut PolymathOptions,
+    display: u32,
+) -> PolymathError {
+    let display = match display {
+        display if display == PolymathDisplay::Block as u32 => Display::Block,
+        display if display == PolymathDisplay::Inline as u32 => Display::Inline,
+        _ => return PolymathError::InvalidArgument,
+    };
+
+    match options.as_mut() {
+        Some(options) => {
+            options.0.display = display;
+            PolymathError::Ok
+        }
+        None => PolymathError::NullPointer,
+    }
+}
+
+///
+/// Whether MathML elements carry the byte range of their source in
+/// `data-src-start` and `data-src-end`.
+///
+/// # Safety
+///
+/// `options` must be null or a live handle.
+///
+#[no_mangle]
+pub unsafe extern "C" fn polymath_options_set_source_positions(
+    options: *mut PolymathOptions,
+    source_positions: bool,
+) -> PolymathError {
+    match options.as_mut() {
+        Some(options) => {
+            options.0.source_positions = source_positions;
+            PolymathError::Ok
+        }
+        None => PolymathError::NullPointer,
+    }
+}
@@ -0,0 +1,38 @@
+package eu.reverseengineer.polymath;
+
+/** A problem in a formula. Offsets are indices into the formula string. */
+public final class Diagnostic {
+  private final int start;
+  private final int end;
+  private final Severity severity;
+  private final String message;
+
+  /** Called from native code. */
+  Diagnostic(int start, int end, String severity, String message) {
+    this.start = start;
+    this.end = end;
+    this.severity = Severity.valueOf(severity);
+    this.message = message;
+  }
+
+  public int getStart() {
+    return start;
+  }
+
+  public int getEnd() {
+    return end;
+  }
+
+  public Severity getSeverity() {
+    return severity;
+  }
+
+  public String getMessage() {
+    return message;
+  }
+
+  @Override
+  public String toString() {
+    return start + ".." + end + ": " + message;
+  }
+}
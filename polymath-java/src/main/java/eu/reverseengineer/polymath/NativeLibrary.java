@@ -0,0 +1,63 @@
+package eu.reverseengineer.polymath;
+
+import java.io.IOException;
+import java.io.InputStream;
+import java.io.UncheckedIOException;
+import java.nio.file.Files;
+import java.nio.file.Path;
+import java.nio.file.StandardCopyOption;
+import java.util.Locale;
+
+/**
+ * Entry points of the polymath-jni library. The library bundled for the current platform is
+ * extracted from the resources, without one it is looked up on {@code java.library.path}.
+ */
+final class NativeLibrary {
+  private static final String NAME = "polymath_jni";
+
+  static {
+    load();
+  }
+
+  private NativeLibrary() {}
+
+  static native String render(String input, int format, boolean inline, boolean sourcePositions);
+
+  static native Diagnostic[] diagnose(String input);
+
+  private static void load() {
+    String fileName = System.mapLibraryName(NAME);
+
+    try (InputStream library =
+        NativeLibrary.class.getResourceAsStream("/" + platform() + "/" + fileName)) {
+      if (library == null) {
+        System.loadLibrary(NAME);
+        return;
+      }
+
+      Path file = Files.createTempFile(NAME, fileName);
+      file.toFile().deleteOnExit();
+      Files.copy(library, file, StandardCopyOption.REPLACE_EXISTING);
+      System.load(file.toAbsolutePath().toString());
+    } catch (IOException e) {
+      throw new UncheckedIOException(e);
+    }
+  }
+
+  /** Resource directory of the current platform, e.g. {@code linux-x86-64}. */
+  private static String platform() {
+    String os = System.getProperty("os.name").toLowerCase(Locale.ROOT);
+    String arch = System.getProperty("os.arch").toLowerCase(Locale.ROOT);
+
+    if (os.startsWith("windows")) {
+      os = "win32";
+    } else if (os.startsWith("mac")) {
+      os = "darwin";
+    }
+    if (arch.equals("amd64") || arch.equals("x86_64")) {
+      arch = "x86-64";
+    }
+
+    return os + "-" + arch;
+  }
+}
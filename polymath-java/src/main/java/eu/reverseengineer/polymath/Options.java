@@ -0,0 +1,33 @@
+package eu.reverseengineer.polymath;
+
+/** Options for the MathML output. */
+public final class Options {
+  private Display display = Display.BLOCK;
+  private boolean sourcePositions = false;
+
+  public static Options defaults() {
+    return new Options();
+  }
+
+  public Display getDisplay() {
+    return display;
+  }
+
+  public Options display(Display display) {
+    this.display = display;
+    return this;
+  }
+
+  public boolean isSourcePositions() {
+    return sourcePositions;
+  }
+
+  /**
+   * Annotate elements with the UTF-8 byte range of their source using {@code data-src-start} and
+   * {@code data-src-end}.
+   */
+  public Options sourcePositions(boolean sourcePositions) {
+    this.sourcePositions = sourcePositions;
+    return this;
+  }
+}
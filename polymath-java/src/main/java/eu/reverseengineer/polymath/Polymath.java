@@ -1,16 +1,14 @@
 package eu.reverseengineer.polymath;
 
-import com.sun.jna.Library;
-import com.sun.jna.Native;
-
-import java.io.IOException;
+import java.util.Arrays;
+import java.util.Collections;
+import java.util.List;
+import java.util.Objects;
 
 public class Polymath {
-  public interface AsciiMathSO extends Library {
-    String to_math_ml(String asciimath);
-  }
-
-  private static AsciiMathSO INSTANCE;
+  private static final int MATHML = 0;
+  private static final int LATEX = 1;
+  private static final int TEXT = 2;
 
   public static Polymath of(String asciimath) {
     return new Polymath(asciimath);
@@ -19,24 +17,29 @@ public class Polymath {
   private final String asciimath;
 
   private Polymath(String asciimath) {
-    this.asciimath = asciimath;
+    this.asciimath = Objects.requireNonNull(asciimath, "asciimath");
   }
 
-  private void init() {
-    try {
-      INSTANCE = Native.load(
-          Native.extractFromResourcePath("polymath_c").getAbsolutePath(),
-          AsciiMathSO.class);
-    } catch (IOException e) {
-      throw new RuntimeException(e);
-    }
+  public String toMathMl() {
+    return toMathMl(Options.defaults());
   }
 
-  public String toMathMl() {
-    if (INSTANCE == null) {
-      init();
-    }
+  public String toMathMl(Options options) {
+    return NativeLibrary.render(
+        asciimath, MATHML, options.getDisplay() == Display.INLINE, options.isSourcePositions());
+  }
+
+  public String toLatex() {
+    return NativeLibrary.render(asciimath, LATEX, false, false);
+  }
+
+  /** Plain Unicode text, e.g. for alt texts. */
+  public String toText() {
+    return NativeLibrary.render(asciimath, TEXT, false, false);
+  }
 
-    return INSTANCE.to_math_ml(asciimath);
+  /** Problems the parser recovered from, ordered by their position. */
+  public List<Diagnostic> diagnostics() {
+    return Collections.unmodifiableList(Arrays.asList(NativeLibrary.diagnose(asciimath)));
   }
 }
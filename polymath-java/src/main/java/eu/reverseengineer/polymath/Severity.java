@@ -0,0 +1,6 @@
+package eu.reverseengineer.polymath;
+
+public enum Severity {
+  ERROR,
+  WARNING,
+}
@@ -1,8 +1,10 @@
 package eu.reverseengineer.polymath;
 
+import java.util.List;
 import java.util.stream.Stream;
 
 import org.assertj.core.api.Assertions;
+import org.junit.jupiter.api.Test;
 import org.junit.jupiter.params.ParameterizedTest;
 import org.junit.jupiter.params.provider.Arguments;
 import org.junit.jupiter.params.provider.MethodSource;
@@ -15,6 +17,32 @@ public class PolymathTest {
     Assertions.assertThat(Polymath.of(asciimath).toMathMl()).isEqualTo(mathml);
   }
 
+  @Test
+  public void test_options() {
+    Options options = Options.defaults().display(Display.INLINE).sourcePositions(true);
+
+    Assertions.assertThat(Polymath.of("x").toMathMl(options))
+        .isEqualTo(
+            "<math display=\"inline\"><mi data-src-start=\"0\" data-src-end=\"1\">x</mi></math>");
+  }
+
+  @Test
+  public void test_formats() {
+    Assertions.assertThat(Polymath.of("a/b").toLatex()).isEqualTo("\\frac{a}{b}");
+    Assertions.assertThat(Polymath.of("sqrt 2").toText()).isEqualTo("\u221A2");
+  }
+
+  @Test
+  public void test_diagnostics() {
+    List<Diagnostic> diagnostics = Polymath.of("\uD835\uDC65 + (b/").diagnostics();
+
+    Assertions.assertThat(diagnostics).hasSize(2);
+    Assertions.assertThat(diagnostics.get(0).getStart()).isEqualTo(5);
+    Assertions.assertThat(diagnostics.get(0).getSeverity()).isEqualTo(Severity.ERROR);
+    Assertions.assertThat(diagnostics.get(0).getMessage()).isEqualTo("unclosed bracket `(`");
+    Assertions.assertThat(Polymath.of("a/b").diagnostics()).isEmpty();
+  }
+
   private static Stream<Arguments> testCases() {
     return Stream.of(
         Arguments.of(
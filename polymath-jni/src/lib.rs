@@ -0,0 +1,165 @@
+//!
+//! # polymath-jni
+//!
+//! JNI bindings backing `eu.reverseengineer.polymath.Polymath`. Results are
+//! returned as Java objects, so the JVM owns all memory handed out. Offsets
+//! of diagnostics count UTF-16 code units like Java string indices.
+//!
+
+use std::panic::{self, UnwindSafe};
+
+use jni::{
+    objects::{JClass, JObject, JString, JValue},
+    sys::{jboolean, jint, jobjectArray, jstring, JNI_TRUE},
+    JNIEnv,
+};
+use polymath_rs::{
+    diagnose,
+    diagnostics::Severity,
+    mathml::{Display, Options},
+    render, Format,
+};
+
+const DIAGNOSTIC_CLASS: &str = "eu/reverseengineer/polymath/Diagnostic";
+const DIAGNOSTIC_CONSTRUCTOR: &str = "(IILjava/lang/String;Ljava/lang/String;)V";
+
+///
+/// `static native String render(String input, int format, boolean inline, boolean sourcePositions)`
+///
+#[no_mangle]
+pub extern "system" fn Java_eu_reverseengineer_polymath_NativeLibrary_render<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    input: JString<'local>,
+    format: jint,
+    inline: jboolean,
+    source_positions: jboolean,
+) -> jstring {
+    let result = (|| -> Result<jstring, Error> {
+        let input: String = env.get_string(&input)?.into();
+        let format = match format {
+            0 => Format::MathMl,
+            1 => Format::Latex,
+            2 => Format::Text,
+            _ => return Err(Error::Argument(format!("unknown format {format}"))),
+        };
+        let options = Options {
+            display: if inline == JNI_TRUE {
+                Display::Inline
+            } else {
+                Display::Block
+            },
+            source_positions: source_positions == JNI_TRUE,
+        };
+
+        let rendered = guard(|| render(&input, format, &options))?;
+        Ok(env.new_string(rendered)?.into_raw())
+    })();
+
+    result.unwrap_or_else(|error| {
+        error.throw(&mut env);
+        std::ptr::null_mut()
+    })
+}
+
+///
+/// `static native Diagnostic[] diagnose(String input)`
+///
+#[no_mangle]
+pub extern "system" fn Java_eu_reverseengineer_polymath_NativeLibrary_diagnose<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    input: JString<'local>,
+) -> jobjectArray {
+    let result = (|| -> Result<jobjectArray, Error> {
+        let input: String = env.get_string(&input)?.into();
+        let diagnostics = guard(|| diagnose(&input))?;
+
+        let array =
+            env.new_object_array(diagnostics.len() as jint, DIAGNOSTIC_CLASS, JObject::null())?;
+
+        for (index, diagnostic) in diagnostics.into_iter().enumerate() {
+            let severity = env.new_string(match diagnostic.severity {
+                Severity::Error => "ERROR",
+                Severity::Warning => "WARNING",
+            })?;
+            let message = env.new_string(diagnostic.message)?;
+            let object = env.new_object(
+                DIAGNOSTIC_CLASS,
+                DIAGNOSTIC_CONSTRUCTOR,
+                &[
+                    JValue::Int(utf16_offset(&input, diagnostic.span.start)),
+                    JValue::Int(utf16_offset(&input, diagnostic.span.end)),
+                    JValue::Object(&severity),
+                    JValue::Object(&message),
+                ],
+            )?;
+            env.set_object_array_element(&array, index as jint, &object)?;
+
+            // only a few local references are guaranteed, release them early
+            env.delete_local_ref(object)?;
+            env.delete_local_ref(message)?;
+            env.delete_local_ref(severity)?;
+        }
+
+        Ok(array.into_raw())
+    })();
+
+    result.unwrap_or_else(|error| {
+        error.throw(&mut env);
+        std::ptr::null_mut()
+    })
+}
+
+enum Error {
+    Jni(jni::errors::Error),
+    Argument(String),
+    Panic,
+}
+
+impl From<jni::errors::Error> for Error {
+    fn from(error: jni::errors::Error) -> Self {
+        Error::Jni(error)
+    }
+}
+
+impl Error {
+    ///
+    /// Raises the error as a Java exception, unless one is pending already.
+    ///
+    fn throw(self, env: &mut JNIEnv) {
+        if env.exception_check().unwrap_or(true) {
+            return;
+        }
+
+        let _ = match self {
+            Error::Jni(error) => env.throw_new("java/lang/RuntimeException", error.to_string()),
+            Error::Argument(message) => {
+                env.throw_new("java/lang/IllegalArgumentException", message)
+            }
+            Error::Panic => env.throw_new("java/lang/IllegalStateException", "polymath panicked"),
+        };
+    }
+}
+
+///
+/// Runs `f`, turning a panic into an error instead of unwinding into the JVM.
+///
+fn guard<T>(f: impl FnOnce() -> T + UnwindSafe) -> Result<T, Error> {
+    panic::catch_unwind(f).map_err(|_| Error::Panic)
+}
+
+fn utf16_offset(text: &str, offset: usize) -> jint {
+    text[..offset.min(text.len())].encode_utf16().count() as jint
+}
+
+#[cfg(test)]
+mod test {
+    use super::utf16_offset;
+
+    #[test]
+    fn test_utf16_offset() {
+        assert_eq!(utf16_offset("a𝑥(b", 5), 3);
+        assert_eq!(utf16_offset("ab", 5), 2);
+    }
+}
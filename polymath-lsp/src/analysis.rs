@@ -0,0 +1,350 @@
+//!
+//! # Analysis
+//!
+//! Everything the server answers, independent of the protocol plumbing.
+//! `.amath` files contain one formula per line, any other document is
+//! scanned for delimited formulas with [polymath_rs::document].
+//!
+
+use std::ops::Range;
+
+use lsp_types::{
+    CompletionItem, CompletionItemKind, Diagnostic, DiagnosticSeverity, Documentation, Hover,
+    HoverContents, MarkupContent, MarkupKind, Position,
+};
+use polymath_rs::{
+    ast::{Expression, Literal},
+    diagnose,
+    diagnostics::{self, Severity},
+    document::{self, Options},
+    mathml::{self, expression_to_mathml, text_content},
+    symbols::{self, Category},
+    to_ast,
+};
+
+pub const EXTENSION: &str = ".amath";
+
+pub struct Document {
+    pub text: String,
+    /// Byte ranges of the formula sources in `text`
+    pub formulas: Vec<Range<usize>>,
+    pub diagnostics: Vec<diagnostics::Diagnostic>,
+}
+
+impl Document {
+    pub fn new(uri: &str, text: String) -> Self {
+        if uri.ends_with(EXTENSION) {
+            let mut formulas = Vec::new();
+            let mut diagnostics = Vec::new();
+            let mut start = 0;
+
+            for line in text.split_inclusive('\n') {
+                let formula = line.trim_end_matches(['\n', '\r']);
+                diagnostics.extend(diagnose(formula).into_iter().map(|diagnostic| {
+                    diagnostics::Diagnostic {
+                        span: start + diagnostic.span.start..start + diagnostic.span.end,
+                        ..diagnostic
+                    }
+                }));
+                formulas.push(start..start + formula.len());
+                start += line.len();
+            }
+
+            Self {
+                text,
+                formulas,
+                diagnostics,
+            }
+        } else {
+            let document = document::convert(&text, &Options::default());
+            let formulas = document
+                .formulas
+                .iter()
+                .map(|formula| formula.content.clone())
+                .collect();
+            let diagnostics = document
+                .diagnostics
+                .into_iter()
+                .chain(document.formulas.into_iter().flat_map(|f| f.diagnostics))
+                .collect();
+
+            Self {
+                text,
+                formulas,
+                diagnostics,
+            }
+        }
+    }
+
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.diagnostics
+            .iter()
+            .map(|diagnostic| Diagnostic {
+                range: self.range(&diagnostic.span),
+                severity: Some(match diagnostic.severity {
+                    Severity::Error => DiagnosticSeverity::ERROR,
+                    Severity::Warning => DiagnosticSeverity::WARNING,
+                }),
+                source: Some("polymath".to_string()),
+                message: diagnostic.message.clone(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    ///
+    /// Shows the expression under the cursor as Unicode text and MathML.
+    ///
+    pub fn hover(&self, position: Position) -> Option<Hover> {
+        let offset = self.offset(position);
+        let formula = self.formula_at(offset)?;
+        let ast = to_ast(&self.text[formula.clone()]);
+        let expression = ast
+            .expressions
+            .expressions
+            .iter()
+            .find_map(|expression| expression_at(expression, offset - formula.start))?;
+
+        let span = expression.span()?;
+        let mathml = format!(
+            "<math>{}</math>",
+            expression_to_mathml(expression, &mathml::Options::default())
+        );
+
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: format!("{}\n\n```xml\n{mathml}\n```", text_content(&mathml)),
+            }),
+            range: Some(self.range(&(formula.start + span.start..formula.start + span.end))),
+        })
+    }
+
+    ///
+    /// Symbol names starting with the word in front of the cursor.
+    ///
+    pub fn completion(&self, position: Position) -> Vec<CompletionItem> {
+        let offset = self.offset(position);
+        if self.formula_at(offset).is_none() {
+            return vec![];
+        }
+
+        let before = &self.text[..offset];
+        let prefix = &before[before
+            .rfind(|c: char| !c.is_ascii_alphabetic())
+            .map_or(0, |pos| pos + 1)..];
+
+        symbols::search(prefix)
+            .into_iter()
+            .filter(|(spelling, _)| spelling.chars().all(|c| c.is_ascii_alphabetic()))
+            .map(|(spelling, symbol)| CompletionItem {
+                label: spelling.to_string(),
+                kind: Some(match symbol.category {
+                    Category::Greek | Category::Misc => CompletionItemKind::CONSTANT,
+                    Category::Function | Category::UnaryOperator | Category::BinaryOperator => {
+                        CompletionItemKind::FUNCTION
+                    }
+                    _ => CompletionItemKind::OPERATOR,
+                }),
+                detail: Some(symbol.category.description().to_string()),
+                documentation: Some(Documentation::String(match symbol.code_point {
+                    Some(c) => format!("{c} {}", symbol.description),
+                    None => symbol.description.clone(),
+                })),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    ///
+    /// Position of the bracket matching the one under the cursor, as paired
+    /// by the parser.
+    ///
+    pub fn matching_bracket(&self, position: Position) -> Option<lsp_types::Range> {
+        let offset = self.offset(position);
+        let formula = self.formula_at(offset)?;
+        let ast = to_ast(&self.text[formula.clone()]);
+        let span = ast
+            .expressions
+            .expressions
+            .iter()
+            .find_map(|expression| matching_bracket(expression, offset - formula.start))?;
+
+        Some(self.range(&(formula.start + span.start..formula.start + span.end)))
+    }
+
+    fn formula_at(&self, offset: usize) -> Option<Range<usize>> {
+        self.formulas
+            .iter()
+            .find(|formula| formula.start <= offset && offset <= formula.end)
+            .cloned()
+    }
+
+    pub fn range(&self, span: &Range<usize>) -> lsp_types::Range {
+        lsp_types::Range {
+            start: self.position(span.start),
+            end: self.position(span.end),
+        }
+    }
+
+    ///
+    /// LSP positions count UTF-16 code units.
+    ///
+    pub fn position(&self, offset: usize) -> Position {
+        let before = &self.text[..offset];
+        let line_start = before.rfind('\n').map_or(0, |pos| pos + 1);
+
+        Position {
+            line: before.matches('\n').count() as u32,
+            character: before[line_start..].encode_utf16().count() as u32,
+        }
+    }
+
+    pub fn offset(&self, position: Position) -> usize {
+        let line_start = match position.line {
+            0 => 0,
+            line => self
+                .text
+                .match_indices('\n')
+                .nth(line as usize - 1)
+                .map_or(self.text.len(), |(pos, _)| pos + 1),
+        };
+
+        let mut units = 0;
+        for (pos, c) in self.text[line_start..].char_indices() {
+            if units >= position.character as usize || c == '\n' {
+                return line_start + pos;
+            }
+            units += c.len_utf16();
+        }
+        self.text.len()
+    }
+}
+
+fn children<'e, 'a>(expression: &'e Expression<'a>) -> Vec<&'e Expression<'a>> {
+    match expression {
+        Expression::Frac(bi) | Expression::Sub(bi) | Expression::Pow(bi) => {
+            vec![&bi.expression_1, &bi.expression_2]
+        }
+        Expression::SubPow(tri) => vec![&tri.expression_1, &tri.expression_2, &tri.expression_3],
+        Expression::Group(group) => group.expressions.expressions.iter().collect(),
+        Expression::Unary(unary) => vec![&unary.expression],
+        Expression::Binary(binary) => vec![&binary.expression_1, &binary.expression_2],
+        Expression::Literal(Literal::Table(table)) => table
+            .rows
+            .iter()
+            .flat_map(|row| row.cols.iter())
+            .flat_map(|col| col.expressions.iter())
+            .collect(),
+        Expression::Expressions(expressions) => expressions.expressions.iter().collect(),
+        Expression::Literal(Literal::Literal(_)) | Expression::Unit => vec![],
+    }
+}
+
+fn expression_at<'e, 'a>(
+    expression: &'e Expression<'a>,
+    offset: usize,
+) -> Option<&'e Expression<'a>> {
+    if !expression.span()?.contains(&offset) {
+        return None;
+    }
+
+    children(expression)
+        .into_iter()
+        .find_map(|child| expression_at(child, offset))
+        .or(Some(expression))
+}
+
+fn matching_bracket(expression: &Expression, offset: usize) -> Option<Range<usize>> {
+    let braces = match expression {
+        Expression::Group(group) => Some((&group.l_brace, &group.r_brace)),
+        Expression::Literal(Literal::Table(table)) => Some((&table.l_brace, &table.r_brace)),
+        _ => None,
+    };
+
+    if let Some((l_brace, r_brace)) = braces {
+        let l_span = l_brace.span.start..l_brace.span.end;
+        let r_span = r_brace.span.start..r_brace.span.end;
+
+        if l_span.contains(&offset) {
+            return Some(r_span);
+        } else if r_span.contains(&offset) {
+            return Some(l_span);
+        }
+    }
+
+    children(expression)
+        .into_iter()
+        .find_map(|child| matching_bracket(child, offset))
+}
+
+#[cfg(test)]
+mod test {
+    use lsp_types::{HoverContents, Position};
+
+    use super::Document;
+
+    #[test]
+    fn test_diagnostics() {
+        let document = Document::new("file:///a.amath", "x^2\nä (a\n".to_string());
+        let diagnostics = document.diagnostics();
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start, Position::new(1, 2));
+        assert_eq!(diagnostics[0].range.end, Position::new(1, 3));
+
+        let document = Document::new("file:///a.md", "# `(a` \\(b\\)".to_string());
+        assert_eq!(document.formulas, vec![3..5, 9..10]);
+        assert_eq!(document.diagnostics()[0].range.start, Position::new(0, 3));
+    }
+
+    #[test]
+    fn test_hover() {
+        let document = Document::new("file:///a.amath", "1 + alpha/2".to_string());
+        let hover = document.hover(Position::new(0, 5)).unwrap();
+
+        assert_eq!(
+            hover.range,
+            Some(lsp_types::Range::new(
+                Position::new(0, 4),
+                Position::new(0, 9)
+            ))
+        );
+        let HoverContents::Markup(markup) = hover.contents else {
+            panic!("expected markup");
+        };
+        assert!(markup.value.starts_with("α\n"));
+
+        assert!(document.hover(Position::new(0, 1)).is_none());
+    }
+
+    #[test]
+    fn test_completion() {
+        let document = Document::new("file:///a.amath", "x + rightar".to_string());
+        let items = document.completion(Position::new(0, 11));
+
+        assert!(items.iter().any(|item| item.label == "rightarrow"));
+        assert!(items.iter().all(|item| item.label.starts_with("rightar")));
+    }
+
+    #[test]
+    fn test_matching_bracket() {
+        let document = Document::new("file:///a.amath", "a\n[(x) + {y}]".to_string());
+
+        assert_eq!(
+            document.matching_bracket(Position::new(1, 0)),
+            Some(lsp_types::Range::new(
+                Position::new(1, 10),
+                Position::new(1, 11)
+            ))
+        );
+        assert_eq!(
+            document.matching_bracket(Position::new(1, 3)),
+            Some(lsp_types::Range::new(
+                Position::new(1, 1),
+                Position::new(1, 2)
+            ))
+        );
+        assert_eq!(document.matching_bracket(Position::new(1, 2)), None);
+    }
+}
@@ -0,0 +1,177 @@
+//!
+//! # polymath-lsp
+//!
+//! Language server for `.amath` files and formulas embedded in other
+//! documents, speaking LSP over stdio. Provides diagnostics, hover, symbol
+//! completion and go to definition on brackets, which jumps to the matching
+//! bracket.
+//!
+
+mod analysis;
+
+use std::{collections::HashMap, error::Error};
+
+use analysis::Document;
+use lsp_server::{Connection, Message, Notification, Request, Response};
+use lsp_types::{
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+        PublishDiagnostics,
+    },
+    request::{Completion, GotoDefinition, HoverRequest, Request as _},
+    CompletionOptions, GotoDefinitionResponse, HoverProviderCapability, Location, OneOf,
+    PublishDiagnosticsParams, ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind,
+    Uri,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
+
+fn main() -> Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities = serde_json::to_value(ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        completion_provider: Some(CompletionOptions::default()),
+        definition_provider: Some(OneOf::Left(true)),
+        ..Default::default()
+    })?;
+
+    connection.initialize(capabilities)?;
+    Server::default().run(&connection)?;
+
+    // the writer thread only finishes once the connection is gone
+    drop(connection);
+    io_threads.join()?;
+
+    Ok(())
+}
+
+#[derive(Default)]
+struct Server {
+    documents: HashMap<Uri, Document>,
+}
+
+impl Server {
+    fn run(&mut self, connection: &Connection) -> Result<()> {
+        for message in &connection.receiver {
+            match message {
+                Message::Request(request) => {
+                    if connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+                    connection
+                        .sender
+                        .send(Message::Response(self.handle_request(request)))?;
+                }
+                Message::Notification(notification) => {
+                    if let Some(diagnostics) = self.handle_notification(notification) {
+                        connection.sender.send(Message::Notification(diagnostics))?;
+                    }
+                }
+                Message::Response(_) => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle_request(&self, request: Request) -> Response {
+        match request.method.as_str() {
+            HoverRequest::METHOD => respond(request, |params: lsp_types::HoverParams| {
+                let position = params.text_document_position_params;
+                self.documents
+                    .get(&position.text_document.uri)
+                    .and_then(|document| document.hover(position.position))
+            }),
+            Completion::METHOD => respond(request, |params: lsp_types::CompletionParams| {
+                let position = params.text_document_position;
+                self.documents
+                    .get(&position.text_document.uri)
+                    .map(|document| document.completion(position.position))
+            }),
+            GotoDefinition::METHOD => {
+                respond(request, |params: lsp_types::GotoDefinitionParams| {
+                    let position = params.text_document_position_params;
+                    let uri = position.text_document.uri;
+                    let range = self
+                        .documents
+                        .get(&uri)?
+                        .matching_bracket(position.position)?;
+
+                    Some(GotoDefinitionResponse::Scalar(Location { uri, range }))
+                })
+            }
+            method => Response::new_err(
+                request.id,
+                lsp_server::ErrorCode::MethodNotFound as i32,
+                format!("unsupported request `{method}`"),
+            ),
+        }
+    }
+
+    ///
+    /// Keeps track of open documents, returns the diagnostics to publish.
+    ///
+    fn handle_notification(&mut self, notification: Notification) -> Option<Notification> {
+        let uri = match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: lsp_types::DidOpenTextDocumentParams = params(notification)?;
+                let uri = params.text_document.uri;
+                self.open(uri.clone(), params.text_document.text);
+                uri
+            }
+            DidChangeTextDocument::METHOD => {
+                let mut params: lsp_types::DidChangeTextDocumentParams = params(notification)?;
+                let uri = params.text_document.uri;
+                // full synchronization, the last change holds the whole text
+                self.open(uri.clone(), params.content_changes.pop()?.text);
+                uri
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: lsp_types::DidCloseTextDocumentParams = params(notification)?;
+                self.documents.remove(&params.text_document.uri);
+                return Some(publish(params.text_document.uri, vec![]));
+            }
+            _ => return None,
+        };
+
+        let diagnostics = self.documents.get(&uri)?.diagnostics();
+        Some(publish(uri, diagnostics))
+    }
+
+    fn open(&mut self, uri: Uri, text: String) {
+        let document = Document::new(uri.as_str(), text);
+        self.documents.insert(uri, document);
+    }
+}
+
+fn respond<P: DeserializeOwned, R: Serialize>(
+    request: Request,
+    f: impl FnOnce(P) -> R,
+) -> Response {
+    match serde_json::from_value(request.params) {
+        Ok(params) => Response::new_ok(request.id, f(params)),
+        Err(error) => Response::new_err(
+            request.id,
+            lsp_server::ErrorCode::InvalidParams as i32,
+            error.to_string(),
+        ),
+    }
+}
+
+fn params<P: DeserializeOwned>(notification: Notification) -> Option<P> {
+    serde_json::from_value(notification.params).ok()
+}
+
+fn publish(uri: Uri, diagnostics: Vec<lsp_types::Diagnostic>) -> Notification {
+    Notification::new(
+        PublishDiagnostics::METHOD.to_string(),
+        PublishDiagnosticsParams {
+            uri,
+            diagnostics,
+            version: None,
+        },
+    )
+}
@@ -0,0 +1,59 @@
+//!
+//! # polymath-mdbook
+//!
+//! mdBook preprocessor rendering AsciiMath formulas to MathML.
+//!
+//! ```toml
+//! [preprocessor.polymath]
+//! command = "polymath-mdbook"
+//! display = "inline"
+//! delimiters = [
+//!     { open = "\\(", close = "\\)" },
+//!     { open = "$$", close = "$$", display = "block" },
+//! ]
+//! ```
+//!
+
+mod preprocessor;
+
+use std::{
+    io::{self, Write},
+    process::ExitCode,
+};
+
+use preprocessor::{process_book, Config};
+use serde_json::Value;
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        // MathML is only of use to renderers producing html
+        ["supports", "html"] => ExitCode::SUCCESS,
+        ["supports", _] => ExitCode::FAILURE,
+        [] => match run() {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("polymath-mdbook: {error}");
+                ExitCode::FAILURE
+            }
+        },
+        _ => {
+            eprintln!("usage: polymath-mdbook [supports <renderer>]");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run() -> Result<(), serde_json::Error> {
+    let (context, mut book): (Value, Value) = serde_json::from_reader(io::stdin().lock())?;
+    let options = Config::from_context(&context)?.to_options();
+
+    for message in process_book(&mut book, &options) {
+        eprintln!("{message}");
+    }
+
+    let mut stdout = io::stdout().lock();
+    serde_json::to_writer(&mut stdout, &book)?;
+    stdout.flush().map_err(serde_json::Error::io)
+}
@@ -0,0 +1,265 @@
+//!
+//! # Preprocessor
+//!
+//! Rewrites the chapters of a book as handed over by mdBook. Formulas are
+//! found with [polymath_rs::document], fenced code blocks are left alone.
+//!
+
+use polymath_rs::{
+    diagnostics::{line_column, Diagnostic},
+    document::{self, Delimiter},
+    mathml::Display,
+};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Name of the preprocessor table in `book.toml`
+pub const NAME: &str = "polymath";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Config {
+    /// Display mode of delimiters that don't specify one
+    pub display: Option<DisplayConfig>,
+    pub delimiters: Option<Vec<DelimiterConfig>>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DisplayConfig {
+    Inline,
+    Block,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DelimiterConfig {
+    pub open: String,
+    pub close: String,
+    pub display: Option<DisplayConfig>,
+}
+
+impl From<DisplayConfig> for Display {
+    fn from(display: DisplayConfig) -> Self {
+        match display {
+            DisplayConfig::Inline => Display::Inline,
+            DisplayConfig::Block => Display::Block,
+        }
+    }
+}
+
+impl Config {
+    ///
+    /// Reads the `[preprocessor.polymath]` table from the book configuration
+    /// mdBook passes along with the book.
+    ///
+    pub fn from_context(context: &Value) -> Result<Self, serde_json::Error> {
+        match context.pointer(&format!("/config/preprocessor/{NAME}")) {
+            Some(config) => Config::deserialize(config),
+            None => Ok(Config::default()),
+        }
+    }
+
+    ///
+    /// Backticks are code spans in markdown, so unlike [document::Options]
+    /// only `\(...\)` and `$$...$$` are recognized by default.
+    ///
+    pub fn to_options(&self) -> document::Options {
+        let delimiters = match &self.delimiters {
+            Some(delimiters) => delimiters
+                .iter()
+                .map(|delimiter| Delimiter {
+                    open: delimiter.open.clone(),
+                    close: delimiter.close.clone(),
+                    display: delimiter
+                        .display
+                        .or(self.display)
+                        .map_or(Display::Inline, Into::into),
+                })
+                .collect(),
+            None => vec![
+                Delimiter::new(
+                    "\\(",
+                    "\\)",
+                    self.display.map_or(Display::Inline, Into::into),
+                ),
+                Delimiter::new("$$", "$$", self.display.map_or(Display::Block, Into::into)),
+            ],
+        };
+
+        document::Options {
+            delimiters,
+            ..Default::default()
+        }
+    }
+}
+
+///
+/// Converts every chapter of `book` in place and returns the diagnostics as
+/// `path:line:column: message` lines.
+///
+pub fn process_book(book: &mut Value, options: &document::Options) -> Vec<String> {
+    let mut messages = Vec::new();
+    walk(book, &mut |chapter| {
+        let path = chapter
+            .get("path")
+            .and_then(Value::as_str)
+            .or_else(|| chapter.get("name").and_then(Value::as_str))
+            .unwrap_or("<unknown>")
+            .to_string();
+
+        if let Some(Value::String(content)) = chapter.get_mut("content") {
+            let (converted, diagnostics) = process_chapter(content, options);
+
+            messages.extend(diagnostics.iter().map(|diagnostic| {
+                let (line, column) = line_column(content, diagnostic.span.start);
+                format!("{path}:{line}:{column}: {}", diagnostic.message)
+            }));
+
+            *content = converted;
+        }
+    });
+    messages
+}
+
+fn walk(value: &mut Value, f: &mut impl FnMut(&mut Value)) {
+    match value {
+        Value::Object(map) => {
+            if let Some(chapter) = map.get_mut("Chapter") {
+                f(chapter);
+            }
+            map.values_mut().for_each(|value| walk(value, f));
+        }
+        Value::Array(values) => values.iter_mut().for_each(|value| walk(value, f)),
+        _ => {}
+    }
+}
+
+///
+/// Converts the formulas of a single chapter, skipping fenced code blocks.
+///
+pub fn process_chapter(content: &str, options: &document::Options) -> (String, Vec<Diagnostic>) {
+    let mut converted = String::with_capacity(content.len());
+    let mut diagnostics = Vec::new();
+    let mut fence: Option<&str> = None;
+    let mut text_start = 0;
+    let mut pos = 0;
+
+    let mut flush = |converted: &mut String, range: std::ops::Range<usize>| {
+        let document = document::convert(&content[range.clone()], options);
+
+        converted.push_str(&document.text);
+        diagnostics.extend(
+            document
+                .diagnostics
+                .into_iter()
+                .chain(document.formulas.into_iter().flat_map(|f| f.diagnostics))
+                .map(|diagnostic| Diagnostic {
+                    span: range.start + diagnostic.span.start..range.start + diagnostic.span.end,
+                    ..diagnostic
+                }),
+        );
+    };
+
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let marker = ["```", "~~~"]
+            .into_iter()
+            .find(|marker| trimmed.starts_with(marker));
+
+        match (fence, marker) {
+            (None, Some(marker)) => {
+                flush(&mut converted, text_start..pos);
+                fence = Some(marker);
+            }
+            (Some(open), Some(close)) if open == close => {
+                converted.push_str(line);
+                fence = None;
+                text_start = pos + line.len();
+            }
+            _ => {}
+        }
+
+        if fence.is_some() {
+            converted.push_str(line);
+        }
+
+        pos += line.len();
+    }
+
+    // unterminated code blocks extend to the end of the chapter
+    if fence.is_none() {
+        flush(&mut converted, text_start..content.len());
+    }
+
+    diagnostics.sort_by_key(|diagnostic| diagnostic.span.start);
+    (converted, diagnostics)
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::{process_book, process_chapter, Config};
+
+    #[test]
+    fn test_chapter() {
+        let content = "# Title\n\n\\(x^2\\)\n\n```\n$$a$$\n```\n$$b$$\n";
+        let (converted, diagnostics) = process_chapter(content, &Config::default().to_options());
+
+        assert_eq!(
+            converted,
+            "# Title\n\n<math display=\"inline\"><msup><mi>x</mi><mn>2</mn></msup></math>\n\n\
+             ```\n$$a$$\n```\n<math display=\"block\"><mi>b</mi></math>\n"
+        );
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_book() {
+        let context = json!({
+            "root": "/book",
+            "config": {
+                "preprocessor": {
+                    "polymath": {
+                        "display": "block",
+                        "delimiters": [{ "open": "`", "close": "`" }]
+                    }
+                }
+            },
+            "renderer": "html",
+            "mdbook_version": "0.4.40"
+        });
+        let mut book = json!({
+            "sections": [
+                { "Chapter": {
+                    "name": "One",
+                    "content": "`a`",
+                    "path": "one.md",
+                    "sub_items": [
+                        { "Chapter": {
+                            "name": "Two",
+                            "content": "\n`(b`",
+                            "path": "two.md",
+                            "sub_items": []
+                        } }
+                    ]
+                } },
+                "Separator"
+            ]
+        });
+
+        let options = Config::from_context(&context).unwrap().to_options();
+        let messages = process_book(&mut book, &options);
+
+        assert_eq!(
+            book.pointer("/sections/0/Chapter/content").unwrap(),
+            "<math display=\"block\"><mi>a</mi></math>"
+        );
+        assert_eq!(
+            book.pointer("/sections/0/Chapter/sub_items/0/Chapter/content")
+                .unwrap(),
+            "\n<math display=\"block\"><mi>(</mi><mi>b</mi></math>"
+        );
+        assert_eq!(messages, vec!["two.md:2:2: unclosed bracket `(`"]);
+    }
+}
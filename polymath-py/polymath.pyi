@@ -0,0 +1,43 @@
+from typing import List, Literal, Optional, TypedDict
+
+Format = Literal["mathml", "latex", "text"]
+Display = Literal["block", "inline"]
+
+class Span(TypedDict):
+    start: int
+    end: int
+
+class Diagnostic(TypedDict):
+    start: int
+    end: int
+    severity: Literal["error", "warning"]
+    message: str
+
+class Token(TypedDict):
+    text: str
+    kind: str
+    name: Optional[str]
+    span: Optional[Span]
+
+class ParseResult(TypedDict):
+    diagnostics: List[Diagnostic]
+    ast: "Node"
+
+# Nodes are dicts tagged with "type": expressions, frac, sub, pow, subPow,
+# group, unary, binary, token or table.
+Node = dict
+
+def render(
+    asciimath: str,
+    format: Format = "mathml",
+    *,
+    display: Display = "block",
+    source_positions: bool = False,
+) -> str: ...
+def to_mathml(
+    asciimath: str, *, display: Display = "block", source_positions: bool = False
+) -> str: ...
+def to_latex(asciimath: str) -> str: ...
+def to_text(asciimath: str) -> str: ...
+def diagnose(asciimath: str) -> List[Diagnostic]: ...
+def parse(asciimath: str) -> ParseResult: ...
@@ -0,0 +1,109 @@
+//!
+//! # polymath-py
+//!
+//! Python bindings, built as the `polymath` extension module with maturin.
+//! Offsets in diagnostics and the AST count code points, so they can be used
+//! as Python string indices directly.
+//!
+
+mod tree;
+
+use polymath_rs::{
+    mathml::{Display, Options},
+    Format,
+};
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyDict};
+
+///
+/// Converts `asciimath` to `format`, one of `mathml`, `latex` or `text`.
+/// `display` and `source_positions` only apply to MathML.
+///
+#[pyfunction]
+#[pyo3(signature = (asciimath, format = "mathml", *, display = "block", source_positions = false))]
+fn render(
+    py: Python<'_>,
+    asciimath: &str,
+    format: &str,
+    display: &str,
+    source_positions: bool,
+) -> PyResult<String> {
+    let format = match format {
+        "mathml" => Format::MathMl,
+        "latex" => Format::Latex,
+        "text" => Format::Text,
+        _ => return Err(PyValueError::new_err(format!("unknown format `{format}`"))),
+    };
+    let options = Options {
+        display: match display {
+            "block" => Display::Block,
+            "inline" => Display::Inline,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "unknown display `{display}`"
+                )))
+            }
+        },
+        source_positions,
+    };
+
+    Ok(py.detach(|| polymath_rs::render(asciimath, format, &options)))
+}
+
+#[pyfunction]
+#[pyo3(signature = (asciimath, *, display = "block", source_positions = false))]
+fn to_mathml(
+    py: Python<'_>,
+    asciimath: &str,
+    display: &str,
+    source_positions: bool,
+) -> PyResult<String> {
+    render(py, asciimath, "mathml", display, source_positions)
+}
+
+#[pyfunction]
+fn to_latex(py: Python<'_>, asciimath: &str) -> String {
+    py.detach(|| polymath_rs::render(asciimath, Format::Latex, &Options::default()))
+}
+
+///
+/// Plain Unicode text, e.g. for alt texts.
+///
+#[pyfunction]
+fn to_text(py: Python<'_>, asciimath: &str) -> String {
+    py.detach(|| polymath_rs::render(asciimath, Format::Text, &Options::default()))
+}
+
+///
+/// Problems the parser recovered from as a list of dicts with `start`,
+/// `end`, `severity` and `message`.
+///
+#[pyfunction]
+fn diagnose<'py>(py: Python<'py>, asciimath: &str) -> PyResult<Vec<Bound<'py, PyDict>>> {
+    let diagnostics = py.detach(|| polymath_rs::diagnose(asciimath));
+    tree::diagnostics(py, asciimath, diagnostics)
+}
+
+///
+/// The diagnostics and the AST, nodes are dicts tagged with `type`.
+///
+#[pyfunction]
+fn parse<'py>(py: Python<'py>, asciimath: &str) -> PyResult<Bound<'py, PyDict>> {
+    let (ast, diagnostics) = py.detach(|| {
+        (
+            polymath_rs::to_ast(asciimath),
+            polymath_rs::diagnose(asciimath),
+        )
+    });
+    tree::parse_result(py, asciimath, &ast, diagnostics)
+}
+
+#[pymodule]
+fn polymath(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(render, module)?)?;
+    module.add_function(wrap_pyfunction!(to_mathml, module)?)?;
+    module.add_function(wrap_pyfunction!(to_latex, module)?)?;
+    module.add_function(wrap_pyfunction!(to_text, module)?)?;
+    module.add_function(wrap_pyfunction!(diagnose, module)?)?;
+    module.add_function(wrap_pyfunction!(parse, module)?)?;
+    Ok(())
+}
@@ -0,0 +1,260 @@
+//!
+//! # Tree
+//!
+//! Python facing view of the AST and diagnostics, plain dicts and lists
+//! shaped like the JavaScript tree of `polymath-web`.
+//!
+
+use std::ops::Range;
+
+use polymath_rs::{
+    ast::{Expression, Expressions, Literal, AST},
+    diagnostics::{Diagnostic, Severity},
+    tokens::{types::TokenType, Token},
+};
+use pyo3::{prelude::*, types::PyDict};
+
+///
+/// Maps byte offsets of a string to code point offsets.
+///
+pub struct Offsets(Vec<usize>);
+
+impl Offsets {
+    pub fn new(text: &str) -> Self {
+        let mut offsets = vec![0; text.len() + 1];
+        let mut chars = 0;
+
+        for (index, c) in text.char_indices() {
+            offsets[index..index + c.len_utf8()].fill(chars);
+            chars += 1;
+        }
+        offsets[text.len()] = chars;
+
+        Self(offsets)
+    }
+
+    pub fn get(&self, offset: usize) -> usize {
+        self.0[offset.min(self.0.len() - 1)]
+    }
+}
+
+struct Converter<'py> {
+    py: Python<'py>,
+    offsets: Offsets,
+}
+
+pub fn diagnostics<'py>(
+    py: Python<'py>,
+    input: &str,
+    diagnostics: Vec<Diagnostic>,
+) -> PyResult<Vec<Bound<'py, PyDict>>> {
+    let converter = Converter {
+        py,
+        offsets: Offsets::new(input),
+    };
+    diagnostics
+        .into_iter()
+        .map(|diagnostic| converter.diagnostic(diagnostic))
+        .collect()
+}
+
+pub fn parse_result<'py>(
+    py: Python<'py>,
+    input: &str,
+    ast: &AST,
+    diagnostics: Vec<Diagnostic>,
+) -> PyResult<Bound<'py, PyDict>> {
+    let converter = Converter {
+        py,
+        offsets: Offsets::new(input),
+    };
+
+    let result = PyDict::new(py);
+    result.set_item(
+        "diagnostics",
+        diagnostics
+            .into_iter()
+            .map(|diagnostic| converter.diagnostic(diagnostic))
+            .collect::<PyResult<Vec<_>>>()?,
+    )?;
+    result.set_item("ast", converter.expressions(&ast.expressions)?)?;
+    Ok(result)
+}
+
+impl<'py> Converter<'py> {
+    fn diagnostic(&self, diagnostic: Diagnostic) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(self.py);
+        dict.set_item("start", self.offsets.get(diagnostic.span.start))?;
+        dict.set_item("end", self.offsets.get(diagnostic.span.end))?;
+        dict.set_item(
+            "severity",
+            match diagnostic.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+            },
+        )?;
+        dict.set_item("message", diagnostic.message)?;
+        Ok(dict)
+    }
+
+    fn span(&self, span: Option<Range<usize>>) -> PyResult<Option<Bound<'py, PyDict>>> {
+        span.map(|span| {
+            let dict = PyDict::new(self.py);
+            dict.set_item("start", self.offsets.get(span.start))?;
+            dict.set_item("end", self.offsets.get(span.end))?;
+            Ok(dict)
+        })
+        .transpose()
+    }
+
+    ///
+    /// A dict with the node `type` and its `span`.
+    ///
+    fn node(&self, kind: &str, span: Option<Range<usize>>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(self.py);
+        dict.set_item("type", kind)?;
+        dict.set_item("span", self.span(span)?)?;
+        Ok(dict)
+    }
+
+    fn expressions(&self, expressions: &Expressions) -> PyResult<Bound<'py, PyDict>> {
+        let node = self.node("expressions", expressions.span())?;
+        node.set_item("children", self.children(expressions)?)?;
+        Ok(node)
+    }
+
+    fn children(&self, expressions: &Expressions) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        expressions
+            .expressions
+            .iter()
+            .filter(|expression| !matches!(expression, Expression::Unit))
+            .map(|expression| self.expression(expression))
+            .collect()
+    }
+
+    fn expression(&self, expression: &Expression) -> PyResult<Bound<'py, PyDict>> {
+        let span = expression.span();
+
+        let node = match expression {
+            Expression::Frac(frac) => {
+                let node = self.node("frac", span)?;
+                node.set_item("numerator", self.expression(&frac.expression_1)?)?;
+                node.set_item("denominator", self.expression(&frac.expression_2)?)?;
+                node
+            }
+            Expression::Sub(sub) => {
+                let node = self.node("sub", span)?;
+                node.set_item("base", self.expression(&sub.expression_1)?)?;
+                node.set_item("sub", self.expression(&sub.expression_2)?)?;
+                node
+            }
+            Expression::Pow(pow) => {
+                let node = self.node("pow", span)?;
+                node.set_item("base", self.expression(&pow.expression_1)?)?;
+                node.set_item("sup", self.expression(&pow.expression_2)?)?;
+                node
+            }
+            Expression::SubPow(sub_pow) => {
+                let node = self.node("subPow", span)?;
+                node.set_item("base", self.expression(&sub_pow.expression_1)?)?;
+                node.set_item("sub", self.expression(&sub_pow.expression_2)?)?;
+                node.set_item("sup", self.expression(&sub_pow.expression_3)?)?;
+                node
+            }
+            Expression::Group(group) => {
+                let node = self.node("group", span)?;
+                node.set_item("open", self.token(&group.l_brace)?)?;
+                node.set_item("close", self.token(&group.r_brace)?)?;
+                node.set_item("children", self.children(&group.expressions)?)?;
+                node
+            }
+            Expression::Unary(unary) => {
+                let node = self.node("unary", span)?;
+                node.set_item("operator", self.token(&unary.operator)?)?;
+                node.set_item("operand", self.expression(&unary.expression)?)?;
+                node
+            }
+            Expression::Binary(binary) => {
+                let node = self.node("binary", span)?;
+                node.set_item("operator", self.token(&binary.operator)?)?;
+                node.set_item("first", self.expression(&binary.expression_1)?)?;
+                node.set_item("second", self.expression(&binary.expression_2)?)?;
+                node
+            }
+            Expression::Literal(Literal::Literal(literal)) => {
+                let node = self.node("token", span)?;
+                node.set_item("token", self.token(literal)?)?;
+                node
+            }
+            Expression::Literal(Literal::Table(table)) => {
+                let node = self.node("table", span)?;
+                node.set_item("open", self.token(&table.l_brace)?)?;
+                node.set_item("close", self.token(&table.r_brace)?)?;
+                node.set_item("separators", &table.seperators)?;
+                node.set_item(
+                    "rows",
+                    table
+                        .rows
+                        .iter()
+                        .map(|row| {
+                            row.cols
+                                .iter()
+                                .map(|col| self.expressions(col))
+                                .collect::<PyResult<Vec<_>>>()
+                        })
+                        .collect::<PyResult<Vec<_>>>()?,
+                )?;
+                node
+            }
+            Expression::Expressions(inner) => self.expressions(inner)?,
+            Expression::Unit => {
+                let node = self.node("expressions", None)?;
+                node.set_item("children", Vec::<Bound<'py, PyDict>>::new())?;
+                node
+            }
+        };
+
+        Ok(node)
+    }
+
+    fn token(&self, token: &Token) -> PyResult<Bound<'py, PyDict>> {
+        // `Greek(Alpha)` becomes kind `greek` and name `Alpha`
+        let debug = format!("{:?}", token.token_type);
+        let (kind, name) = match debug.split_once('(') {
+            Some((kind, name)) => (kind, Some(name.trim_end_matches(')'))),
+            None => (debug.as_str(), None),
+        };
+
+        let mut chars = kind.chars();
+        let kind: String = chars
+            .next()
+            .map(|first| first.to_lowercase().chain(chars).collect())
+            .unwrap_or_default();
+
+        let span = match token.token_type {
+            TokenType::None => None,
+            _ => Some(token.span.start..token.span.end),
+        };
+
+        let dict = PyDict::new(self.py);
+        dict.set_item("text", token.span.text)?;
+        dict.set_item("kind", kind)?;
+        dict.set_item("name", name)?;
+        dict.set_item("span", self.span(span)?)?;
+        Ok(dict)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Offsets;
+
+    #[test]
+    fn test_offsets() {
+        let offsets = Offsets::new("α/(b");
+
+        assert_eq!(offsets.get(2), 1);
+        assert_eq!(offsets.get(4), 3);
+        assert_eq!(offsets.get(99), 4);
+    }
+}
@@ -0,0 +1,43 @@
+import polymath
+import pytest
+
+
+def test_to_mathml():
+    assert (
+        polymath.to_mathml("a/b")
+        == '<math display="block"><mfrac><mi>a</mi><mi>b</mi></mfrac></math>'
+    )
+    assert (
+        polymath.to_mathml("x", display="inline", source_positions=True)
+        == '<math display="inline"><mi data-src-start="0" data-src-end="1">x</mi></math>'
+    )
+
+
+def test_formats():
+    assert polymath.to_latex("a/b") == "\\frac{a}{b}"
+    assert polymath.render("a/b", "latex") == "\\frac{a}{b}"
+    assert polymath.to_text("sqrt 2") == "√2"
+
+    with pytest.raises(ValueError):
+        polymath.render("a/b", "html")
+
+
+def test_diagnose():
+    assert polymath.diagnose("α + (b") == [
+        {"start": 4, "end": 5, "severity": "error", "message": "unclosed bracket `(`"}
+    ]
+    assert polymath.diagnose("a/b") == []
+
+
+def test_parse():
+    result = polymath.parse("α/b")
+    frac = result["ast"]["children"][0]
+
+    assert result["diagnostics"] == []
+    assert frac["type"] == "frac"
+    assert frac["numerator"]["token"] == {
+        "text": "α",
+        "kind": "symbol",
+        "name": None,
+        "span": {"start": 0, "end": 1},
+    }
@@ -0,0 +1,426 @@
+//!
+//! # Analysis
+//!
+//! Lists the symbols a formula uses, e.g. to index it for search. Unlike
+//! [semantics](crate::semantics) this works on any AST, also on ones with
+//! errors, and keeps every occurrence with its span.
+//!
+//! ```
+//! use polymath_rs::{analysis::analyze, to_ast};
+//!
+//! let analysis = analyze(&to_ast("sum_(i=1)^n a_i x^i"));
+//! assert_eq!(analysis.variables[0].name, "n");
+//! assert_eq!(analysis.bound[0].name, "i");
+//! assert_eq!(analysis.bound[0].spans, vec![5..6, 14..15, 18..19]);
+//! ```
+//!
+
+use std::ops::Range;
+
+use crate::{
+    ast::{Expression, Expressions, Literal, AST},
+    tokens::{
+        types::{Arrow, BinaryOperator, Misc, Operation, Relational, TokenType, UnaryOperator},
+        Token,
+    },
+};
+
+///
+/// A symbol along with where it occurs.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Usage {
+    /// As spelled in the first occurrence
+    pub name: String,
+    /// Byte ranges of all occurrences, in source order
+    pub spans: Vec<Range<usize>>,
+}
+
+///
+/// The symbols of a formula by their role, each list in order of first
+/// occurrence.
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Analysis {
+    /// Free single letter symbols and Greek letters
+    pub variables: Vec<Usage>,
+    /// Builtin functions like `sin` and symbols applied like `f(x)`
+    pub functions: Vec<Usage>,
+    /// Variables bound by `sum`, `prod`, `int` or `lim`, `i` in `sum_(i=1)^n i`
+    pub bound: Vec<Usage>,
+    pub numbers: Vec<Usage>,
+    /// Operations, relations, arrows, logical symbols and operators like `sqrt`
+    pub operators: Vec<Usage>,
+}
+
+///
+/// Analyzes `ast`. Symbols followed by parentheses are taken for products,
+/// `a(b+c)`, only `f` and `g` are functions.
+///
+pub fn analyze(ast: &AST) -> Analysis {
+    analyze_with_functions(ast, &|_| false)
+}
+
+///
+/// Like [analyze], but symbols for which `is_function` returns `true` count
+/// as functions if parentheses follow them, `h(x)`.
+///
+pub fn analyze_with_functions(ast: &AST, is_function: &dyn Fn(&str) -> bool) -> Analysis {
+    let mut analyzer = Analyzer {
+        is_function,
+        bound: Vec::new(),
+        analysis: Analysis::default(),
+    };
+    analyzer.expressions(&ast.expressions);
+    analyzer.analysis
+}
+
+struct Analyzer<'f> {
+    is_function: &'f dyn Fn(&str) -> bool,
+    /// Variables bound at the current position, innermost last
+    bound: Vec<String>,
+    analysis: Analysis,
+}
+
+///
+/// Where the body of a big operator ends in its sequence.
+///
+enum Scope {
+    /// `sum` and friends take the product following them
+    Product,
+    /// Integrals end with the variable of their `dx` at this index
+    Differential(usize),
+}
+
+impl<'f> Analyzer<'f> {
+    fn expressions(&mut self, expressions: &Expressions) {
+        let items: Vec<&Expression> = expressions
+            .expressions
+            .iter()
+            .filter(|item| !matches!(item, Expression::Unit))
+            .collect();
+        let depth = self.bound.len();
+        let mut scopes: Vec<(usize, Scope)> = Vec::new();
+
+        for (index, item) in items.iter().enumerate() {
+            // a product ends with the next operator of a lower precedence
+            if literal(item).is_some_and(ends_product) {
+                while let Some((depth, Scope::Product)) = scopes.last() {
+                    self.bound.truncate(*depth);
+                    scopes.pop();
+                }
+            }
+
+            if let Some((depth, Scope::Differential(end))) = scopes.last() {
+                let (depth, end) = (*depth, *end);
+                if index + 1 == end {
+                    // the `d` of `dx`
+                    continue;
+                }
+                if index == end {
+                    let token = literal(item).expect("differentials are tokens");
+                    push(&mut self.analysis.bound, token);
+                    self.bound.truncate(depth);
+                    scopes.pop();
+                    continue;
+                }
+            }
+
+            match big_operator(item) {
+                Some((operator, lower, upper)) => {
+                    push(&mut self.analysis.operators, operator);
+                    let variable = self.bounds(operator, lower, upper);
+
+                    let scope = match &operator.token_type {
+                        TokenType::Misc(Misc::Int | Misc::OInt) => {
+                            differential(&items, index).map(|(end, variable)| {
+                                (
+                                    Some(variable.span.text.to_string()),
+                                    Scope::Differential(end),
+                                )
+                            })
+                        }
+                        _ => Some((variable, Scope::Product)),
+                    };
+                    if let Some((variable, scope)) = scope {
+                        scopes.push((self.bound.len(), scope));
+                        self.bound.extend(variable);
+                    }
+                }
+                None => match literal(item) {
+                    Some(token)
+                        if token.token_type == TokenType::Symbol
+                            && (self.is_function)(token.span.text)
+                            && matches!(items.get(index + 1), Some(Expression::Group(_))) =>
+                    {
+                        push(&mut self.analysis.functions, token)
+                    }
+                    _ => self.expression(item),
+                },
+            }
+        }
+
+        self.bound.truncate(depth);
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Frac(bi_expression)
+            | Expression::Sub(bi_expression)
+            | Expression::Pow(bi_expression) => {
+                self.expression(&bi_expression.expression_1);
+                self.expression(&bi_expression.expression_2);
+            }
+            Expression::SubPow(tri_expression) => {
+                self.expression(&tri_expression.expression_1);
+                self.expression(&tri_expression.expression_2);
+                self.expression(&tri_expression.expression_3);
+            }
+            Expression::Group(group) => self.expressions(&group.expressions),
+            Expression::Unary(unary) => {
+                push(&mut self.analysis.operators, &unary.operator);
+                // the letters of `text(abc)` are no variables
+                if unary.operator.token_type != TokenType::UnaryOperator(UnaryOperator::Text) {
+                    self.expression(&unary.expression);
+                }
+            }
+            Expression::Binary(binary) => {
+                push(&mut self.analysis.operators, &binary.operator);
+                // nor is the `red` of `color(red)(x)`
+                if binary.operator.token_type != TokenType::BinaryOperator(BinaryOperator::Color) {
+                    self.expression(&binary.expression_1);
+                }
+                self.expression(&binary.expression_2);
+            }
+            Expression::Literal(Literal::Literal(token)) => self.token(token),
+            Expression::Literal(Literal::Table(table)) => {
+                for col in table.rows.iter().flat_map(|row| row.cols.iter()) {
+                    self.expressions(col);
+                }
+            }
+            Expression::Expressions(expressions) => self.expressions(expressions),
+            Expression::Unit => {}
+        }
+    }
+
+    fn token(&mut self, token: &Token) {
+        let list = match &token.token_type {
+            TokenType::Symbol if token.span.text.chars().all(char::is_alphabetic) => {
+                match self.bound.iter().any(|name| name == token.span.text) {
+                    true => &mut self.analysis.bound,
+                    false => &mut self.analysis.variables,
+                }
+            }
+            TokenType::Greek(_) => match self.bound.iter().any(|name| name == token.span.text) {
+                true => &mut self.analysis.bound,
+                false => &mut self.analysis.variables,
+            },
+            TokenType::Number => &mut self.analysis.numbers,
+            TokenType::Function(_) => &mut self.analysis.functions,
+            TokenType::Operation(_)
+            | TokenType::Relational(_)
+            | TokenType::Arrow(_)
+            | TokenType::Logical(_)
+            | TokenType::Division
+            | TokenType::Misc(Misc::Int | Misc::OInt | Misc::Lim) => &mut self.analysis.operators,
+            _ => return,
+        };
+        push(list, token);
+    }
+
+    ///
+    /// Walks the bounds of a big operator, returns the variable bound by a
+    /// lower bound like `(i=1)`, `(x->0)` or `i`.
+    ///
+    fn bounds(
+        &mut self,
+        operator: &Token,
+        lower: Option<&Expression>,
+        upper: Option<&Expression>,
+    ) -> Option<String> {
+        let integral = matches!(operator.token_type, TokenType::Misc(Misc::Int | Misc::OInt));
+        let mut variable = None;
+
+        if let Some(lower) = lower {
+            match (lower, integral) {
+                (Expression::Group(group), false) => {
+                    let items: Vec<&Expression> = group
+                        .expressions
+                        .expressions
+                        .iter()
+                        .filter(|item| !matches!(item, Expression::Unit))
+                        .collect();
+                    let binds = items
+                        .get(1)
+                        .and_then(|item| literal(item))
+                        .is_some_and(|token| {
+                            matches!(
+                                token.token_type,
+                                TokenType::Relational(Relational::Equals)
+                                    | TokenType::Arrow(Arrow::ToArrow | Arrow::RightArrow)
+                            )
+                        });
+
+                    match items.first().and_then(|item| literal(item)) {
+                        Some(token) if binds && is_variable(token) => {
+                            push(&mut self.analysis.bound, token);
+                            variable = Some(token.span.text.to_string());
+                            for item in &items[1..] {
+                                self.expression(item);
+                            }
+                        }
+                        _ => self.expression(lower),
+                    }
+                }
+                (Expression::Literal(Literal::Literal(token)), false) if is_variable(token) => {
+                    push(&mut self.analysis.bound, token);
+                    variable = Some(token.span.text.to_string());
+                }
+                _ => self.expression(lower),
+            }
+        }
+        if let Some(upper) = upper {
+            self.expression(upper);
+        }
+
+        variable
+    }
+}
+
+///
+/// The token, lower and upper bound of a big operator like `sum_(i=1)^n`.
+///
+fn big_operator<'s, 'a>(
+    expression: &'s Expression<'a>,
+) -> Option<(
+    &'s Token<'a>,
+    Option<&'s Expression<'a>>,
+    Option<&'s Expression<'a>>,
+)> {
+    let (base, lower, upper) = match expression {
+        Expression::Sub(sub) => (&*sub.expression_1, Some(&*sub.expression_2), None),
+        Expression::Pow(pow) => (&*pow.expression_1, None, Some(&*pow.expression_2)),
+        Expression::SubPow(sub_pow) => (
+            &*sub_pow.expression_1,
+            Some(&*sub_pow.expression_2),
+            Some(&*sub_pow.expression_3),
+        ),
+        _ => (expression, None, None),
+    };
+
+    let token = literal(base)?;
+    matches!(
+        token.token_type,
+        TokenType::Operation(Operation::Sum | Operation::Prod)
+            | TokenType::Misc(Misc::Int | Misc::OInt | Misc::Lim)
+    )
+    .then_some((token, lower, upper))
+}
+
+///
+/// The index and token of the variable of the `dx` closing the integral at
+/// `index`.
+///
+fn differential<'s, 'a>(
+    items: &[&'s Expression<'a>],
+    index: usize,
+) -> Option<(usize, &'s Token<'a>)> {
+    (index + 1..items.len().saturating_sub(1)).find_map(|position| {
+        let d = literal(items[position])?;
+        let variable = literal(items[position + 1])?;
+        (d.token_type == TokenType::Symbol && d.span.text == "d" && is_variable(variable))
+            .then_some((position + 1, variable))
+    })
+}
+
+fn is_variable(token: &Token) -> bool {
+    match token.token_type {
+        TokenType::Symbol => token.span.text.chars().all(char::is_alphabetic),
+        TokenType::Greek(_) => true,
+        _ => false,
+    }
+}
+
+///
+/// Whether `token` ends the product a `sum` applies to.
+///
+fn ends_product(token: &Token) -> bool {
+    match &token.token_type {
+        TokenType::Operation(operation) => matches!(operation, Operation::Plus | Operation::Minus),
+        TokenType::Relational(_) | TokenType::Arrow(_) | TokenType::Logical(_) => true,
+        TokenType::Symbol => token.span.text == ",",
+        _ => false,
+    }
+}
+
+fn literal<'s, 'a>(expression: &'s Expression<'a>) -> Option<&'s Token<'a>> {
+    match expression {
+        Expression::Literal(Literal::Literal(token)) => Some(token),
+        _ => None,
+    }
+}
+
+fn push(usages: &mut Vec<Usage>, token: &Token) {
+    let span = token.span.start..token.span.end;
+    match usages
+        .iter_mut()
+        .find(|usage| usage.name == token.span.text)
+    {
+        Some(usage) => usage.spans.push(span),
+        None => usages.push(Usage {
+            name: token.span.text.to_string(),
+            spans: vec![span],
+        }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::to_ast;
+
+    use super::{analyze, analyze_with_functions, Analysis, Usage};
+
+    fn names(usages: &[Usage]) -> Vec<&str> {
+        usages.iter().map(|usage| usage.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_variables() {
+        let analysis = analyze(&to_ast("alpha x^2 + 3.5y - x_1 = text(abc)"));
+
+        assert_eq!(names(&analysis.variables), vec!["alpha", "x", "y"]);
+        assert_eq!(analysis.variables[1].spans, vec![6..7, 19..20]);
+        assert_eq!(names(&analysis.numbers), vec!["2", "3.5", "1"]);
+        assert_eq!(names(&analysis.operators), vec!["+", "-", "=", "text"]);
+        assert!(analysis.bound.is_empty());
+    }
+
+    #[test]
+    fn test_functions() {
+        let analysis = analyze(&to_ast("sin(x) + f(y) + h(z)"));
+        assert_eq!(names(&analysis.functions), vec!["sin", "f"]);
+        assert_eq!(names(&analysis.variables), vec!["x", "y", "h", "z"]);
+
+        let analysis = analyze_with_functions(&to_ast("sin(x) + h(z) + h z"), &|name| name == "h");
+        assert_eq!(analysis.functions[1].spans, vec![9..10]);
+        assert_eq!(analysis.variables[2].spans, vec![16..17]);
+    }
+
+    #[test]
+    fn test_bound() {
+        let analysis = analyze(&to_ast("sum_(i=1)^n i^2 + i"));
+        assert_eq!(analysis.bound[0].spans, vec![5..6, 12..13]);
+        assert_eq!(names(&analysis.variables), vec!["n", "i"]);
+
+        let analysis = analyze(&to_ast("int_0^x t f(t) dt + lim_(h->0) h"));
+        assert_eq!(names(&analysis.bound), vec!["t", "h"]);
+        assert_eq!(analysis.bound[0].spans, vec![8..9, 12..13, 16..17]);
+        assert_eq!(names(&analysis.variables), vec!["x"]);
+        assert_eq!(names(&analysis.operators), vec!["int", "+", "lim", "->"]);
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(analyze(&to_ast("")), Analysis::default());
+    }
+}
@@ -7,9 +7,12 @@
 
 pub mod predictive;
 
-use std::collections::VecDeque;
+use std::{collections::VecDeque, ops::Range};
 
-use crate::tokens::Token;
+use crate::tokens::{
+    types::{LBrace, RBrace, TokenType},
+    Token,
+};
 
 #[derive(Debug, Clone)]
 pub struct AST<'a> {
@@ -76,6 +79,7 @@ pub enum Literal<'a> {
 
 #[derive(Debug, Clone)]
 pub struct Table<'a> {
+    /// Columns with a vertical line in front, from `|` columns
     pub seperators: Vec<usize>,
     pub l_brace: Token<'a>,
     // first vec seperates tables that need seperation by a seperator
@@ -89,3 +93,264 @@ pub struct Table<'a> {
 pub struct TableRow<'a> {
     pub cols: Vec<Expressions<'a>>,
 }
+
+///
+/// What a table stands for, see [`Table::kind`].
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableKind {
+    Matrix,
+    /// A single column, `[[1],[2]]`
+    ColumnVector,
+    /// Columns split by `|`, `[[1,|,2],[3,|,4]]`
+    AugmentedMatrix,
+    /// Value and condition per row, `{(x, x >= 0),(-x, x < 0):}`
+    Cases,
+    /// A relation in every row, `{(2x,+,y,=,3),(x,-,y,=,0):}`
+    EquationSystem,
+}
+
+///
+/// Horizontal alignment of the cells of a column.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnAlign {
+    Left,
+    Center,
+    Right,
+}
+
+impl<'a> Table<'a> {
+    ///
+    /// Classifies the table by its separators, brackets and contents.
+    ///
+    pub fn kind(&self) -> TableKind {
+        let cases = matches!(self.l_brace.token_type, TokenType::LBrace(LBrace::LBrace))
+            && matches!(
+                self.r_brace.token_type,
+                TokenType::RBrace(RBrace::RColonBrace)
+            );
+
+        if !self.seperators.is_empty() {
+            TableKind::AugmentedMatrix
+        } else if cases && self.rows.iter().all(|row| row.cols.len() == 2) {
+            TableKind::Cases
+        } else if self.rows.iter().all(|row| {
+            row.cols
+                .iter()
+                .flat_map(|col| col.expressions.iter())
+                .any(is_relation)
+        }) {
+            TableKind::EquationSystem
+        } else if self.rows.iter().all(|row| row.cols.len() == 1) {
+            TableKind::ColumnVector
+        } else {
+            TableKind::Matrix
+        }
+    }
+
+    ///
+    /// Number of rows and columns, rows of different lengths count with
+    /// the longest one.
+    ///
+    pub fn dimensions(&self) -> (usize, usize) {
+        let columns = self.rows.iter().map(|row| row.cols.len()).max();
+        (self.rows.len(), columns.unwrap_or(0))
+    }
+
+    ///
+    /// Alignment of every column. Equation systems with the operators in
+    /// columns of their own line up at the first relation, the columns in
+    /// front of it are aligned to the right and the ones after it to the
+    /// left. Cases are aligned to the left, everything else is centered.
+    ///
+    pub fn column_alignment(&self) -> Vec<ColumnAlign> {
+        let (_, columns) = self.dimensions();
+
+        match self.kind() {
+            TableKind::EquationSystem => {
+                let relation = (0..columns).find(|index| {
+                    is_operator_column(self, *index)
+                        && self.rows.iter().any(|row| {
+                            row.cols
+                                .get(*index)
+                                .is_some_and(|col| col.expressions.iter().any(is_relation))
+                        })
+                });
+
+                (0..columns)
+                    .map(|index| match relation {
+                        Some(_) if is_operator_column(self, index) => ColumnAlign::Center,
+                        Some(relation) if index < relation => ColumnAlign::Right,
+                        _ => ColumnAlign::Left,
+                    })
+                    .collect()
+            }
+            TableKind::Cases => vec![ColumnAlign::Left; columns],
+            _ => vec![ColumnAlign::Center; columns],
+        }
+    }
+}
+
+///
+/// Whether the cells of a column hold nothing but an operator or relation,
+/// like the `+` and `=` columns of `{(2x,+,y,=,3),(x,-,y,=,0):}`.
+///
+fn is_operator_column(table: &Table, index: usize) -> bool {
+    table.rows.iter().all(|row| match row.cols.get(index) {
+        Some(col) => matches!(
+            col.expressions.iter().collect::<Vec<_>>()[..],
+            [Expression::Literal(Literal::Literal(Token {
+                token_type: TokenType::Operation(_) | TokenType::Relational(_),
+                ..
+            }))]
+        ),
+        None => true,
+    })
+}
+
+fn is_relation(expression: &Expression) -> bool {
+    matches!(
+        expression,
+        Expression::Literal(Literal::Literal(Token {
+            token_type: TokenType::Relational(_),
+            ..
+        }))
+    )
+}
+
+impl<'a> Expressions<'a> {
+    ///
+    /// Calls `f` for every token of these expressions in source order.
+    ///
+    pub fn for_each_token<F: FnMut(&Token<'a>)>(&self, f: &mut F) {
+        self.expressions
+            .iter()
+            .for_each(|expression| expression.for_each_token(f));
+    }
+
+    ///
+    /// Byte range of the source these expressions have been parsed from.
+    ///
+    pub fn span(&self) -> Option<Range<usize>> {
+        let mut span = None;
+        self.for_each_token(&mut |token| extend_span(&mut span, token));
+        span
+    }
+}
+
+impl<'a> Expression<'a> {
+    ///
+    /// Calls `f` for every token of this expression in source order.
+    ///
+    pub fn for_each_token<F: FnMut(&Token<'a>)>(&self, f: &mut F) {
+        match self {
+            Expression::Frac(bi_expression)
+            | Expression::Sub(bi_expression)
+            | Expression::Pow(bi_expression) => {
+                bi_expression.expression_1.for_each_token(f);
+                bi_expression.expression_2.for_each_token(f);
+            }
+            Expression::SubPow(tri_expression) => {
+                tri_expression.expression_1.for_each_token(f);
+                tri_expression.expression_2.for_each_token(f);
+                tri_expression.expression_3.for_each_token(f);
+            }
+            Expression::Group(group) => {
+                f(&group.l_brace);
+                group.expressions.for_each_token(f);
+                f(&group.r_brace);
+            }
+            Expression::Unary(unary) => {
+                f(&unary.operator);
+                unary.expression.for_each_token(f);
+            }
+            Expression::Binary(binary) => {
+                f(&binary.operator);
+                binary.expression_1.for_each_token(f);
+                binary.expression_2.for_each_token(f);
+            }
+            Expression::Literal(Literal::Literal(token)) => f(token),
+            Expression::Literal(Literal::Table(table)) => {
+                f(&table.l_brace);
+                table
+                    .rows
+                    .iter()
+                    .flat_map(|row| row.cols.iter())
+                    .for_each(|col| col.for_each_token(f));
+                f(&table.r_brace);
+            }
+            Expression::Expressions(expressions) => expressions.for_each_token(f),
+            Expression::Unit => {}
+        }
+    }
+
+    ///
+    /// Byte range of the source this expression has been parsed from. Tokens
+    /// the parser made up for missing operands are not taken into account.
+    ///
+    pub fn span(&self) -> Option<Range<usize>> {
+        let mut span = None;
+        self.for_each_token(&mut |token| extend_span(&mut span, token));
+        span
+    }
+}
+
+fn extend_span(span: &mut Option<Range<usize>>, token: &Token) {
+    if token.token_type != TokenType::None {
+        *span = Some(match span.take() {
+            Some(span) => span.start.min(token.span.start)..span.end.max(token.span.end),
+            None => token.span.start..token.span.end,
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::to_ast;
+
+    use super::{ColumnAlign::*, Expression, Literal, TableKind};
+
+    fn table(asciimath: &str) -> (TableKind, (usize, usize)) {
+        match &to_ast(asciimath).expressions.expressions[0] {
+            Expression::Literal(Literal::Table(table)) => (table.kind(), table.dimensions()),
+            expression => panic!("not a table: {expression:?}"),
+        }
+    }
+
+    #[test]
+    fn test_table_kinds() {
+        assert_eq!(table("[[1,2],[3,4]]"), (TableKind::Matrix, (2, 2)));
+        assert_eq!(table("((1),(2),(3))"), (TableKind::ColumnVector, (3, 1)));
+        assert_eq!(
+            table("[[1,|,2,|,3],[4,|,5,|,6]]"),
+            (TableKind::AugmentedMatrix, (2, 3))
+        );
+        assert_eq!(
+            table("{(x, x > 0),(0, \"otherwise\"):}"),
+            (TableKind::Cases, (2, 2))
+        );
+        assert_eq!(
+            table("{(2x,+,17y,=,23),(x,-,y,=,5):}"),
+            (TableKind::EquationSystem, (2, 5))
+        );
+        assert_eq!(table("[[1,|,2],[3,4,5]]").1, (2, 3));
+        assert_eq!(table("[[1,2],[-,-],[3,4]]"), (TableKind::Matrix, (3, 2)));
+    }
+
+    #[test]
+    fn test_column_alignment() {
+        let alignment = |asciimath| match &to_ast(asciimath).expressions.expressions[0] {
+            Expression::Literal(Literal::Table(table)) => table.column_alignment(),
+            expression => panic!("not a table: {expression:?}"),
+        };
+
+        assert_eq!(
+            alignment("{(2x,+,17y,=,23),(x,-,y,=,5):}"),
+            vec![Right, Center, Right, Center, Left]
+        );
+        assert_eq!(alignment("{:(x+y=1),(x-y=3):}"), vec![Left]);
+        assert_eq!(alignment("{(x, x > 0),(0, \"else\"):}"), vec![Left, Left]);
+        assert_eq!(alignment("[[1,2],[3,4]]"), vec![Center, Center]);
+    }
+}
@@ -15,7 +15,7 @@ use super::{
     BiExpression, Binary, Expression, Expressions, Group, Literal, TriExpression, Unary, AST,
 };
 
-pub fn convert<'a>(cst: &'a PredictiveCST<'a>) -> AST<'a> {
+pub fn convert<'a>(cst: &PredictiveCST<'a>) -> AST<'a> {
     AST {
         expressions: convert_e(&cst.expression),
     }
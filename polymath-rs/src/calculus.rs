@@ -0,0 +1,903 @@
+//!
+//! # Calculus
+//!
+//! Symbolic differentiation and simplification of [semantic
+//! trees](crate::semantics). The results are trees again, they print as
+//! AsciiMath and can be rendered like any other input.
+//!
+//! ```
+//! use polymath_rs::{
+//!     calculus::{differentiate, simplify},
+//!     semantics::build,
+//!     to_ast, to_math_ml,
+//! };
+//!
+//! let f = build(&to_ast("x^3 + sin(2x)")).unwrap();
+//! let derivative = simplify(&differentiate(&f, "x").unwrap());
+//!
+//! assert_eq!(derivative.to_string(), "3x^2 + 2cos(2x)");
+//! assert!(to_math_ml(&derivative.to_string()).contains("<mi>cos</mi>"));
+//! ```
+//!
+
+use std::{fmt, ops::Range};
+
+use crate::{
+    semantics::{BigOperator, Callee, Kind, Node, Operator},
+    symbols,
+    tokens::types::{Function, TokenType, UnaryOperator},
+};
+
+/// Rounds of rewriting [simplify] does at most before settling
+const MAX_ROUNDS: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    /// What has no derivative here, e.g. `relations` or `` `floor` ``
+    pub construct: String,
+    /// Byte range of the source, `None` if the node has been made up
+    pub span: Option<Range<usize>>,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cannot differentiate {}", self.construct)
+    }
+}
+
+impl std::error::Error for Error {}
+
+///
+/// The derivative of `node` with respect to `variable`, spelled like in the
+/// tree, e.g. `x` or `x_1`. The result follows the rules literally and is
+/// full of `0` and `1` factors, see [simplify].
+///
+pub fn differentiate(node: &Node, variable: &str) -> Result<Node, Error> {
+    let d = |node: &Node| differentiate(node, variable);
+    let depends = |node: &Node| depends_on(node, variable);
+    let unsupported = |construct: String| {
+        Err(Error {
+            construct,
+            span: node.span.clone(),
+        })
+    };
+
+    Ok(match &node.kind {
+        Kind::Number(_) | Kind::Constant(_) => number(0.0),
+        Kind::Identifier(name) => number(if name == variable { 1.0 } else { 0.0 }),
+        Kind::Negate(operand) => negate(d(operand)?),
+        Kind::Binary(Operator::Add, left, right) => add(d(left)?, d(right)?),
+        Kind::Binary(Operator::Subtract, left, right) => subtract(d(left)?, d(right)?),
+        Kind::Binary(Operator::Multiply { .. }, left, right) => add(
+            multiply(d(left)?, (**right).clone()),
+            multiply((**left).clone(), d(right)?),
+        ),
+        Kind::Binary(Operator::Divide, left, right) => divide(
+            subtract(
+                multiply(d(left)?, (**right).clone()),
+                multiply((**left).clone(), d(right)?),
+            ),
+            power((**right).clone(), number(2.0)),
+        ),
+        Kind::Binary(Operator::Power, base, exponent) => {
+            let (base, exponent) = (&**base, &**exponent);
+            match (depends(base), depends(exponent)) {
+                (_, false) => multiply(
+                    multiply(
+                        exponent.clone(),
+                        power(base.clone(), subtract(exponent.clone(), number(1.0))),
+                    ),
+                    d(base)?,
+                ),
+                (false, true) => multiply(multiply(node.clone(), ln(base.clone())), d(exponent)?),
+                // u^v = e^(v ln u)
+                (true, true) => multiply(
+                    node.clone(),
+                    add(
+                        multiply(d(exponent)?, ln(base.clone())),
+                        divide(multiply(exponent.clone(), d(base)?), base.clone()),
+                    ),
+                ),
+            }
+        }
+        Kind::Binary(Operator::Modulo, ..) => return unsupported("`mod`".to_string()),
+        Kind::Apply {
+            function: Callee::Builtin(function),
+            subscript,
+            arguments,
+        } if arguments.len() == 1 => {
+            if subscript.as_deref().is_some_and(depends) {
+                return unsupported("logarithms to a variable base".to_string());
+            }
+            let outer = match derivative(function, &arguments[0], subscript.as_deref()) {
+                Some(outer) => outer,
+                None => return unsupported(format!("`{}`", spelling(function))),
+            };
+            multiply(outer, d(&arguments[0])?)
+        }
+        Kind::Apply {
+            function: Callee::Builtin(function),
+            ..
+        } => return unsupported(format!("`{}`", spelling(function))),
+        Kind::Apply {
+            function: Callee::Named(name),
+            ..
+        } => return unsupported(format!("`{name}`")),
+        Kind::Root {
+            index: None,
+            radicand,
+        } => divide(d(radicand)?, multiply(number(2.0), node.clone())),
+        // root(n)(u) = u^(1/n)
+        Kind::Root {
+            index: Some(index),
+            radicand,
+        } if !depends(index) => d(&power(
+            (**radicand).clone(),
+            divide(number(1.0), (**index).clone()),
+        ))?,
+        Kind::Root { .. } => return unsupported("roots of a variable degree".to_string()),
+        Kind::Unary(UnaryOperator::Abs, operand) => {
+            multiply(divide((**operand).clone(), node.clone()), d(operand)?)
+        }
+        Kind::Unary(operator, _) => {
+            let operator = TokenType::UnaryOperator(operator.clone());
+            return unsupported(format!(
+                "`{}`",
+                symbols::spelling(&operator).unwrap_or_default()
+            ));
+        }
+        Kind::BigOperator { .. } if !depends(node) => number(0.0),
+        // the derivative of a sum is the sum of the derivatives
+        Kind::BigOperator {
+            operator: BigOperator::Sum,
+            variable: bound,
+            lower,
+            upper,
+            body,
+        } if lower
+            .iter()
+            .chain(upper.iter())
+            .all(|bound| !depends(bound)) =>
+        {
+            Node::new(
+                Kind::BigOperator {
+                    operator: BigOperator::Sum,
+                    variable: bound.clone(),
+                    lower: lower.clone(),
+                    upper: upper.clone(),
+                    body: Box::new(d(body)?),
+                },
+                None,
+            )
+        }
+        Kind::BigOperator { .. } => return unsupported("big operators".to_string()),
+        Kind::Tuple(nodes) => Node::new(
+            Kind::Tuple(nodes.iter().map(d).collect::<Result<_, _>>()?),
+            None,
+        ),
+        Kind::Table(rows) => Node::new(
+            Kind::Table(
+                rows.iter()
+                    .map(|row| row.iter().map(d).collect())
+                    .collect::<Result<_, _>>()?,
+            ),
+            None,
+        ),
+        Kind::Relation(..) => return unsupported("relations".to_string()),
+        Kind::Text(_) => return unsupported("text".to_string()),
+        Kind::Other(source) => return unsupported(format!("`{source}`")),
+    })
+}
+
+///
+/// The derivative of `function` at `u`, `base` is the subscript of `log`.
+///
+fn derivative(function: &Function, u: &Node, base: Option<&Node>) -> Option<Node> {
+    let f = |function| apply(function, u.clone());
+    let squared = |node| power(node, number(2.0));
+
+    Some(match function {
+        Function::Sin => f(Function::Cos),
+        Function::Cos => negate(f(Function::Sin)),
+        Function::Tan => squared(f(Function::Sec)),
+        Function::Sec => multiply(f(Function::Sec), f(Function::Tan)),
+        Function::Csc => negate(multiply(f(Function::Csc), f(Function::Cot))),
+        Function::Cot => negate(squared(f(Function::Csc))),
+        Function::Arcsin | Function::Arccos => {
+            let derivative = divide(
+                number(1.0),
+                Node::new(
+                    Kind::Root {
+                        index: None,
+                        radicand: Box::new(subtract(number(1.0), squared(u.clone()))),
+                    },
+                    None,
+                ),
+            );
+            match function {
+                Function::Arcsin => derivative,
+                _ => negate(derivative),
+            }
+        }
+        Function::Arctan => divide(number(1.0), add(number(1.0), squared(u.clone()))),
+        Function::Sinh => f(Function::Cosh),
+        Function::Cosh => f(Function::Sinh),
+        Function::Tanh => squared(f(Function::Sech)),
+        Function::Sech => negate(multiply(f(Function::Sech), f(Function::Tanh))),
+        Function::Csch => negate(multiply(f(Function::Csch), f(Function::Coth))),
+        Function::Coth => negate(squared(f(Function::Csch))),
+        Function::Exp => f(Function::Exp),
+        Function::Ln => divide(number(1.0), u.clone()),
+        Function::Log => divide(
+            number(1.0),
+            multiply(u.clone(), ln(base.cloned().unwrap_or_else(|| number(10.0)))),
+        ),
+        _ => return None,
+    })
+}
+
+///
+/// Whether `variable` occurs free in `node`.
+///
+fn depends_on(node: &Node, variable: &str) -> bool {
+    match &node.kind {
+        Kind::Identifier(name) => name == variable,
+        Kind::BigOperator {
+            variable: Some(bound),
+            lower,
+            upper,
+            ..
+        } if bound == variable => lower
+            .iter()
+            .chain(upper.iter())
+            .any(|bound| depends_on(bound, variable)),
+        _ => node
+            .children()
+            .into_iter()
+            .any(|child| depends_on(child, variable)),
+    }
+}
+
+///
+/// Rewrites `node` into a simpler tree of the same meaning. Constants are
+/// folded, fractions of integers exactly, neutral elements like `+0`, `*1`
+/// and `^1` are dropped and like terms collected, `x + 2x` becomes `3x` and
+/// `x^2/x` becomes `x`.
+///
+pub fn simplify(node: &Node) -> Node {
+    let mut node = node.clone();
+
+    for _ in 0..MAX_ROUNDS {
+        let next = round(node.clone());
+        if next.same_as(&node) {
+            return next;
+        }
+        node = next;
+    }
+
+    node
+}
+
+///
+/// Rewrites the children of `node`, then `node` itself.
+///
+fn round(mut node: Node) -> Node {
+    for child in node.children_mut() {
+        *child = round(std::mem::replace(child, number(0.0)));
+    }
+
+    rewrite(&node).unwrap_or(node)
+}
+
+///
+/// The simpler form of `node`, `None` if there is none.
+///
+fn rewrite(node: &Node) -> Option<Node> {
+    if let Some(constant) = constant(node) {
+        return (!matches!(node.kind, Kind::Number(_))).then(|| constant.node());
+    }
+
+    match &node.kind {
+        Kind::Negate(operand) => match &operand.kind {
+            Kind::Negate(operand) => Some((**operand).clone()),
+            _ => None,
+        },
+        Kind::Binary(Operator::Add | Operator::Subtract, ..) => Some(sum(node)),
+        Kind::Binary(Operator::Multiply { .. } | Operator::Divide, ..) => Some(product(node)),
+        Kind::Binary(Operator::Power, base, exponent) => {
+            let exponent_value = constant(exponent);
+            match (constant(base), exponent_value) {
+                (_, Some(exponent)) if exponent.is_zero() => Some(number(1.0)),
+                (_, Some(exponent)) if exponent.is_one() => Some((**base).clone()),
+                (Some(base), _) if base.is_one() => Some(number(1.0)),
+                (Some(base), Some(exponent)) => base.pow(exponent).map(Coefficient::node),
+                // (x^a)^n = x^(a n) for whole n
+                (None, Some(Coefficient::Ratio(_, 1))) => match &base.kind {
+                    Kind::Binary(Operator::Power, inner, inner_exponent) => Some(power(
+                        (**inner).clone(),
+                        multiply((**inner_exponent).clone(), (**exponent).clone()),
+                    )),
+                    _ => None,
+                },
+                _ => None,
+            }
+        }
+        Kind::Apply {
+            function: Callee::Builtin(function),
+            subscript,
+            arguments,
+        } if arguments.len() == 1 => {
+            let argument = &arguments[0];
+            let value = constant(argument).map(Coefficient::value);
+            let base = subscript
+                .as_deref()
+                .cloned()
+                .unwrap_or_else(|| number(10.0));
+
+            let result = match (function, value) {
+                (Function::Ln | Function::Log, Some(1.0)) => 0.0,
+                (Function::Ln, _) if argument.kind == Kind::Identifier("e".to_string()) => 1.0,
+                (Function::Log, _) if argument.same_as(&base) => 1.0,
+                (Function::Exp | Function::Cos | Function::Cosh, Some(0.0)) => 1.0,
+                (
+                    Function::Sin
+                    | Function::Tan
+                    | Function::Arcsin
+                    | Function::Arctan
+                    | Function::Sinh
+                    | Function::Tanh,
+                    Some(0.0),
+                ) => 0.0,
+                _ => return None,
+            };
+            Some(number(result))
+        }
+        Kind::Root {
+            index: None,
+            radicand,
+        } => match constant(radicand)? {
+            Coefficient::Ratio(radicand, 1) if radicand >= 0 => {
+                let root = (radicand as f64).sqrt().round() as i64;
+                (root * root == radicand).then(|| number(root as f64))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+///
+/// Collects the like terms of a sum, constants last.
+///
+fn sum(node: &Node) -> Node {
+    let mut terms: Vec<(Coefficient, Option<Node>)> = Vec::new();
+    let mut summands = Vec::new();
+    summands_of(node, false, &mut summands);
+
+    for (summand, negative) in summands {
+        let (mut coefficient, rest) = split(summand);
+        if negative {
+            coefficient = coefficient.neg();
+        }
+
+        let like = terms.iter_mut().find(|(_, other)| match (&rest, other) {
+            (Some(rest), Some(other)) => rest.same_as(other),
+            (None, None) => true,
+            _ => false,
+        });
+        match like {
+            Some((sum, _)) => *sum = sum.add(coefficient),
+            None => terms.push((coefficient, rest)),
+        }
+    }
+
+    terms.retain(|(coefficient, _)| !coefficient.is_zero());
+    terms.sort_by_key(|(_, rest)| rest.is_none());
+
+    let mut terms = terms.into_iter();
+    let Some((coefficient, rest)) = terms.next() else {
+        return number(0.0);
+    };
+
+    terms.fold(
+        term(coefficient, rest),
+        |sum, (coefficient, rest)| match coefficient.is_negative() {
+            true => subtract(sum, term(coefficient.neg(), rest)),
+            false => add(sum, term(coefficient, rest)),
+        },
+    )
+}
+
+fn summands_of<'n>(node: &'n Node, negative: bool, summands: &mut Vec<(&'n Node, bool)>) {
+    match &node.kind {
+        Kind::Binary(Operator::Add, left, right) => {
+            summands_of(left, negative, summands);
+            summands_of(right, negative, summands);
+        }
+        Kind::Binary(Operator::Subtract, left, right) => {
+            summands_of(left, negative, summands);
+            summands_of(right, !negative, summands);
+        }
+        Kind::Negate(operand) => summands_of(operand, !negative, summands),
+        _ => summands.push((node, negative)),
+    }
+}
+
+///
+/// Splits a term into its numeric coefficient and the rest, `None` for
+/// constants.
+///
+fn split(node: &Node) -> (Coefficient, Option<Node>) {
+    if let Some(constant) = constant(node) {
+        return (constant, None);
+    }
+
+    match &node.kind {
+        Kind::Negate(operand) => {
+            let (coefficient, rest) = split(operand);
+            (coefficient.neg(), rest)
+        }
+        Kind::Binary(Operator::Multiply { .. }, left, right) => match constant(left) {
+            Some(factor) => {
+                let (coefficient, rest) = split(right);
+                (factor.mul(coefficient), rest)
+            }
+            None => (Coefficient::ONE, Some(node.clone())),
+        },
+        _ => (Coefficient::ONE, Some(node.clone())),
+    }
+}
+
+///
+/// Multiplies out a product or quotient, collecting powers of the same
+/// base. Factors with a negative exponent end up in the denominator.
+///
+fn product(node: &Node) -> Node {
+    let mut coefficient = Coefficient::ONE;
+    let mut factors: Vec<(Node, Node)> = Vec::new();
+    factors_of(node, false, &mut coefficient, &mut factors);
+
+    // `0/x` and `0/0` may be undefined, only divisions by nonzero constants
+    // have gone into the coefficient
+    if coefficient.is_zero() {
+        let defined = factors
+            .iter()
+            .all(|(_, exponent)| constant(exponent).is_some_and(|e| !e.is_negative()));
+        return match defined {
+            true => number(0.0),
+            false => node.clone(),
+        };
+    }
+
+    let mut numerator = Vec::new();
+    let mut denominator = Vec::new();
+    for (base, exponent) in factors {
+        match constant(&exponent) {
+            Some(exponent) if exponent.is_zero() => {}
+            Some(exponent) if exponent.is_one() => numerator.push(base),
+            Some(exponent) if exponent.is_negative() && exponent.neg().is_one() => {
+                denominator.push(base)
+            }
+            Some(exponent) if exponent.is_negative() => {
+                denominator.push(power(base, exponent.neg().node()))
+            }
+            _ => numerator.push(power(base, exponent)),
+        }
+    }
+
+    let multiplied = |factors: Vec<Node>| factors.into_iter().reduce(multiply);
+    if denominator.is_empty() {
+        return term(coefficient, multiplied(numerator));
+    }
+
+    // coefficients go into the fraction, `1/(2 sqrt(x))` rather than `1/2 1/sqrt(x)`
+    let magnitude = coefficient.abs();
+    let (upper, lower) = match magnitude {
+        Coefficient::Ratio(numerator, denominator) => (numerator as f64, denominator as f64),
+        Coefficient::Real(value) => (value, 1.0),
+    };
+    if upper != 1.0 {
+        numerator.insert(0, number(upper));
+    }
+    if lower != 1.0 {
+        denominator.insert(0, number(lower));
+    }
+
+    let fraction = divide(
+        multiplied(numerator).unwrap_or_else(|| number(1.0)),
+        multiplied(denominator).unwrap_or_else(|| number(1.0)),
+    );
+    match coefficient.is_negative() {
+        true => negate(fraction),
+        false => fraction,
+    }
+}
+
+fn factors_of(
+    node: &Node,
+    inverse: bool,
+    coefficient: &mut Coefficient,
+    factors: &mut Vec<(Node, Node)>,
+) {
+    if let Some(constant) = constant(node) {
+        let constant = match inverse {
+            true => match constant.recip() {
+                Some(constant) => constant,
+                // keep division by zero as written
+                None => return factors.push((node.clone(), number(-1.0))),
+            },
+            false => constant,
+        };
+        *coefficient = coefficient.mul(constant);
+        return;
+    }
+
+    let (base, exponent) = match &node.kind {
+        Kind::Binary(Operator::Multiply { .. }, left, right) => {
+            factors_of(left, inverse, coefficient, factors);
+            return factors_of(right, inverse, coefficient, factors);
+        }
+        Kind::Binary(Operator::Divide, left, right) => {
+            factors_of(left, inverse, coefficient, factors);
+            return factors_of(right, !inverse, coefficient, factors);
+        }
+        Kind::Negate(operand) => {
+            *coefficient = coefficient.neg();
+            return factors_of(operand, inverse, coefficient, factors);
+        }
+        Kind::Binary(Operator::Power, base, exponent) => ((**base).clone(), (**exponent).clone()),
+        _ => (node.clone(), number(1.0)),
+    };
+    let exponent = match inverse {
+        true => match constant(&exponent) {
+            Some(constant) => constant.neg().node(),
+            None => negate(exponent),
+        },
+        false => exponent,
+    };
+
+    match factors.iter_mut().find(|(other, _)| other.same_as(&base)) {
+        Some((_, sum)) => {
+            *sum = match (constant(sum), constant(&exponent)) {
+                (Some(a), Some(b)) => a.add(b).node(),
+                _ => add(sum.clone(), exponent),
+            }
+        }
+        None => factors.push((base, exponent)),
+    }
+}
+
+///
+/// `coefficient` times `rest`, signs in front.
+///
+fn term(coefficient: Coefficient, rest: Option<Node>) -> Node {
+    let magnitude = coefficient.abs();
+    let node = match rest {
+        None => magnitude.node(),
+        Some(rest) if magnitude.is_one() => rest,
+        Some(rest) => prepend(magnitude.node(), rest),
+    };
+
+    match coefficient.is_negative() {
+        true => negate(node),
+        false => node,
+    }
+}
+
+///
+/// `factor` times `product`, as the leftmost factor so that `2 a x` stays
+/// a flat product.
+///
+fn prepend(factor: Node, product: Node) -> Node {
+    match product.kind {
+        Kind::Binary(Operator::Multiply { implicit: true }, left, right) => {
+            multiply(prepend(factor, *left), *right)
+        }
+        kind => multiply(factor, Node::new(kind, product.span)),
+    }
+}
+
+///
+/// The value of a numeric node like `2`, `-3` or `1/3`.
+///
+fn constant(node: &Node) -> Option<Coefficient> {
+    match &node.kind {
+        Kind::Number(number) => Some(Coefficient::new(*number)),
+        Kind::Negate(operand) => constant(operand).map(Coefficient::neg),
+        Kind::Binary(Operator::Divide, left, right) => {
+            Some(constant(left)?.mul(constant(right)?.recip()?))
+        }
+        _ => None,
+    }
+}
+
+///
+/// A number kept as an exact fraction while its parts fit.
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Coefficient {
+    /// Reduced, the denominator is positive
+    Ratio(i64, i64),
+    Real(f64),
+}
+
+impl Coefficient {
+    const ONE: Coefficient = Coefficient::Ratio(1, 1);
+
+    fn new(number: f64) -> Self {
+        match number.fract() == 0.0 && number.abs() <= i64::MAX as f64 / 2.0 {
+            true => Coefficient::Ratio(number as i64, 1),
+            false => Coefficient::Real(number),
+        }
+    }
+
+    fn ratio(numerator: i128, denominator: i128) -> Self {
+        let divisor = gcd(numerator, denominator) * denominator.signum();
+        let (numerator, denominator) = (numerator / divisor, denominator / divisor);
+
+        match (i64::try_from(numerator), i64::try_from(denominator)) {
+            (Ok(numerator), Ok(denominator)) => Coefficient::Ratio(numerator, denominator),
+            _ => Coefficient::Real(numerator as f64 / denominator as f64),
+        }
+    }
+
+    fn value(self) -> f64 {
+        match self {
+            Coefficient::Ratio(numerator, denominator) => numerator as f64 / denominator as f64,
+            Coefficient::Real(value) => value,
+        }
+    }
+
+    fn add(self, other: Self) -> Self {
+        match (self, other) {
+            (Coefficient::Ratio(a, b), Coefficient::Ratio(c, d)) => Coefficient::ratio(
+                a as i128 * d as i128 + c as i128 * b as i128,
+                b as i128 * d as i128,
+            ),
+            _ => Coefficient::Real(self.value() + other.value()),
+        }
+    }
+
+    fn mul(self, other: Self) -> Self {
+        match (self, other) {
+            (Coefficient::Ratio(a, b), Coefficient::Ratio(c, d)) => {
+                Coefficient::ratio(a as i128 * c as i128, b as i128 * d as i128)
+            }
+            _ => Coefficient::Real(self.value() * other.value()),
+        }
+    }
+
+    fn neg(self) -> Self {
+        match self {
+            Coefficient::Ratio(numerator, denominator) => {
+                Coefficient::Ratio(-numerator, denominator)
+            }
+            Coefficient::Real(value) => Coefficient::Real(-value),
+        }
+    }
+
+    fn abs(self) -> Self {
+        match self.is_negative() {
+            true => self.neg(),
+            false => self,
+        }
+    }
+
+    fn recip(self) -> Option<Self> {
+        match self {
+            _ if self.is_zero() => None,
+            Coefficient::Ratio(numerator, denominator) => {
+                Some(Coefficient::ratio(denominator as i128, numerator as i128))
+            }
+            Coefficient::Real(value) => Some(Coefficient::Real(1.0 / value)),
+        }
+    }
+
+    ///
+    /// `self` to a whole power, `None` for other exponents, which would
+    /// leave the rationals.
+    ///
+    fn pow(self, exponent: Self) -> Option<Self> {
+        let Coefficient::Ratio(exponent, 1) = exponent else {
+            return None;
+        };
+        if exponent.unsigned_abs() > 64 {
+            return None;
+        }
+
+        let base = match exponent < 0 {
+            true => self.recip()?,
+            false => self,
+        };
+        Some((0..exponent.unsigned_abs()).fold(Coefficient::ONE, |power, _| power.mul(base)))
+    }
+
+    fn is_zero(self) -> bool {
+        self.value() == 0.0
+    }
+
+    fn is_one(self) -> bool {
+        self.value() == 1.0
+    }
+
+    fn is_negative(self) -> bool {
+        self.value() < 0.0
+    }
+
+    fn node(self) -> Node {
+        let magnitude = match self.abs() {
+            Coefficient::Ratio(numerator, 1) => number(numerator as f64),
+            Coefficient::Ratio(numerator, denominator) => {
+                divide(number(numerator as f64), number(denominator as f64))
+            }
+            Coefficient::Real(value) => number(value),
+        };
+
+        match self.is_negative() {
+            true => negate(magnitude),
+            false => magnitude,
+        }
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    match b {
+        0 => a.abs().max(1),
+        _ => gcd(b, a % b),
+    }
+}
+
+fn number(value: f64) -> Node {
+    Node::new(Kind::Number(value), None)
+}
+
+fn negate(operand: Node) -> Node {
+    Node::new(Kind::Negate(Box::new(operand)), None)
+}
+
+fn binary(operator: Operator, left: Node, right: Node) -> Node {
+    Node::new(
+        Kind::Binary(operator, Box::new(left), Box::new(right)),
+        None,
+    )
+}
+
+fn add(left: Node, right: Node) -> Node {
+    binary(Operator::Add, left, right)
+}
+
+fn subtract(left: Node, right: Node) -> Node {
+    binary(Operator::Subtract, left, right)
+}
+
+fn multiply(left: Node, right: Node) -> Node {
+    binary(Operator::Multiply { implicit: true }, left, right)
+}
+
+fn divide(left: Node, right: Node) -> Node {
+    binary(Operator::Divide, left, right)
+}
+
+fn power(base: Node, exponent: Node) -> Node {
+    binary(Operator::Power, base, exponent)
+}
+
+fn apply(function: Function, argument: Node) -> Node {
+    Node::new(
+        Kind::Apply {
+            function: Callee::Builtin(function),
+            subscript: None,
+            arguments: vec![argument],
+        },
+        None,
+    )
+}
+
+fn ln(argument: Node) -> Node {
+    apply(Function::Ln, argument)
+}
+
+fn spelling(function: &Function) -> &'static str {
+    symbols::spelling(&TokenType::Function(function.clone())).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{semantics::build, to_ast};
+
+    use super::{differentiate, simplify, Error};
+
+    fn derivative(asciimath: &str) -> String {
+        let node = build(&to_ast(asciimath)).unwrap();
+        simplify(&differentiate(&node, "x").unwrap()).to_string()
+    }
+
+    fn simplified(asciimath: &str) -> String {
+        simplify(&build(&to_ast(asciimath)).unwrap()).to_string()
+    }
+
+    #[test]
+    fn test_simplify() {
+        assert_eq!(simplified("0 + 1 x^1 + 2 * 3"), "x + 6");
+        assert_eq!(simplified("2x + y - x + 3y - y"), "x + 3y");
+        assert_eq!(simplified("x x^2 * 2"), "2x^3");
+        assert_eq!(simplified("x^2/x - 1/2 + 2/4"), "x");
+        assert_eq!(simplified("(6x)/(4y)"), "(3x)/(2y)");
+        assert_eq!(simplified("-(-x) * 0 + ln(e) + 2^(-1)"), "3/2");
+        assert_eq!(simplified("sqrt(16) + sin(0) + x^0"), "5");
+    }
+
+    #[test]
+    fn test_zero_quotients() {
+        assert_eq!(simplified("0/0"), "0/0");
+        assert_eq!(simplified("0/(x-x)"), "0/0");
+        assert_eq!(simplified("0/x"), "0/x");
+        assert_eq!(simplified("(0 x)/2 + 0/(-3)"), "0");
+    }
+
+    #[test]
+    fn test_polynomials() {
+        assert_eq!(derivative("x^3 + 2x^2 - 5x + 7"), "3x^2 + 4x - 5");
+        assert_eq!(derivative("(x+1)(x-1)"), "2x");
+        assert_eq!(derivative("a x^2 + b"), "2a x");
+        assert_eq!(derivative("x^n"), "n x^(n - 1)");
+    }
+
+    #[test]
+    fn test_functions() {
+        assert_eq!(derivative("sin(x)^2"), "2sin(x) cos(x)");
+        assert_eq!(derivative("cos(3x)"), "-3sin(3x)");
+        assert_eq!(derivative("e^(2x)"), "2e^(2x)");
+        assert_eq!(derivative("ln(x^2 + 1)"), "(2x)/(x^2 + 1)");
+        assert_eq!(derivative("log_2 x"), "1/(x ln(2))");
+        assert_eq!(derivative("x sin x"), "sin(x) + x cos(x)");
+        assert_eq!(derivative("arctan x"), "1/(x^2 + 1)");
+        assert_eq!(derivative("ln(sin x)"), "(cos x)/(sin x)");
+    }
+
+    #[test]
+    fn test_roots_and_quotients() {
+        assert_eq!(derivative("sqrt x"), "1/(2sqrt(x))");
+        assert_eq!(derivative("root(3)(x)"), "1/(3x^(2/3))");
+        assert_eq!(derivative("1/x"), "-1/x^2");
+        assert_eq!(derivative("(sin x)/x"), "(cos(x) x - sin(x))/x^2");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for asciimath in ["x^3 sin(x)", "sqrt(x^2 + 1)", "x/(1 + x)", "e^x ln(x)"] {
+            let node = build(&to_ast(asciimath)).unwrap();
+            let derivative = simplify(&differentiate(&node, "x").unwrap());
+            let printed = derivative.to_string();
+
+            assert!(
+                build(&to_ast(&printed)).unwrap().same_as(&derivative),
+                "{printed}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let error =
+            |asciimath| differentiate(&build(&to_ast(asciimath)).unwrap(), "x").unwrap_err();
+
+        assert_eq!(
+            error("y = x^2"),
+            Error {
+                construct: "relations".to_string(),
+                span: Some(0..7)
+            }
+        );
+        assert_eq!(error("x < 1").to_string(), "cannot differentiate relations");
+        assert_eq!(error("(x = 1) + 2").span, Some(0..7));
+
+        assert_eq!(
+            error("x + floor(x)"),
+            Error {
+                construct: "`floor`".to_string(),
+                span: Some(4..12)
+            }
+        );
+    }
+}
@@ -32,7 +32,7 @@ use crate::tokens::{types::TokenType, Token};
 
 pub mod predictive;
 
-pub type TokenStream<'a> = &'a [Token<'a>];
+pub type TokenStream<'s, 'a> = &'s [Token<'a>];
 
 #[derive(Debug)]
 pub struct Cursor {
@@ -49,11 +49,11 @@ impl Cursor {
         self.get_pos()
     }
 
-    fn parse<'a, F: Fn(&'a TokenType) -> bool + 'static>(
+    fn parse<'s, 'a, F: Fn(&'s TokenType) -> bool + 'static>(
         &self,
-        tokens: TokenStream<'a>,
+        tokens: TokenStream<'s, 'a>,
         func: F,
-    ) -> Option<&'a Token<'a>> {
+    ) -> Option<&'s Token<'a>> {
         if let Some(token) = self.peek(tokens, func) {
             self.advance(tokens);
             Some(token)
@@ -62,16 +62,16 @@ impl Cursor {
         }
     }
 
-    fn advance<'a>(&self, tokens: TokenStream<'a>) -> Option<&'a Token<'a>> {
+    fn advance<'s, 'a>(&self, tokens: TokenStream<'s, 'a>) -> Option<&'s Token<'a>> {
         self.set_pos(self.get_pos() + 1);
         tokens.get(self.get_pos() - 1)
     }
 
-    fn slice_to<'a>(
+    fn slice_to<'s, 'a>(
         &self,
-        tokens: TokenStream<'a>,
+        tokens: TokenStream<'s, 'a>,
         token: &Token<'a>,
-    ) -> Option<(Cursor, TokenStream<'a>)> {
+    ) -> Option<(Cursor, TokenStream<'s, 'a>)> {
         let token_pos = tokens
             .iter()
             .enumerate()
@@ -88,20 +88,20 @@ impl Cursor {
         })
     }
 
-    fn peek<'a, F: Fn(&'a TokenType) -> bool>(
+    fn peek<'s, 'a, F: Fn(&'s TokenType) -> bool>(
         &self,
-        tokens: TokenStream<'a>,
+        tokens: TokenStream<'s, 'a>,
         func: F,
-    ) -> Option<&'a Token<'a>> {
+    ) -> Option<&'s Token<'a>> {
         self.peek_n(tokens, 0, func)
     }
 
-    fn peek_n<'a, F: Fn(&'a TokenType) -> bool>(
+    fn peek_n<'s, 'a, F: Fn(&'s TokenType) -> bool>(
         &self,
-        tokens: TokenStream<'a>,
+        tokens: TokenStream<'s, 'a>,
         offset: usize,
         func: F,
-    ) -> Option<&'a Token<'a>> {
+    ) -> Option<&'s Token<'a>> {
         tokens
             .get(self.get_pos() + offset)
             .filter(|token| (func)(&token.token_type))
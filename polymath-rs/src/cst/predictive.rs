@@ -77,7 +77,7 @@ pub enum SimpleExpression<'a> {
 ///
 /// Parses a stream of tokes according to the grammar.
 ///
-pub fn parse(tokens: TokenStream) -> PredictiveCST {
+pub fn parse<'a>(tokens: TokenStream<'_, 'a>) -> PredictiveCST<'a> {
     let cursor = Cursor {
         pos: RefCell::new(0),
     };
@@ -92,7 +92,7 @@ pub fn parse(tokens: TokenStream) -> PredictiveCST {
     }
 }
 
-fn parse_expression<'a>(tokens: TokenStream<'a>, cursor: &Cursor) -> Expression<'a> {
+fn parse_expression<'a>(tokens: TokenStream<'_, 'a>, cursor: &Cursor) -> Expression<'a> {
     let intermediate_expression = parse_intermediate_expression(tokens, cursor);
     // test for division
     if let Some(division_token) = cursor.parse(tokens, |tt| {
@@ -114,7 +114,7 @@ fn parse_expression<'a>(tokens: TokenStream<'a>, cursor: &Cursor) -> Expression<
 }
 
 fn parse_intermediate_expression<'a>(
-    tokens: TokenStream<'a>,
+    tokens: TokenStream<'_, 'a>,
     cursor: &Cursor,
 ) -> IntermediateExpression<'a> {
     let simple_expression = parse_simple_expression(tokens, cursor);
@@ -150,7 +150,10 @@ fn parse_intermediate_expression<'a>(
     }
 }
 
-fn parse_simple_expression<'a>(tokens: TokenStream<'a>, cursor: &Cursor) -> SimpleExpression<'a> {
+fn parse_simple_expression<'a>(
+    tokens: TokenStream<'_, 'a>,
+    cursor: &Cursor,
+) -> SimpleExpression<'a> {
     if let Some(simple_expression) = parse_group(tokens, cursor) {
         simple_expression
     } else if let Some(unary_symbol) = cursor.parse(tokens, |tt| {
@@ -171,10 +174,18 @@ fn parse_simple_expression<'a>(tokens: TokenStream<'a>, cursor: &Cursor) -> Simp
     } else if let Some(token) = cursor.parse(tokens, |_| true) {
         SimpleExpression::Symbol(token.clone())
     } else {
+        // the operand is missing, place an empty token right behind the last one
+        let pos = cursor
+            .get_pos()
+            .checked_sub(1)
+            .and_then(|index| tokens.get(index))
+            .map(|token| token.span.end)
+            .unwrap_or(0);
+
         SimpleExpression::Symbol(Token {
             span: Span {
-                start: cursor.get_pos(),
-                end: cursor.get_pos(),
+                start: pos,
+                end: pos,
                 text: "",
             },
             token_type: TokenType::None,
@@ -182,7 +193,7 @@ fn parse_simple_expression<'a>(tokens: TokenStream<'a>, cursor: &Cursor) -> Simp
     }
 }
 
-fn parse_group<'a>(tokens: TokenStream<'a>, cursor: &Cursor) -> Option<SimpleExpression<'a>> {
+fn parse_group<'a>(tokens: TokenStream<'_, 'a>, cursor: &Cursor) -> Option<SimpleExpression<'a>> {
     if cursor
         .peek(tokens, |tt| {
             matches!(tt, crate::tokens::types::TokenType::LBrace(_))
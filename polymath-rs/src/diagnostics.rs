@@ -0,0 +1,223 @@
+//!
+//! # Diagnostics
+//!
+//! The parser never fails, it falls back to rendering tokens literally when
+//! they don't fit the grammar. This module finds the places where that
+//! happened so they can be reported to the author.
+//!
+
+use std::ops::Range;
+
+use crate::{
+    ast::{Expression, Expressions, Literal, Table, AST},
+    tokens::{types::TokenType, Token},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Byte range of the source the diagnostic refers to
+    pub span: Range<usize>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+///
+/// Collects the diagnostics of a transformed AST in source order.
+///
+pub fn check(ast: &AST) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    check_expressions(&ast.expressions, &mut diagnostics);
+    diagnostics.sort_by_key(|diagnostic| diagnostic.span.start);
+    diagnostics
+}
+
+///
+/// One based line and column (in characters) of the byte `offset` in `text`.
+///
+pub fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |pos| pos + 1);
+
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+fn check_expressions(expressions: &Expressions, diagnostics: &mut Vec<Diagnostic>) {
+    expressions
+        .expressions
+        .iter()
+        .for_each(|expression| check_expression(expression, diagnostics));
+}
+
+fn check_expression(expression: &Expression, diagnostics: &mut Vec<Diagnostic>) {
+    match expression {
+        Expression::Frac(frac) => {
+            check_expression(&frac.expression_1, diagnostics);
+            check_operand(&frac.expression_2, "denominator", diagnostics);
+        }
+        Expression::Sub(sub) => {
+            check_expression(&sub.expression_1, diagnostics);
+            check_operand(&sub.expression_2, "subscript", diagnostics);
+        }
+        Expression::Pow(pow) => {
+            check_expression(&pow.expression_1, diagnostics);
+            check_operand(&pow.expression_2, "superscript", diagnostics);
+        }
+        Expression::SubPow(sub_pow) => {
+            check_expression(&sub_pow.expression_1, diagnostics);
+            check_operand(&sub_pow.expression_2, "subscript", diagnostics);
+            check_operand(&sub_pow.expression_3, "superscript", diagnostics);
+        }
+        Expression::Group(group) => check_expressions(&group.expressions, diagnostics),
+        Expression::Unary(unary) => check_operand(
+            &unary.expression,
+            &format!("argument of `{}`", unary.operator.span.text),
+            diagnostics,
+        ),
+        Expression::Binary(binary) => {
+            let operand = format!("argument of `{}`", binary.operator.span.text);
+            check_operand(&binary.expression_1, &operand, diagnostics);
+            check_operand(&binary.expression_2, &operand, diagnostics);
+        }
+        Expression::Literal(Literal::Literal(token)) => check_token(token, diagnostics),
+        Expression::Literal(Literal::Table(table)) => {
+            check_table(table, diagnostics);
+            table
+                .rows
+                .iter()
+                .flat_map(|row| row.cols.iter())
+                .for_each(|col| check_expressions(col, diagnostics))
+        }
+        Expression::Expressions(expressions) => check_expressions(expressions, diagnostics),
+        Expression::Unit => {}
+    }
+}
+
+fn check_operand(operand: &Expression, name: &str, diagnostics: &mut Vec<Diagnostic>) {
+    match operand {
+        Expression::Literal(Literal::Literal(token)) if token.token_type == TokenType::None => {
+            diagnostics.push(Diagnostic {
+                span: token.span.start..token.span.end,
+                severity: Severity::Error,
+                message: format!("missing {name}"),
+            })
+        }
+        _ => check_expression(operand, diagnostics),
+    }
+}
+
+///
+/// Rows need as many columns as the longest one, separators `|` count only
+/// where they are in the first row.
+///
+fn check_table(table: &Table, diagnostics: &mut Vec<Diagnostic>) {
+    let (_, columns) = table.dimensions();
+
+    for (index, row) in table.rows.iter().enumerate() {
+        if row.cols.len() == columns {
+            continue;
+        }
+
+        let span = row
+            .cols
+            .iter()
+            .filter_map(|col| col.span())
+            .reduce(|first, last| first.start.min(last.start)..first.end.max(last.end))
+            .unwrap_or(table.l_brace.span.start..table.l_brace.span.end);
+        diagnostics.push(Diagnostic {
+            span,
+            severity: Severity::Warning,
+            message: format!(
+                "row {} has {} of {columns} columns",
+                index + 1,
+                row.cols.len()
+            ),
+        })
+    }
+}
+
+fn check_token(token: &Token, diagnostics: &mut Vec<Diagnostic>) {
+    let message = match token.token_type {
+        TokenType::LBrace(_) => format!("unclosed bracket `{}`", token.span.text),
+        TokenType::RBrace(_) => format!("unmatched closing bracket `{}`", token.span.text),
+        TokenType::Symbol if token.span.text == "\"" => "unterminated text".to_string(),
+        _ => return,
+    };
+
+    diagnostics.push(Diagnostic {
+        span: token.span.start..token.span.end,
+        severity: Severity::Error,
+        message,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::diagnose;
+
+    use super::{line_column, Diagnostic, Severity};
+
+    fn error(span: std::ops::Range<usize>, message: &str) -> Diagnostic {
+        Diagnostic {
+            span,
+            severity: Severity::Error,
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_valid() {
+        assert_eq!(diagnose("sum_(i=1)^n i^3=((n(n+1))/2)^2 + ()"), vec![]);
+    }
+
+    #[test]
+    fn test_brackets() {
+        assert_eq!(diagnose("(a+b"), vec![error(0..1, "unclosed bracket `(`")]);
+        assert_eq!(
+            diagnose("a]"),
+            vec![error(1..2, "unmatched closing bracket `]`")]
+        );
+    }
+
+    #[test]
+    fn test_missing_operands() {
+        assert_eq!(
+            diagnose("a + b/"),
+            vec![error(6..6, "missing denominator"),]
+        );
+        assert_eq!(diagnose("(x^)"), vec![error(3..3, "missing superscript")]);
+    }
+
+    #[test]
+    fn test_tables() {
+        assert_eq!(diagnose("[[1, |, 2], [a, |, b]]"), vec![]);
+        assert_eq!(
+            diagnose("[[1, |, 2], [a, b, c]]"),
+            vec![Diagnostic {
+                span: 2..9,
+                severity: Severity::Warning,
+                message: "row 1 has 2 of 3 columns".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_text() {
+        assert_eq!(diagnose("\"abc"), vec![error(0..1, "unterminated text")]);
+    }
+
+    #[test]
+    fn test_line_column() {
+        assert_eq!(line_column("a\nbä c", 0), (1, 1));
+        assert_eq!(line_column("a\nbä c", 2), (2, 1));
+        assert_eq!(line_column("a\nbä c", 6), (2, 4));
+    }
+}
@@ -0,0 +1,239 @@
+//!
+//! # Document
+//!
+//! Scans text (Markdown, HTML, ...) for formulas enclosed in delimiters and
+//! replaces them with MathML, leaving everything else untouched.
+//!
+//! An opening delimiter preceded by a backslash is not treated as the start of
+//! a formula, the backslash is dropped and the delimiter is kept as text.
+//!
+
+use std::ops::Range;
+
+use crate::{
+    diagnose,
+    diagnostics::{Diagnostic, Severity},
+    mathml::{self, Display},
+    to_math_ml_with_options,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Delimiter {
+    pub open: String,
+    pub close: String,
+    pub display: Display,
+}
+
+impl Delimiter {
+    pub fn new(open: &str, close: &str, display: Display) -> Self {
+        Self {
+            open: open.to_string(),
+            close: close.to_string(),
+            display,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub delimiters: Vec<Delimiter>,
+    /// Used for every formula, `display` is overridden by the delimiter
+    pub mathml: mathml::Options,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            delimiters: vec![
+                Delimiter::new("`", "`", Display::Inline),
+                Delimiter::new("\\(", "\\)", Display::Inline),
+                Delimiter::new("$$", "$$", Display::Block),
+            ],
+            mathml: mathml::Options::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Formula {
+    /// Byte range of the formula in the scanned text, including delimiters
+    pub span: Range<usize>,
+    /// Byte range of the formula source in the scanned text
+    pub content: Range<usize>,
+    /// Byte range of the formula in the converted text
+    pub output: Range<usize>,
+    pub source: String,
+    pub display: Display,
+    /// Diagnostics with spans relative to the scanned text
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document {
+    pub text: String,
+    pub formulas: Vec<Formula>,
+    /// Problems with the document itself, like unclosed delimiters
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+///
+/// Converts every delimited formula of `text` to MathML.
+///
+pub fn convert(text: &str, options: &Options) -> Document {
+    // prefer the longest delimiter if several of them match
+    let mut delimiters = options
+        .delimiters
+        .iter()
+        .filter(|delimiter| !delimiter.open.is_empty() && !delimiter.close.is_empty())
+        .collect::<Vec<_>>();
+    delimiters.sort_by_key(|delimiter| std::cmp::Reverse(delimiter.open.len()));
+
+    let mut document = Document {
+        text: String::with_capacity(text.len()),
+        formulas: vec![],
+        diagnostics: vec![],
+    };
+
+    let mut pos = 0;
+    while let Some(c) = text[pos..].chars().next() {
+        let rest = &text[pos..];
+
+        if let Some(escaped) = rest.strip_prefix('\\') {
+            if let Some(delimiter) = delimiters.iter().find(|d| escaped.starts_with(&d.open)) {
+                document.text.push_str(&delimiter.open);
+                pos += 1 + delimiter.open.len();
+                continue;
+            }
+        }
+
+        let Some(delimiter) = delimiters.iter().find(|d| rest.starts_with(&d.open)) else {
+            document.text.push(c);
+            pos += c.len_utf8();
+            continue;
+        };
+
+        let content_start = pos + delimiter.open.len();
+        let Some(content_len) = text[content_start..].find(&delimiter.close) else {
+            document.diagnostics.push(Diagnostic {
+                span: pos..content_start,
+                severity: Severity::Warning,
+                message: format!("unclosed delimiter `{}`", delimiter.open),
+            });
+            document.text.push_str(rest);
+            break;
+        };
+
+        let content_end = content_start + content_len;
+        let source = &text[content_start..content_end];
+        let mathml_options = mathml::Options {
+            display: delimiter.display,
+            ..options.mathml
+        };
+
+        let output_start = document.text.len();
+        document
+            .text
+            .push_str(&to_math_ml_with_options(source, &mathml_options));
+
+        document.formulas.push(Formula {
+            span: pos..content_end + delimiter.close.len(),
+            content: content_start..content_end,
+            output: output_start..document.text.len(),
+            source: source.to_string(),
+            display: delimiter.display,
+            diagnostics: diagnose(source)
+                .into_iter()
+                .map(|diagnostic| Diagnostic {
+                    span: content_start + diagnostic.span.start
+                        ..content_start + diagnostic.span.end,
+                    ..diagnostic
+                })
+                .collect(),
+        });
+
+        pos = content_end + delimiter.close.len();
+    }
+
+    document
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{diagnostics::Severity, mathml::Display};
+
+    use super::{convert, Delimiter, Options};
+
+    #[test]
+    fn test_convert() {
+        let document = convert("Let `x^2` be $$sum_i i$$.", &Options::default());
+
+        assert_eq!(
+            document.text,
+            "Let <math display=\"inline\"><msup><mi>x</mi><mn>2</mn></msup></math> be \
+             <math display=\"block\"><munder><mo>&#x2211;</mo><mi>i</mi></munder><mi>i</mi></math>."
+        );
+        assert_eq!(document.formulas.len(), 2);
+        assert_eq!(document.formulas[0].span, 4..9);
+        assert_eq!(document.formulas[0].content, 5..8);
+        assert_eq!(document.formulas[0].source, "x^2");
+        assert_eq!(document.formulas[1].display, Display::Block);
+        assert_eq!(
+            &document.text[document.formulas[0].output.clone()],
+            "<math display=\"inline\"><msup><mi>x</mi><mn>2</mn></msup></math>"
+        );
+    }
+
+    #[test]
+    fn test_escaping() {
+        let document = convert("\\`a\\` and \\\\(b\\) `c`", &Options::default());
+
+        assert_eq!(
+            document.text,
+            "`a` and \\(b\\) <math display=\"inline\"><mi>c</mi></math>"
+        );
+    }
+
+    #[test]
+    fn test_markup_in_text() {
+        let document = convert(
+            "see `\"<img src=x onerror=alert(1)>\"` and `a & b`",
+            &Options::default(),
+        );
+
+        assert_eq!(
+            document.text,
+            "see <math display=\"inline\"><mtext>&lt;img src=x onerror=alert(1)&gt;</mtext></math> \
+             and <math display=\"inline\"><mi>a</mi><mi>&amp;</mi><mi>b</mi></math>"
+        );
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let document = convert("ab \\((x+1\\) and `y", &Options::default());
+
+        assert_eq!(document.formulas[0].diagnostics.len(), 1);
+        assert_eq!(document.formulas[0].diagnostics[0].span, 5..6);
+        assert_eq!(document.diagnostics.len(), 1);
+        assert_eq!(document.diagnostics[0].span, 16..17);
+        assert_eq!(document.diagnostics[0].severity, Severity::Warning);
+        assert!(document.text.ends_with(" and `y"));
+    }
+
+    #[test]
+    fn test_custom_delimiters() {
+        let options = Options {
+            delimiters: vec![
+                Delimiter::new("$", "$", Display::Inline),
+                Delimiter::new("$$", "$$", Display::Block),
+            ],
+            ..Default::default()
+        };
+        let document = convert("$a$ $$b$$ `c`", &options);
+
+        assert_eq!(
+            document.text,
+            "<math display=\"inline\"><mi>a</mi></math> \
+             <math display=\"block\"><mi>b</mi></math> `c`"
+        );
+    }
+}
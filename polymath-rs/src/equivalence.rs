@@ -0,0 +1,620 @@
+//!
+//! # Equivalence
+//!
+//! Decides whether two formulas say the same, e.g. to grade an answer
+//! against a reference. Both are read into [semantic trees](crate::semantics),
+//! which already ignore whitespace, the style of brackets and alias
+//! spellings like `ne` for `!=`. Sums and products are compared regardless
+//! of the order of their operands. Optionally, formulas that still differ
+//! are evaluated at random points.
+//!
+//! ```
+//! use polymath_rs::equivalence::{compare, Equivalence, Options};
+//!
+//! let comparison = compare("a != b + 2c", "a ne [c*2 + b]", &Options::default()).unwrap();
+//! assert_eq!(comparison.equivalence, Equivalence::Structural);
+//! ```
+//!
+
+use std::{collections::BTreeSet, fmt, ops::Range};
+
+use crate::{
+    eval::{evaluate_node, Environment},
+    semantics::{self, Kind, Node, Operator},
+    to_ast,
+};
+
+/// Variables are sampled from `-SAMPLE_RANGE..SAMPLE_RANGE`
+const SAMPLE_RANGE: f64 = 3.0;
+/// Samples are the same for every comparison, so are the results
+const SEED: u64 = 0x5EED;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    /// Ignore the order of the operands of `+` and `*`
+    pub commutative: bool,
+    /// Points both formulas are evaluated at if their trees differ, `0` to
+    /// only compare the trees
+    pub samples: usize,
+    /// Largest difference of values considered equal, relative to their size
+    pub tolerance: f64,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            commutative: true,
+            samples: 0,
+            tolerance: 1e-9,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Equivalence {
+    /// The trees are the same
+    Structural,
+    /// The trees differ, but their values agree wherever both are defined
+    Numeric,
+    Different,
+}
+
+///
+/// A part of the expected formula that doesn't match a part of the actual
+/// one. Parts missing on one side point to where they are missing.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Difference {
+    /// Byte range of the expected formula
+    pub expected: Range<usize>,
+    /// Byte range of the actual formula
+    pub actual: Range<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    pub equivalence: Equivalence,
+    /// Empty if the trees are the same
+    pub differences: Vec<Difference>,
+}
+
+impl Comparison {
+    pub fn is_equivalent(&self) -> bool {
+        self.equivalence != Equivalence::Different
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The expected formula has no semantic tree
+    Expected(semantics::Error),
+    /// The actual formula has no semantic tree
+    Actual(semantics::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Expected(error) => write!(f, "expected formula: {error}"),
+            Error::Actual(error) => write!(f, "actual formula: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+///
+/// Compares the formula `actual` to `expected`.
+///
+pub fn compare(expected: &str, actual: &str, options: &Options) -> Result<Comparison, Error> {
+    let expected_node = semantics::build(&to_ast(expected)).map_err(Error::Expected)?;
+    let actual_node = semantics::build(&to_ast(actual)).map_err(Error::Actual)?;
+
+    let expected_node = canonical(expected_node, options);
+    let actual_node = canonical(actual_node, options);
+
+    let mut differences = Vec::new();
+    diff(
+        &expected_node,
+        &actual_node,
+        (0..expected.len(), 0..actual.len()),
+        options,
+        &mut differences,
+    );
+
+    let equivalence = match differences.is_empty() {
+        true => Equivalence::Structural,
+        false if options.samples > 0 && numeric(&expected_node, &actual_node, options) => {
+            Equivalence::Numeric
+        }
+        false => Equivalence::Different,
+    };
+
+    Ok(Comparison {
+        equivalence,
+        differences,
+    })
+}
+
+///
+/// Brings `node` into a form in which equivalent trees are equal: every
+/// product is implicit and, if commutative, operands of sums and products
+/// are sorted.
+///
+fn canonical(mut node: Node, options: &Options) -> Node {
+    for child in node.children_mut() {
+        let taken = std::mem::replace(child, Node::new(Kind::Number(0.0), None));
+        *child = canonical(taken, options);
+    }
+
+    match node.kind {
+        Kind::Binary(Operator::Add | Operator::Subtract, ..) if options.commutative => {
+            let mut summands = Vec::new();
+            summands_of(&node, false, &mut summands);
+
+            let mut summands: Vec<_> = summands
+                .into_iter()
+                .map(|(summand, negative)| (summand.to_string(), summand.clone(), negative))
+                .collect();
+            summands.sort_by(|a, b| (&a.0, a.2).cmp(&(&b.0, b.2)));
+
+            let mut summands = summands.into_iter();
+            let first = summands
+                .next()
+                .map(|(_, summand, negative)| match negative {
+                    true => {
+                        let span = summand.span.clone();
+                        Node::new(Kind::Negate(Box::new(summand)), span)
+                    }
+                    false => summand,
+                });
+            let sum = summands.fold(first.expect("sums have summands"), |sum, summand| {
+                let operator = match summand.2 {
+                    true => Operator::Subtract,
+                    false => Operator::Add,
+                };
+                binary(operator, sum, summand.1, None)
+            });
+
+            Node {
+                span: node.span,
+                ..sum
+            }
+        }
+        Kind::Binary(Operator::Multiply { .. }, ..) if options.commutative => {
+            let mut factors = Vec::new();
+            factors_of(&node, &mut factors);
+
+            let mut factors: Vec<_> = factors
+                .into_iter()
+                .map(|factor| (factor.to_string(), factor.clone()))
+                .collect();
+            factors.sort_by(|a, b| a.0.cmp(&b.0));
+
+            let product = factors
+                .into_iter()
+                .map(|(_, factor)| factor)
+                .reduce(|product, factor| {
+                    binary(Operator::Multiply { implicit: true }, product, factor, None)
+                })
+                .expect("products have factors");
+
+            Node {
+                span: node.span,
+                ..product
+            }
+        }
+        Kind::Binary(Operator::Multiply { implicit: false }, left, right) => binary(
+            Operator::Multiply { implicit: true },
+            *left,
+            *right,
+            node.span,
+        ),
+        kind => Node::new(kind, node.span),
+    }
+}
+
+///
+/// The operands of a sum, along with whether they are subtracted.
+///
+fn summands_of<'n>(node: &'n Node, negative: bool, summands: &mut Vec<(&'n Node, bool)>) {
+    match &node.kind {
+        Kind::Binary(Operator::Add, left, right) => {
+            summands_of(left, negative, summands);
+            summands_of(right, negative, summands);
+        }
+        Kind::Binary(Operator::Subtract, left, right) => {
+            summands_of(left, negative, summands);
+            summands_of(right, !negative, summands);
+        }
+        // `a + -b` is `a - b`
+        Kind::Negate(operand) => summands.push((operand, !negative)),
+        _ => summands.push((node, negative)),
+    }
+}
+
+fn factors_of<'n>(node: &'n Node, factors: &mut Vec<&'n Node>) {
+    match &node.kind {
+        Kind::Binary(Operator::Multiply { .. }, left, right) => {
+            factors_of(left, factors);
+            factors_of(right, factors);
+        }
+        _ => factors.push(node),
+    }
+}
+
+///
+/// Collects the outermost parts in which `expected` and `actual` differ.
+/// `context` are the spans of their parents, for made up nodes.
+///
+fn diff(
+    expected: &Node,
+    actual: &Node,
+    context: (Range<usize>, Range<usize>),
+    options: &Options,
+    differences: &mut Vec<Difference>,
+) {
+    if expected.same_as(actual) {
+        return;
+    }
+
+    let spans = (
+        expected.span.clone().unwrap_or(context.0),
+        actual.span.clone().unwrap_or(context.1),
+    );
+
+    if options.commutative {
+        let operands = match (&expected.kind, &actual.kind) {
+            (
+                Kind::Binary(Operator::Add | Operator::Subtract, ..),
+                Kind::Binary(Operator::Add | Operator::Subtract, ..),
+            ) => {
+                let (mut expected_summands, mut actual_summands) = (Vec::new(), Vec::new());
+                summands_of(expected, false, &mut expected_summands);
+                summands_of(actual, false, &mut actual_summands);
+                Some((expected_summands, actual_summands))
+            }
+            (
+                Kind::Binary(Operator::Multiply { .. }, ..),
+                Kind::Binary(Operator::Multiply { .. }, ..),
+            ) => {
+                let (mut expected_factors, mut actual_factors) = (Vec::new(), Vec::new());
+                factors_of(expected, &mut expected_factors);
+                factors_of(actual, &mut actual_factors);
+                let unsigned = |factor| (factor, false);
+                Some((
+                    expected_factors.into_iter().map(unsigned).collect(),
+                    actual_factors.into_iter().map(unsigned).collect(),
+                ))
+            }
+            _ => None,
+        };
+
+        if let Some((expected_operands, actual_operands)) = operands {
+            return diff_operands(
+                expected_operands,
+                actual_operands,
+                spans,
+                options,
+                differences,
+            );
+        }
+    }
+
+    if same_head(expected, actual) {
+        for (expected, actual) in expected.children().into_iter().zip(actual.children()) {
+            diff(expected, actual, spans.clone(), options, differences);
+        }
+    } else {
+        differences.push(Difference {
+            expected: spans.0,
+            actual: spans.1,
+        });
+    }
+}
+
+///
+/// Pairs up equal operands of two sums or products in any order, then
+/// compares the rest in order.
+///
+fn diff_operands(
+    expected: Vec<(&Node, bool)>,
+    mut actual: Vec<(&Node, bool)>,
+    spans: (Range<usize>, Range<usize>),
+    options: &Options,
+    differences: &mut Vec<Difference>,
+) {
+    let mut unmatched = Vec::new();
+    for (operand, negative) in expected {
+        let position = actual.iter().position(|(other, other_negative)| {
+            *other_negative == negative && other.same_as(operand)
+        });
+        match position {
+            Some(position) => {
+                actual.remove(position);
+            }
+            None => unmatched.push((operand, negative)),
+        }
+    }
+
+    let span = |node: &Node, fallback: &Range<usize>| node.span.clone().unwrap_or(fallback.clone());
+    for index in 0..unmatched.len().max(actual.len()) {
+        match (unmatched.get(index), actual.get(index)) {
+            (Some((expected, negative)), Some((actual, other_negative)))
+                if negative == other_negative =>
+            {
+                diff(expected, actual, spans.clone(), options, differences)
+            }
+            (expected, actual) => differences.push(Difference {
+                expected: expected.map_or(spans.0.clone(), |(node, _)| span(node, &spans.0)),
+                actual: actual.map_or(spans.1.clone(), |(node, _)| span(node, &spans.1)),
+            }),
+        }
+    }
+}
+
+///
+/// Whether both nodes are of the same kind with the same number of
+/// children, so that their children can be compared pairwise.
+///
+fn same_head(a: &Node, b: &Node) -> bool {
+    match (&a.kind, &b.kind) {
+        (Kind::Negate(_), Kind::Negate(_)) => true,
+        (Kind::Binary(a, ..), Kind::Binary(b, ..)) => a == b,
+        (Kind::Relation(a, ..), Kind::Relation(b, ..)) => a == b,
+        (
+            Kind::Apply {
+                function,
+                subscript,
+                arguments,
+            },
+            Kind::Apply {
+                function: other_function,
+                subscript: other_subscript,
+                arguments: other_arguments,
+            },
+        ) => {
+            function == other_function
+                && subscript.is_some() == other_subscript.is_some()
+                && arguments.len() == other_arguments.len()
+        }
+        (Kind::Root { index, .. }, Kind::Root { index: other, .. }) => {
+            index.is_some() == other.is_some()
+        }
+        (Kind::Unary(a, _), Kind::Unary(b, _)) => a == b,
+        (
+            Kind::BigOperator {
+                operator,
+                variable,
+                lower,
+                upper,
+                ..
+            },
+            Kind::BigOperator {
+                operator: other_operator,
+                variable: other_variable,
+                lower: other_lower,
+                upper: other_upper,
+                ..
+            },
+        ) => {
+            operator == other_operator
+                && variable == other_variable
+                && lower.is_some() == other_lower.is_some()
+                && upper.is_some() == other_upper.is_some()
+        }
+        (Kind::Tuple(a), Kind::Tuple(b)) => a.len() == b.len(),
+        (Kind::Table(a), Kind::Table(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.len() == b.len())
+        }
+        _ => false,
+    }
+}
+
+///
+/// Whether both formulas have the same values at the sample points. Points
+/// where either is undefined don't count, at least one has to.
+///
+fn numeric(expected: &Node, actual: &Node, options: &Options) -> bool {
+    match (&expected.kind, &actual.kind) {
+        (Kind::Relation(relation, left, right), Kind::Relation(other, other_left, other_right)) => {
+            relation == other
+                && numeric(left, other_left, options)
+                && numeric(right, other_right, options)
+        }
+        (Kind::Tuple(a), Kind::Tuple(b)) if a.len() == b.len() => {
+            a.iter().zip(b).all(|(a, b)| numeric(a, b, options))
+        }
+        (Kind::Table(a), Kind::Table(b)) if a.len() == b.len() => a.iter().zip(b).all(|(a, b)| {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| numeric(a, b, options))
+        }),
+        _ => sample(expected, actual, options),
+    }
+}
+
+fn sample(expected: &Node, actual: &Node, options: &Options) -> bool {
+    let mut variables = BTreeSet::new();
+    identifiers(expected, &mut variables);
+    identifiers(actual, &mut variables);
+    // constants unless assigned
+    variables.remove("e");
+    variables.remove("pi");
+
+    let mut random = Random(SEED);
+    let mut compared = 0;
+
+    for _ in 0..options.samples {
+        let mut environment = Environment::new();
+        for variable in &variables {
+            environment.set_variable(*variable, (random.next() * 2.0 - 1.0) * SAMPLE_RANGE);
+        }
+
+        match (
+            evaluate_node(expected, &environment),
+            evaluate_node(actual, &environment),
+        ) {
+            (Ok(a), Ok(b)) if a.is_finite() && b.is_finite() => {
+                let scale = a.abs().max(b.abs()).max(1.0);
+                if (a - b).abs() > options.tolerance * scale {
+                    return false;
+                }
+                compared += 1;
+            }
+            _ => {}
+        }
+    }
+
+    compared > 0
+}
+
+fn identifiers<'n>(node: &'n Node, names: &mut BTreeSet<&'n str>) {
+    if let Kind::Identifier(name) = &node.kind {
+        names.insert(name);
+    }
+    for child in node.children() {
+        identifiers(child, names);
+    }
+}
+
+fn binary(operator: Operator, left: Node, right: Node, span: Option<Range<usize>>) -> Node {
+    Node::new(
+        Kind::Binary(operator, Box::new(left), Box::new(right)),
+        span,
+    )
+}
+
+///
+/// SplitMix64, uniform numbers in `0..1`.
+///
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self) -> f64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{compare, Difference, Equivalence, Error, Options};
+    use crate::semantics;
+
+    fn equivalence(expected: &str, actual: &str) -> Equivalence {
+        compare(expected, actual, &Options::default())
+            .unwrap()
+            .equivalence
+    }
+
+    #[test]
+    fn test_structural() {
+        assert_eq!(equivalence("a+b", " a +  b "), Equivalence::Structural);
+        assert_eq!(equivalence("(a+b)/2", "{:a+b:}/2"), Equivalence::Structural);
+        assert_eq!(equivalence("x != y", "x ne y"), Equivalence::Structural);
+        assert_eq!(equivalence("2x + y", "y + x*2"), Equivalence::Structural);
+        assert_eq!(
+            equivalence("a - b + c", "c + -b + a"),
+            Equivalence::Structural
+        );
+        assert_eq!(equivalence("(a+b)+c", "c+(b+a)"), Equivalence::Structural);
+        assert_eq!(equivalence("a - b", "b - a"), Equivalence::Different);
+        assert_eq!(equivalence("a/b", "b/a"), Equivalence::Different);
+
+        let options = Options {
+            commutative: false,
+            ..Options::default()
+        };
+        assert_eq!(
+            compare("2*x", "2x", &options).unwrap().equivalence,
+            Equivalence::Structural
+        );
+        assert_eq!(
+            compare("x 2", "2x", &options).unwrap().equivalence,
+            Equivalence::Different
+        );
+    }
+
+    #[test]
+    fn test_differences() {
+        let comparison = compare("x^2 + 3x + 1", "1 + x^2 + 2x", &Options::default()).unwrap();
+        assert_eq!(
+            comparison.differences,
+            vec![Difference {
+                expected: 6..7,
+                actual: 10..11
+            }]
+        );
+
+        let comparison = compare("sin(x) + 1", "cos(x) + 1 + y", &Options::default()).unwrap();
+        assert_eq!(
+            comparison.differences,
+            vec![
+                Difference {
+                    expected: 0..6,
+                    actual: 0..6
+                },
+                Difference {
+                    expected: 0..10,
+                    actual: 13..14
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_numeric() {
+        let options = Options {
+            samples: 16,
+            ..Options::default()
+        };
+        let numeric = |expected, actual| compare(expected, actual, &options).unwrap();
+
+        let comparison = numeric("(x+1)^2", "x^2 + 2x + 1");
+        assert_eq!(comparison.equivalence, Equivalence::Numeric);
+        assert!(comparison.is_equivalent());
+        assert!(!comparison.differences.is_empty());
+
+        assert_eq!(numeric("0.5", "1/2").equivalence, Equivalence::Numeric);
+        assert_eq!(
+            numeric("y = sqrt(x)^2", "y = x").equivalence,
+            Equivalence::Numeric
+        );
+        assert_eq!(
+            numeric("(x+1)^2", "x^2 + 1").equivalence,
+            Equivalence::Different
+        );
+        assert_eq!(numeric("f(x)", "x").equivalence, Equivalence::Different);
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |expected, actual| compare(expected, actual, &Options::default()).unwrap_err();
+
+        // the error names the side without a semantic tree
+        assert_eq!(
+            error("x +", "x"),
+            Error::Expected(semantics::Error {
+                kind: semantics::ErrorKind::MissingOperand,
+                span: 3..3
+            })
+        );
+        assert_eq!(
+            error("x", "2^3^2"),
+            Error::Actual(semantics::Error {
+                kind: semantics::ErrorKind::Unexpected("^".to_string()),
+                span: 3..4
+            })
+        );
+        assert_eq!(
+            error("x", "2^3^2").to_string(),
+            "actual formula: unexpected `^`"
+        );
+
+        // with both sides broken the expected one is reported
+        assert!(matches!(error("1 +", "2^3^2"), Error::Expected(_)));
+    }
+}
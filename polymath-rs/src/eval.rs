@@ -0,0 +1,559 @@
+//!
+//! # Eval
+//!
+//! Numeric evaluation of formulas, e.g. for calculators. Formulas are read
+//! into the [semantic tree](crate::semantics) first, so the usual operator
+//! precedence applies, functions take the following operand (`sin x`) or a
+//! parenthesized argument list (`max(a, b)`) and `sum` and `prod` the
+//! following product as their body.
+//!
+//! ```
+//! use polymath_rs::{eval::{evaluate, Environment}, to_ast};
+//!
+//! let mut environment = Environment::new();
+//! environment.set_variable("n", 4.0);
+//!
+//! let value = evaluate(&to_ast("sum_(i=1)^n i^2"), &environment).unwrap();
+//! assert_eq!(value, 30.0);
+//! ```
+//!
+
+use std::{collections::HashMap, f64::consts, fmt, ops::Range, rc::Rc};
+
+use crate::{
+    ast::AST,
+    semantics::{self, build_with_functions, BigOperator, Callee, Kind, Node, Operator},
+    tokens::types::{Function, Misc, UnaryOperator},
+};
+
+///
+/// Upper limit for the number of terms of all `sum`s and `prod`s of one
+/// evaluation together, nested ones count once per outer term.
+///
+pub const MAX_TERMS: u64 = 1_000_000;
+
+/// Bounds beyond this are not exactly representable as `f64` integers
+const MAX_BOUND: f64 = (1u64 << f64::MANTISSA_DIGITS) as f64;
+
+type UserFunction = Rc<dyn Fn(&[f64]) -> f64>;
+
+///
+/// Values of variables and user defined functions. Variables with a
+/// subscript are looked up by their spelling, e.g. `x_1`.
+///
+#[derive(Clone, Default)]
+pub struct Environment {
+    variables: HashMap<String, f64>,
+    functions: HashMap<String, UserFunction>,
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_variable(&mut self, name: impl Into<String>, value: f64) {
+        self.variables.insert(name.into(), value);
+    }
+
+    ///
+    /// Defines a function applied by name, either one of the generic
+    /// functions `f` and `g` or any other symbol followed by parentheses,
+    /// e.g. `h(x, y)`.
+    ///
+    pub fn set_function(
+        &mut self,
+        name: impl Into<String>,
+        function: impl Fn(&[f64]) -> f64 + 'static,
+    ) {
+        self.functions.insert(name.into(), Rc::new(function));
+    }
+
+    pub fn variable(&self, name: &str) -> Option<f64> {
+        self.variables.get(name).copied()
+    }
+}
+
+impl fmt::Debug for Environment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Environment")
+            .field("variables", &self.variables)
+            .field("functions", &self.functions.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    pub kind: ErrorKind,
+    /// Byte range of the source that could not be evaluated
+    pub span: Range<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    UnboundVariable(String),
+    UnknownFunction(String),
+    /// A construct without numeric meaning, e.g. a relation or a table
+    Unsupported(String),
+    /// An operand the parser made up or that is missing at the end
+    MissingOperand,
+    ArgumentCount {
+        expected: usize,
+        found: usize,
+    },
+    /// More than [MAX_TERMS] terms of `sum` and `prod`, or a bound too large
+    /// to count to
+    TooManyTerms,
+    /// A bound of `sum` or `prod` that is not an integer
+    NonIntegerBound,
+    /// A division or `mod` by zero
+    DivisionByZero,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ErrorKind::UnboundVariable(name) => write!(f, "unbound variable `{name}`"),
+            ErrorKind::UnknownFunction(name) => write!(f, "unknown function `{name}`"),
+            ErrorKind::Unsupported(construct) => write!(f, "cannot evaluate {construct}"),
+            ErrorKind::MissingOperand => write!(f, "missing operand"),
+            ErrorKind::ArgumentCount { expected, found } => {
+                write!(f, "expected {expected} argument(s), found {found}")
+            }
+            ErrorKind::TooManyTerms => write!(f, "too many terms"),
+            ErrorKind::NonIntegerBound => write!(f, "bounds must be integers"),
+            ErrorKind::DivisionByZero => write!(f, "division by zero"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<semantics::Error> for Error {
+    fn from(error: semantics::Error) -> Self {
+        Error {
+            kind: match error.kind {
+                semantics::ErrorKind::MissingOperand => ErrorKind::MissingOperand,
+                semantics::ErrorKind::Unexpected(text) => {
+                    ErrorKind::Unsupported(format!("`{text}`"))
+                }
+            },
+            span: error.span,
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+///
+/// Evaluates `ast` with the variables and functions of `environment`.
+/// `pi` and `e` are constants unless the environment defines them.
+///
+pub fn evaluate(ast: &AST, environment: &Environment) -> Result<f64> {
+    let node = build_with_functions(ast, &|name| environment.functions.contains_key(name))?;
+    evaluate_node(&node, environment)
+}
+
+///
+/// Evaluates a semantic tree, see [evaluate].
+///
+pub fn evaluate_node(node: &Node, environment: &Environment) -> Result<f64> {
+    Evaluator {
+        environment,
+        bound: Vec::new(),
+        terms: MAX_TERMS,
+    }
+    .node(node)
+}
+
+struct Evaluator<'e> {
+    environment: &'e Environment,
+    /// Variables bound by `sum` and `prod`, innermost last
+    bound: Vec<(String, f64)>,
+    /// Terms of `sum` and `prod` left to evaluate
+    terms: u64,
+}
+
+impl<'e> Evaluator<'e> {
+    fn node(&mut self, node: &Node) -> Result<f64> {
+        let unsupported = |construct: &str| Error {
+            kind: ErrorKind::Unsupported(construct.to_string()),
+            span: span(node),
+        };
+
+        match &node.kind {
+            Kind::Number(number) => Ok(*number),
+            Kind::Identifier(name) => self.variable(name).ok_or_else(|| Error {
+                kind: ErrorKind::UnboundVariable(name.clone()),
+                span: span(node),
+            }),
+            Kind::Constant(Misc::Infinity) => Ok(f64::INFINITY),
+            Kind::Negate(operand) => Ok(-self.node(operand)?),
+            Kind::Binary(operator, left, right) => {
+                let (left, right) = (self.node(left)?, self.node(right)?);
+                if matches!(operator, Operator::Divide | Operator::Modulo) && right == 0.0 {
+                    return Err(Error {
+                        kind: ErrorKind::DivisionByZero,
+                        span: span(node),
+                    });
+                }
+
+                Ok(match operator {
+                    Operator::Add => left + right,
+                    Operator::Subtract => left - right,
+                    Operator::Multiply { .. } => left * right,
+                    Operator::Divide => left / right,
+                    Operator::Modulo => left.rem_euclid(right),
+                    Operator::Power => left.powf(right),
+                })
+            }
+            Kind::Apply {
+                function,
+                subscript,
+                arguments,
+            } => {
+                let arguments = arguments
+                    .iter()
+                    .map(|argument| self.node(argument))
+                    .collect::<Result<Vec<_>>>()?;
+
+                match function {
+                    Callee::Builtin(function) => {
+                        let base = subscript
+                            .as_ref()
+                            .map(|subscript| self.node(subscript))
+                            .transpose()?;
+                        apply(function, node, base, &arguments)
+                    }
+                    Callee::Named(name) => match self.environment.functions.get(name) {
+                        Some(function) => Ok(function(&arguments)),
+                        None => Err(Error {
+                            kind: ErrorKind::UnknownFunction(name.clone()),
+                            span: span(node),
+                        }),
+                    },
+                }
+            }
+            Kind::Root { index, radicand } => {
+                let index = match index {
+                    Some(index) => self.node(index)?,
+                    None => 2.0,
+                };
+                Ok(root(index, self.node(radicand)?))
+            }
+            Kind::Unary(operator, operand) => {
+                let function = match operator {
+                    UnaryOperator::Abs | UnaryOperator::Norm => f64::abs,
+                    UnaryOperator::Floor => f64::floor,
+                    UnaryOperator::Ceil => f64::ceil,
+                    _ => return Err(unsupported("accents")),
+                };
+                Ok(function(self.node(operand)?))
+            }
+            Kind::BigOperator {
+                operator: operator @ (BigOperator::Sum | BigOperator::Prod),
+                variable: Some(variable),
+                lower: Some(lower),
+                upper: Some(upper),
+                body,
+            } => {
+                let (neutral, combine): (f64, fn(f64, f64) -> f64) = match operator {
+                    BigOperator::Sum => (0.0, |a, b| a + b),
+                    _ => (1.0, |a, b| a * b),
+                };
+                let from = self.bound(lower)?;
+                let to = self.bound(upper)?;
+
+                let count = (to - from + 1).max(0) as u64;
+                if count > self.terms {
+                    return Err(Error {
+                        kind: ErrorKind::TooManyTerms,
+                        span: span(node),
+                    });
+                }
+                self.terms -= count;
+
+                let mut value = neutral;
+                for index in from..=to {
+                    self.bound.push((variable.clone(), index as f64));
+                    let term = self.node(body);
+                    self.bound.pop();
+
+                    value = combine(value, term?);
+                }
+
+                Ok(value)
+            }
+            Kind::Constant(_) => Err(unsupported("constants other than `oo`")),
+            Kind::BigOperator {
+                operator: BigOperator::Int,
+                ..
+            } => Err(unsupported("int")),
+            Kind::BigOperator {
+                operator: BigOperator::OInt,
+                ..
+            } => Err(unsupported("oint")),
+            Kind::BigOperator {
+                operator: BigOperator::Lim,
+                ..
+            } => Err(unsupported("lim")),
+            Kind::BigOperator { .. } => Err(unsupported("big operators without bounds")),
+            Kind::Relation(..) => Err(unsupported("relations")),
+            Kind::Tuple(_) => Err(unsupported("tuples")),
+            Kind::Table(_) => Err(unsupported("tables")),
+            Kind::Text(_) => Err(unsupported("text")),
+            Kind::Other(source) => Err(unsupported(&format!("`{source}`"))),
+        }
+    }
+
+    ///
+    /// Evaluates a bound of `sum` or `prod`. Bounds too large to count to,
+    /// infinite ones included, exceed the term limit.
+    ///
+    fn bound(&mut self, node: &Node) -> Result<i64> {
+        let value = self.node(node)?;
+        let kind = match value {
+            value if value.is_nan() || value.abs() > MAX_BOUND => ErrorKind::TooManyTerms,
+            value if value.fract() != 0.0 => ErrorKind::NonIntegerBound,
+            value => return Ok(value as i64),
+        };
+
+        Err(Error {
+            kind,
+            span: span(node),
+        })
+    }
+
+    fn variable(&self, name: &str) -> Option<f64> {
+        self.bound
+            .iter()
+            .rev()
+            .find(|(bound, _)| bound == name)
+            .map(|(_, value)| *value)
+            .or_else(|| self.environment.variable(name))
+            .or(match name {
+                "e" => Some(consts::E),
+                "pi" => Some(consts::PI),
+                _ => None,
+            })
+    }
+}
+
+fn apply(function: &Function, node: &Node, base: Option<f64>, arguments: &[f64]) -> Result<f64> {
+    let unary = |f: fn(f64) -> f64| match arguments {
+        [x] => Ok(f(*x)),
+        _ => Err(Error {
+            kind: ErrorKind::ArgumentCount {
+                expected: 1,
+                found: arguments.len(),
+            },
+            span: span(node),
+        }),
+    };
+
+    match function {
+        Function::Sin => unary(f64::sin),
+        Function::Cos => unary(f64::cos),
+        Function::Tan => unary(f64::tan),
+        Function::Sec => unary(|x| 1.0 / x.cos()),
+        Function::Csc => unary(|x| 1.0 / x.sin()),
+        Function::Cot => unary(|x| 1.0 / x.tan()),
+        Function::Arcsin => unary(f64::asin),
+        Function::Arccos => unary(f64::acos),
+        Function::Arctan => unary(f64::atan),
+        Function::Sinh => unary(f64::sinh),
+        Function::Cosh => unary(f64::cosh),
+        Function::Tanh => unary(f64::tanh),
+        Function::Sech => unary(|x| 1.0 / x.cosh()),
+        Function::Csch => unary(|x| 1.0 / x.sinh()),
+        Function::Coth => unary(|x| 1.0 / x.tanh()),
+        Function::Exp => unary(f64::exp),
+        Function::Ln => unary(f64::ln),
+        Function::Log => Ok(unary(f64::log10)? / base.map_or(1.0, f64::log10)),
+        Function::Min => Ok(arguments.iter().copied().fold(f64::INFINITY, f64::min)),
+        Function::Max => Ok(arguments.iter().copied().fold(f64::NEG_INFINITY, f64::max)),
+        Function::Gcd => Ok(arguments.iter().copied().fold(0.0, gcd)),
+        Function::Lcm => Ok(arguments
+            .iter()
+            .copied()
+            .fold(1.0, |a, b| (a * b).abs() / gcd(a, b))),
+        Function::Det
+        | Function::Dim
+        | Function::Mod
+        | Function::Lub
+        | Function::Glb
+        | Function::F
+        | Function::G => Err(Error {
+            kind: ErrorKind::Unsupported(format!("`{function:?}`").to_lowercase()),
+            span: span(node),
+        }),
+    }
+}
+
+fn gcd(a: f64, b: f64) -> f64 {
+    if a.fract() != 0.0 || b.fract() != 0.0 {
+        return f64::NAN;
+    }
+
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0.0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+///
+/// The `index`th root, real for negative radicands and odd indices.
+///
+fn root(index: f64, radicand: f64) -> f64 {
+    if radicand < 0.0 && index.fract() == 0.0 && index % 2.0 != 0.0 {
+        -(-radicand).powf(1.0 / index)
+    } else {
+        radicand.powf(1.0 / index)
+    }
+}
+
+fn span(node: &Node) -> Range<usize> {
+    node.span.clone().unwrap_or(0..0)
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::PI;
+
+    use crate::to_ast;
+
+    use super::{evaluate, Environment, Error, ErrorKind};
+
+    fn eval(asciimath: &str) -> Result<f64, Error> {
+        let mut environment = Environment::new();
+        environment.set_variable("x", 2.0);
+        environment.set_variable("x_1", 5.0);
+        environment.set_function("f", |arguments| arguments.iter().sum());
+        environment.set_function("h", |arguments| arguments[0] * 10.0);
+        evaluate(&to_ast(asciimath), &environment)
+    }
+
+    fn assert_eval(asciimath: &str, expected: f64) {
+        let value = eval(asciimath).unwrap();
+        assert!(
+            (value - expected).abs() < 1e-9,
+            "{asciimath} evaluated to {value}, expected {expected}"
+        );
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eval("1 + 2 * 3 - 4", 3.0);
+        assert_eval("2x^2 - -x", 10.0);
+        assert_eval("(1+x)/2 + 6 -: 4", 3.0);
+        assert_eval("7 mod 3 + 2 xx 3", 7.0);
+        assert_eval("2(x+1) pi", 6.0 * PI);
+        assert_eval("x_1 e^0", 5.0);
+    }
+
+    #[test]
+    fn test_functions() {
+        assert_eval("sin(pi/2) + cos 0", 2.0);
+        assert_eval("sin^2 x + cos(x)^2", 1.0);
+        assert_eval("log_2 8 + log(100) + ln e", 6.0);
+        assert_eval(
+            "sqrt 16 + root(3)(-8) + abs(-1) + floor(1.5) + ceil(1.5)",
+            6.0,
+        );
+        assert_eval("max(1, x, 3) - min(4, 5) + gcd(12, 18)", 5.0);
+        assert_eval("f(1, 2, 3) + h(x)", 26.0);
+    }
+
+    #[test]
+    fn test_big_operators() {
+        assert_eval("sum_(i=1)^4 i^2 + 1", 31.0);
+        assert_eval("prod_(k=1)^x (k+1)", 6.0);
+        assert_eval("sum_(i=3)^1 i", 0.0);
+        assert_eval("sum_(i=1)^3 sum_(j=1)^i j", 10.0);
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = eval("1 + y").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::UnboundVariable("y".to_string()));
+        assert_eq!(error.span, 4..5);
+        assert_eq!(error.to_string(), "unbound variable `y`");
+        assert_eq!(
+            eval("sum_(i=1)^3 j").unwrap_err().kind,
+            ErrorKind::UnboundVariable("j".to_string())
+        );
+
+        let error = eval("1 / (x - 2)").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::DivisionByZero);
+        assert_eq!(error.span, 0..11);
+        assert_eq!(error.to_string(), "division by zero");
+        assert_eq!(eval("3 mod 0").unwrap_err().kind, ErrorKind::DivisionByZero);
+        assert_eq!(eval("0 / 1").unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_calls() {
+        assert_eq!(eval("1 +").unwrap_err().kind, ErrorKind::MissingOperand);
+        assert_eq!(eval("k(1)").unwrap_err().span, 0..1);
+        assert_eq!(
+            eval("g(1)").unwrap_err().kind,
+            ErrorKind::UnknownFunction("g".to_string())
+        );
+        assert_eq!(
+            eval("sin(1, 2)").unwrap_err().kind,
+            ErrorKind::ArgumentCount {
+                expected: 1,
+                found: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_unsupported() {
+        assert_eq!(
+            eval("int_0^1 x dx").unwrap_err().kind,
+            ErrorKind::Unsupported("int".to_string())
+        );
+        assert_eq!(
+            eval("lim_(x->0) x").unwrap_err().kind,
+            ErrorKind::Unsupported("lim".to_string())
+        );
+        assert_eq!(
+            eval("x = 2").unwrap_err().to_string(),
+            "cannot evaluate relations"
+        );
+        assert_eq!(eval("[[1,2],[3,4]]").unwrap_err().span, 0..13);
+        assert_eq!(
+            eval("2^3^2").unwrap_err().kind,
+            ErrorKind::Unsupported("`^`".to_string())
+        );
+
+        let error = eval("sum_(i=1.5)^3 i").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::NonIntegerBound);
+        assert_eq!(error.span, 7..10);
+        assert_eq!(
+            eval("sum_(i=1)^(10^9) i").unwrap_err().kind,
+            ErrorKind::TooManyTerms
+        );
+    }
+
+    #[test]
+    fn test_term_limit() {
+        let error = eval("sum_(i=100000000000000000)^(100000000000000010) 1").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::TooManyTerms);
+        assert_eq!(error.span, 7..25);
+
+        // the inner sum runs out of the terms the outer one left
+        let error = eval("sum_(i=1)^999999 sum_(j=1)^999999 1").unwrap_err();
+        assert_eq!(error.kind, ErrorKind::TooManyTerms);
+        assert_eq!(error.span, 17..35);
+
+        assert_eq!(
+            eval("sum_(i=-oo)^0 i").unwrap_err().kind,
+            ErrorKind::TooManyTerms
+        );
+        assert_eq!(eval("sum_(i=1)^1000 sum_(j=1)^999 1").unwrap(), 999_000.0);
+    }
+}
@@ -0,0 +1,519 @@
+//!
+//! # Incremental
+//!
+//! Re-parsing support for editors that convert the same formula on every
+//! keystroke. A [`Formula`] keeps the tokens of the current text and the
+//! MathML of every top level expression. An edit only re-tokenizes the text
+//! around the edited range until the tokens line up with the previous ones
+//! again and only re-parses the top level expressions containing tokens that
+//! actually changed. Everything else is reused.
+//!
+//! Two kinds of edits cause more work: Adding or removing a `"` re-tokenizes
+//! the whole text since a text reaches up to the next quote and changing a
+//! bracket re-parses the whole formula since brackets are matched across the
+//! entire token stream.
+//!
+
+use std::ops::Range;
+
+use crate::{
+    ast::{predictive::convert, Expression, Expressions, AST},
+    cst::predictive::parse,
+    mathml::{expression_to_mathml, math_open, Options, MATH_CLOSE},
+    tokens::{longest_pattern, tokenize_from, types::TokenType, Span, Token},
+    transformations::transform,
+};
+
+///
+/// Replaces the bytes in `range` of the previous text with `text`.
+///
+#[derive(Debug, Clone)]
+pub struct TextEdit<'a> {
+    pub range: Range<usize>,
+    pub text: &'a str,
+}
+
+///
+/// The result of applying a [`TextEdit`] to a [`Formula`].
+///
+#[derive(Debug, Clone)]
+pub struct Update<'a> {
+    /// The top level expressions that have been parsed again
+    pub expressions: Expressions<'a>,
+    /// Indices of the re-parsed top level expressions within the formula
+    pub fragments: Range<usize>,
+    /// Byte range of the previous MathML output that has been replaced
+    pub replaced: Range<usize>,
+    /// Byte range of the new MathML output that replaced it
+    pub output: Range<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Lexeme {
+    start: usize,
+    end: usize,
+    span: Range<usize>,
+    token_type: TokenType,
+}
+
+#[derive(Debug, Clone)]
+struct Fragment {
+    tokens: Range<usize>,
+    mathml: String,
+}
+
+///
+/// A formula that is kept up to date by applying edits to it.
+///
+#[derive(Debug, Clone, Default)]
+pub struct Formula {
+    options: Options,
+    text: String,
+    lexemes: Vec<Lexeme>,
+    fragments: Vec<Fragment>,
+}
+
+impl Formula {
+    pub fn new(text: &str) -> Self {
+        Self::with_options(text, Options::default())
+    }
+
+    pub fn with_options(text: &str, options: Options) -> Self {
+        let lexemes = lex(text, 0).collect::<Vec<Lexeme>>();
+        let fragments = parse_fragments(text, &lexemes, 0..lexemes.len())
+            .into_iter()
+            .map(|(tokens, expression)| Fragment {
+                tokens,
+                mathml: expression_to_mathml(&expression, &options),
+            })
+            .collect();
+
+        Self {
+            options,
+            text: text.to_owned(),
+            lexemes,
+            fragments,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    ///
+    /// The MathML of the current text, equal to what
+    /// [`crate::to_math_ml_with_options`] returns for it.
+    ///
+    pub fn to_math_ml(&self) -> String {
+        format!(
+            "{}{}{MATH_CLOSE}",
+            math_open(&self.options),
+            self.fragments
+                .iter()
+                .map(|fragment| fragment.mathml.as_str())
+                .collect::<String>()
+        )
+    }
+
+    ///
+    /// The transformed AST of the current text. The stored tokens are reused,
+    /// every top level expression is parsed on its own.
+    ///
+    pub fn ast(&self) -> AST<'_> {
+        AST {
+            expressions: Expressions {
+                expressions: self
+                    .fragments
+                    .iter()
+                    .flat_map(|fragment| {
+                        parse_fragments(&self.text, &self.lexemes, fragment.tokens.clone())
+                    })
+                    .map(|(_, expression)| expression)
+                    .collect(),
+            },
+        }
+    }
+
+    ///
+    /// Applies `edit` to the text and updates tokens, expressions and output.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range of the edit is out of bounds or does not lie on
+    /// char boundaries, just like [`String::replace_range`].
+    ///
+    pub fn edit(&mut self, edit: TextEdit) -> Update<'_> {
+        let TextEdit { range, text } = edit;
+        let requote = self.text[range.clone()].contains('"') || text.contains('"');
+        let inserted_end = range.start + text.len();
+
+        self.text.replace_range(range.clone(), text);
+
+        // re-tokenize until a token ends where a previous token ended after the edit
+        let first = if requote {
+            0
+        } else {
+            let lookahead = longest_pattern();
+            self.lexemes
+                .partition_point(|lexeme| lexeme.end + lookahead <= range.start)
+        };
+        let resume = first
+            .checked_sub(1)
+            .map(|index| self.lexemes[index].end)
+            .unwrap_or(0);
+        let mut relexed = Vec::new();
+        let mut old_end = self.lexemes.len();
+
+        for lexeme in lex(&self.text, resume) {
+            let end = lexeme.end;
+            relexed.push(lexeme);
+
+            if !requote && end >= inserted_end {
+                let old_pos = end - inserted_end + range.end;
+
+                if let Ok(index) =
+                    self.lexemes[first..].binary_search_by_key(&old_pos, |lexeme| lexeme.end)
+                {
+                    old_end = first + index + 1;
+                    break;
+                }
+            }
+        }
+
+        let shift = |pos: usize| pos - range.end + inserted_end;
+        let shifted = |lexeme: &Lexeme| Lexeme {
+            start: shift(lexeme.start),
+            end: shift(lexeme.end),
+            span: shift(lexeme.span.start)..shift(lexeme.span.end),
+            token_type: lexeme.token_type.clone(),
+        };
+
+        // narrow the changed tokens down to the ones that actually differ
+        let prefix = self.lexemes[first..old_end]
+            .iter()
+            .zip(relexed.iter())
+            .take_while(|(old, new)| new.end <= range.start && old == new)
+            .count();
+        let suffix = self.lexemes[first + prefix..old_end]
+            .iter()
+            .rev()
+            .zip(relexed[prefix..].iter().rev())
+            .take_while(|(old, new)| old.start >= range.end && shifted(old) == **new)
+            .count();
+        let changed = first + prefix..old_end - suffix;
+        let changed_new = first + prefix..first + relexed.len() - suffix;
+        let rebrace = self.lexemes[changed.clone()]
+            .iter()
+            .chain(relexed[prefix..relexed.len() - suffix].iter())
+            .any(|lexeme| {
+                matches!(
+                    lexeme.token_type,
+                    TokenType::LBrace(_) | TokenType::RBrace(_)
+                )
+            });
+
+        let tail = self.lexemes[old_end..]
+            .iter()
+            .map(shifted)
+            .collect::<Vec<_>>();
+        self.lexemes.truncate(first);
+        self.lexemes.append(&mut relexed);
+        self.lexemes.extend(tail);
+
+        let token_shift = |index: usize| index + changed_new.end - changed.end;
+
+        let (window_start, parsed, reused) = if changed.is_empty() && changed_new.is_empty() {
+            // only whitespace changed, nothing needs to be parsed again
+            let moved = self.fragments.partition_point(|fragment| {
+                self.lexemes[fragment.tokens.end - 1].end <= range.start
+            });
+
+            (moved, Vec::new(), moved)
+        } else {
+            // re-parse from the expression in front of the change (it may be the
+            // base of a sub, sup or fraction now) until the parser arrives at the
+            // start of a previous expression behind the change again
+            let (window_start, after) = if rebrace {
+                (0, self.fragments.len())
+            } else {
+                let touched = self
+                    .fragments
+                    .partition_point(|fragment| fragment.tokens.end <= changed.start);
+                let after = self
+                    .fragments
+                    .partition_point(|fragment| fragment.tokens.start < changed.end);
+
+                (touched.saturating_sub(1), after)
+            };
+            let start_token = self
+                .fragments
+                .get(window_start)
+                .map(|fragment| fragment.tokens.start)
+                .unwrap_or(changed.start.min(changed_new.start));
+
+            let mut count = 1;
+            loop {
+                let window_end = after + count;
+                let end_token = self
+                    .fragments
+                    .get(window_end - 1)
+                    .map(|fragment| token_shift(fragment.tokens.end))
+                    .unwrap_or(self.lexemes.len());
+                let mut parsed = parse_fragments(&self.text, &self.lexemes, start_token..end_token);
+
+                let resync = (after..window_end.min(self.fragments.len())).find_map(|index| {
+                    let start = token_shift(self.fragments[index].tokens.start);
+                    parsed
+                        .iter()
+                        .position(|(tokens, _)| tokens.start == start)
+                        .map(|position| (index, position))
+                });
+
+                if let Some((index, position)) = resync {
+                    parsed.truncate(position);
+                    break (window_start, parsed, index);
+                } else if end_token == self.lexemes.len() {
+                    break (window_start, parsed, self.fragments.len());
+                }
+
+                count *= 2;
+            }
+        };
+
+        let prefix_len = math_open(&self.options).len()
+            + self.fragments[..window_start]
+                .iter()
+                .map(|fragment| fragment.mathml.len())
+                .sum::<usize>();
+        let replaced_len = self.fragments[window_start..reused]
+            .iter()
+            .map(|fragment| fragment.mathml.len())
+            .sum::<usize>();
+
+        let new_fragments = parsed
+            .iter()
+            .map(|(tokens, expression)| Fragment {
+                tokens: tokens.clone(),
+                mathml: expression_to_mathml(expression, &self.options),
+            })
+            .collect::<Vec<Fragment>>();
+        let output_len = new_fragments
+            .iter()
+            .map(|fragment| fragment.mathml.len())
+            .sum::<usize>();
+        let fragments = window_start..window_start + new_fragments.len();
+
+        let tail = self
+            .fragments
+            .split_off(reused)
+            .into_iter()
+            .map(|fragment| Fragment {
+                tokens: token_shift(fragment.tokens.start)..token_shift(fragment.tokens.end),
+                mathml: fragment.mathml,
+            });
+        self.fragments.truncate(window_start);
+        self.fragments.extend(new_fragments);
+        self.fragments.extend(tail);
+
+        // source positions behind the edit have moved
+        let (moved_len, rendered_len) =
+            if self.options.source_positions && text.len() != range.len() {
+                let moved = &mut self.fragments[fragments.end..];
+                let moved_len = moved.iter().map(|fragment| fragment.mathml.len()).sum();
+
+                moved.iter_mut().for_each(|fragment| {
+                    fragment.mathml =
+                        parse_fragments(&self.text, &self.lexemes, fragment.tokens.clone())
+                            .iter()
+                            .map(|(_, expression)| expression_to_mathml(expression, &self.options))
+                            .collect()
+                });
+
+                (
+                    moved_len,
+                    moved.iter().map(|fragment| fragment.mathml.len()).sum(),
+                )
+            } else {
+                (0, 0)
+            };
+
+        Update {
+            expressions: Expressions {
+                expressions: parsed
+                    .into_iter()
+                    .map(|(_, expression)| expression)
+                    .collect(),
+            },
+            fragments,
+            replaced: prefix_len..prefix_len + replaced_len + moved_len,
+            output: prefix_len..prefix_len + output_len + rendered_len,
+        }
+    }
+}
+
+fn lex(text: &str, start: usize) -> impl Iterator<Item = Lexeme> + '_ {
+    tokenize_from(text, start)
+        // the tokenizer emits an empty token for whitespace at the very end
+        .filter(|(_, token, _)| token.token_type != TokenType::None)
+        .map(|(start, token, end)| Lexeme {
+            start,
+            end,
+            span: token.span.start..token.span.end,
+            token_type: token.token_type,
+        })
+}
+
+///
+/// Parses the given tokens and pairs every resulting top level expression
+/// with the range of tokens it has been parsed from.
+///
+fn parse_fragments<'a>(
+    text: &'a str,
+    lexemes: &[Lexeme],
+    range: Range<usize>,
+) -> Vec<(Range<usize>, Expression<'a>)> {
+    let window = &lexemes[range.clone()];
+    let tokens = window
+        .iter()
+        .map(|lexeme| Token {
+            span: Span {
+                text: &text[lexeme.span.clone()],
+                start: lexeme.span.start,
+                end: lexeme.span.end,
+            },
+            token_type: lexeme.token_type.clone(),
+        })
+        .collect::<Vec<Token<'a>>>();
+    let ast = transform(convert(&parse(&tokens)));
+    let count = ast.expressions.expressions.len();
+    let mut start = range.start;
+
+    ast.expressions
+        .expressions
+        .into_iter()
+        .enumerate()
+        .map(|(index, expression)| {
+            let mut last = None;
+            expression.for_each_token(&mut |token| {
+                if token.token_type != TokenType::None {
+                    last = Some(token.span.start);
+                }
+            });
+
+            let end = if index + 1 == count {
+                range.end
+            } else {
+                last.map(|last| {
+                    range.start + window.partition_point(|lexeme| lexeme.span.start <= last)
+                })
+                .unwrap_or(start)
+            };
+            let tokens = start..end;
+
+            start = end;
+            (tokens, expression)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{mathml::Options, to_math_ml, to_math_ml_with_options};
+
+    use super::{Formula, TextEdit};
+
+    fn apply(text: &str, edits: &[(std::ops::Range<usize>, &str)]) {
+        let mut formula = Formula::new(text);
+
+        for (range, replacement) in edits {
+            let before = formula.to_math_ml();
+            let update = formula.edit(TextEdit {
+                range: range.clone(),
+                text: replacement,
+            });
+            let (replaced, output) = (update.replaced.clone(), update.output.clone());
+            let after = formula.to_math_ml();
+
+            assert_eq!(after, to_math_ml(formula.text()), "{}", formula.text());
+            assert_eq!(before[..replaced.start], after[..output.start]);
+            assert_eq!(before[replaced.end..], after[output.end..]);
+        }
+    }
+
+    #[test]
+    fn test_typing() {
+        let text = "sum_(i=1)^n i^3=((n(n+1))/2)^2";
+        let mut edits = Vec::new();
+
+        for (index, c) in text.char_indices() {
+            edits.push((index..index, &text[index..index + c.len_utf8()]));
+        }
+
+        apply("", &edits);
+    }
+
+    #[test]
+    fn test_deleting() {
+        let text = "[[1, |, 2], [a, b, c]] + obrace(ubrace(t)_(a))^ba";
+        let edits = (0..text.len())
+            .rev()
+            .map(|index| (index..index + 1, ""))
+            .collect::<Vec<_>>();
+
+        apply(text, &edits);
+    }
+
+    #[test]
+    fn test_merging_tokens() {
+        apply(
+            "s u a/b",
+            &[(1..2, ""), (2..2, "m"), (6..6, "/c"), (3..3, "_")],
+        );
+    }
+
+    #[test]
+    fn test_texts_and_brackets() {
+        apply(
+            "a \"text\" (b) c",
+            &[
+                (3..3, "x"),
+                (2..3, ""),
+                (9..10, ""),
+                (0..0, "("),
+                (13..13, ")"),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_output_range() {
+        let mut formula = Formula::new("a+b+c");
+        let update = formula.edit(TextEdit {
+            range: 2..3,
+            text: "x",
+        });
+        let (fragments, output) = (update.fragments, update.output);
+
+        assert_eq!(fragments, 1..3);
+        assert_eq!(&formula.to_math_ml()[output], "<mo>+</mo><mi>x</mi>");
+    }
+
+    #[test]
+    fn test_source_positions() {
+        let options = Options {
+            source_positions: true,
+            ..Default::default()
+        };
+        let mut formula = Formula::with_options("[[1,2],[3,4]] + x", options.clone());
+
+        formula.edit(TextEdit {
+            range: 0..0,
+            text: "y  ",
+        });
+
+        assert_eq!(
+            formula.to_math_ml(),
+            to_math_ml_with_options("y  [[1,2],[3,4]] + x", &options)
+        );
+    }
+}
@@ -0,0 +1,545 @@
+//!
+//! # LaTeX
+//!
+//! LaTeX backend, renders the AST as math mode LaTeX without surrounding
+//! delimiters. Brackets become `\left`/`\right` pairs, tables become
+//! environments by their kind, `pmatrix`, `cases` or `array` for example.
+//!
+
+use itertools::Itertools;
+
+use crate::{
+    ast::{
+        BiExpression, Binary, ColumnAlign, Expression, Expressions, Group, Literal, Table,
+        TableKind, Unary, AST,
+    },
+    tokens::{
+        types::{
+            Arrow, BinaryOperator, Function, Greek, LBrace, Logical, Misc, Operation, RBrace,
+            Relational, TokenType, UnaryOperator,
+        },
+        Token,
+    },
+};
+
+pub fn to_latex(ast: &AST) -> String {
+    expressions_to_latex(&ast.expressions)
+}
+
+fn expressions_to_latex(expressions: &Expressions) -> String {
+    expressions
+        .expressions
+        .iter()
+        .map(expression_to_latex)
+        .filter(|latex| !latex.is_empty())
+        .join(" ")
+}
+
+///
+/// Renders a single expression.
+///
+pub fn expression_to_latex(expression: &Expression) -> String {
+    match expression {
+        Expression::Frac(frac) => format!(
+            "\\frac{{{}}}{{{}}}",
+            braceless(&frac.expression_1),
+            braceless(&frac.expression_2)
+        ),
+        Expression::Sub(bi_expression) => script(bi_expression, "_"),
+        Expression::Pow(bi_expression) => script(bi_expression, "^"),
+        Expression::SubPow(tri_expression) => format!(
+            "{}_{{{}}}^{{{}}}",
+            base(&tri_expression.expression_1),
+            braceless(&tri_expression.expression_2),
+            braceless(&tri_expression.expression_3)
+        ),
+        Expression::Group(group) => group_to_latex(group),
+        Expression::Unary(unary) => unary_to_latex(unary),
+        Expression::Binary(binary) => binary_to_latex(binary),
+        Expression::Literal(Literal::Literal(token)) => token_to_latex(token),
+        Expression::Literal(Literal::Table(table)) => table_to_latex(table),
+        Expression::Expressions(expressions) => expressions_to_latex(expressions),
+        Expression::Unit => String::new(),
+    }
+}
+
+///
+/// Renders the content of a group without its brackets, they are implied
+/// by the LaTeX argument braces.
+///
+fn braceless(expression: &Expression) -> String {
+    match expression {
+        Expression::Group(group) => expressions_to_latex(&group.expressions),
+        _ => expression_to_latex(expression),
+    }
+}
+
+///
+/// Wraps scripted bases that are scripts themselves, `{x_1}^2`.
+///
+fn base(expression: &Expression) -> String {
+    match expression {
+        Expression::Sub(_) | Expression::Pow(_) | Expression::SubPow(_) => {
+            format!("{{{}}}", expression_to_latex(expression))
+        }
+        _ => expression_to_latex(expression),
+    }
+}
+
+fn script(bi_expression: &BiExpression, operator: &str) -> String {
+    format!(
+        "{}{operator}{{{}}}",
+        base(&bi_expression.expression_1),
+        braceless(&bi_expression.expression_2)
+    )
+}
+
+fn group_to_latex(group: &Group) -> String {
+    format!(
+        "\\left{} {} \\right{}",
+        l_brace_to_latex(&group.l_brace),
+        expressions_to_latex(&group.expressions),
+        r_brace_to_latex(&group.r_brace)
+    )
+}
+
+fn unary_to_latex(unary: &Unary) -> String {
+    let TokenType::UnaryOperator(operator) = &unary.operator.token_type else {
+        return braceless(&unary.expression);
+    };
+
+    let (open, close) = match operator {
+        UnaryOperator::Abs => ("\\left|", "\\right|"),
+        UnaryOperator::Floor => ("\\left\\lfloor", "\\right\\rfloor"),
+        UnaryOperator::Ceil => ("\\left\\lceil", "\\right\\rceil"),
+        UnaryOperator::Norm => ("\\left\\|", "\\right\\|"),
+        UnaryOperator::Text => {
+            return format!("\\text{{{}}}", escape_text(&source_text(&unary.expression)))
+        }
+        _ => {
+            let command = match operator {
+                UnaryOperator::Hat => "hat",
+                UnaryOperator::Bar => "overline",
+                UnaryOperator::Ul => "underline",
+                UnaryOperator::Vec => "vec",
+                UnaryOperator::Tilde => "tilde",
+                UnaryOperator::Dot => "dot",
+                UnaryOperator::DDot => "ddot",
+                UnaryOperator::UBrace => "underbrace",
+                UnaryOperator::OBrace => "overbrace",
+                UnaryOperator::Cancel => "cancel",
+                _ => "sqrt",
+            };
+            return format!("\\{command}{{{}}}", braceless(&unary.expression));
+        }
+    };
+
+    format!("{open} {} {close}", braceless(&unary.expression))
+}
+
+fn binary_to_latex(binary: &Binary) -> String {
+    let first = braceless(&binary.expression_1);
+    let second = braceless(&binary.expression_2);
+
+    match binary.operator.token_type {
+        TokenType::BinaryOperator(BinaryOperator::Root) => format!("\\sqrt[{first}]{{{second}}}"),
+        TokenType::BinaryOperator(BinaryOperator::Overset) => {
+            format!("\\overset{{{first}}}{{{second}}}")
+        }
+        TokenType::BinaryOperator(BinaryOperator::Underset) => {
+            format!("\\underset{{{first}}}{{{second}}}")
+        }
+        TokenType::BinaryOperator(BinaryOperator::Color) => {
+            format!(
+                "{{\\color{{{}}} {second}}}",
+                source_text(&binary.expression_1)
+            )
+        }
+        _ => format!("{} {first} {second}", binary.operator.span.text),
+    }
+}
+
+///
+/// Tables with vertical separators or alignment of their own become `array`
+/// environments.
+///
+fn table_to_latex(table: &Table) -> String {
+    let rows = table
+        .rows
+        .iter()
+        .map(|row| row.cols.iter().map(expressions_to_latex).join(" & "))
+        .join(" \\\\ ");
+
+    let environment = match table.kind() {
+        TableKind::Cases => return format!("\\begin{{cases}} {rows} \\end{{cases}}"),
+        TableKind::Matrix | TableKind::ColumnVector => match bracketed_matrix(table) {
+            Some(matrix) => return format!("\\begin{{{matrix}}} {rows} \\end{{{matrix}}}"),
+            None => format!("\\begin{{matrix}} {rows} \\end{{matrix}}"),
+        },
+        _ => {
+            let spec = table
+                .column_alignment()
+                .iter()
+                .enumerate()
+                .map(|(index, align)| {
+                    let align = match align {
+                        ColumnAlign::Left => "l",
+                        ColumnAlign::Center => "c",
+                        ColumnAlign::Right => "r",
+                    };
+                    if index > 0 && table.seperators.contains(&index) {
+                        format!("|{align}")
+                    } else {
+                        align.to_string()
+                    }
+                })
+                .join("");
+            format!("\\begin{{array}}{{{spec}}} {rows} \\end{{array}}")
+        }
+    };
+
+    format!(
+        "\\left{} {environment} \\right{}",
+        l_brace_to_latex(&table.l_brace),
+        r_brace_to_latex(&table.r_brace)
+    )
+}
+
+///
+/// The matrix environment that comes with the brackets of `table`.
+///
+fn bracketed_matrix(table: &Table) -> Option<&'static str> {
+    match (&table.l_brace.token_type, &table.r_brace.token_type) {
+        (TokenType::LBrace(LBrace::LParen), TokenType::RBrace(RBrace::RParen)) => Some("pmatrix"),
+        (TokenType::LBrace(LBrace::LBracket), TokenType::RBrace(RBrace::RBracket)) => {
+            Some("bmatrix")
+        }
+        (TokenType::LBrace(LBrace::LBrace), TokenType::RBrace(RBrace::RBrace)) => Some("Bmatrix"),
+        _ => None,
+    }
+}
+
+///
+/// Source text of the tokens of `expression`, used where the content is
+/// not math, like `text(...)` or the color of `color(...)(...)`.
+///
+fn source_text(expression: &Expression) -> String {
+    let expression = match expression {
+        Expression::Group(group) => Expression::Expressions(group.expressions.clone()),
+        _ => expression.clone(),
+    };
+
+    let mut text = String::new();
+    expression.for_each_token(&mut |token| text.push_str(token.span.text));
+    text
+}
+
+fn escape_text(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\\' => "\\textbackslash{}".to_string(),
+            '~' => "\\textasciitilde{}".to_string(),
+            '^' => "\\textasciicircum{}".to_string(),
+            '#' | '$' | '%' | '&' | '_' | '{' | '}' => format!("\\{c}"),
+            _ => c.to_string(),
+        })
+        .collect()
+}
+
+fn l_brace_to_latex(token: &Token) -> &'static str {
+    match &token.token_type {
+        TokenType::LBrace(LBrace::LParen) => "(",
+        TokenType::LBrace(LBrace::LBracket) => "[",
+        TokenType::LBrace(LBrace::LBrace) => "\\{",
+        TokenType::LBrace(LBrace::LAngle) => "\\langle",
+        _ => ".",
+    }
+}
+
+fn r_brace_to_latex(token: &Token) -> &'static str {
+    match &token.token_type {
+        TokenType::RBrace(RBrace::RParen) => ")",
+        TokenType::RBrace(RBrace::RBracket) => "]",
+        TokenType::RBrace(RBrace::RBrace) => "\\}",
+        TokenType::RBrace(RBrace::RAngle) => "\\rangle",
+        _ => ".",
+    }
+}
+
+fn token_to_latex(token: &Token) -> String {
+    match &token.token_type {
+        TokenType::Greek(greek) => greek_to_latex(greek).to_string(),
+        TokenType::Operation(operation) => operation_to_latex(operation).to_string(),
+        TokenType::Misc(misc) => misc_to_latex(misc).to_string(),
+        TokenType::Relational(relational) => relational_to_latex(relational).to_string(),
+        TokenType::Arrow(arrow) => arrow_to_latex(arrow).to_string(),
+        TokenType::Logical(logical) => logical_to_latex(logical).to_string(),
+        TokenType::Function(function) => function_to_latex(function).to_string(),
+        TokenType::LBrace(_) => l_brace_to_latex(token).to_string(),
+        TokenType::RBrace(_) => r_brace_to_latex(token).to_string(),
+        TokenType::Text => format!("\\text{{{}}}", escape_text(token.span.text)),
+        TokenType::None => String::new(),
+        _ => escape_text(token.span.text),
+    }
+}
+
+fn greek_to_latex(greek: &Greek) -> &'static str {
+    match greek {
+        Greek::Alpha => "\\alpha",
+        Greek::Beta => "\\beta",
+        Greek::Gamma => "\\gamma",
+        Greek::UGamma => "\\Gamma",
+        Greek::Delta => "\\delta",
+        Greek::UDelta => "\\Delta",
+        Greek::Epsilon => "\\epsilon",
+        Greek::VarEpsilon => "\\varepsilon",
+        Greek::Zeta => "\\zeta",
+        Greek::Eta => "\\eta",
+        Greek::Theta => "\\theta",
+        Greek::UTheta => "\\Theta",
+        Greek::VarTheta => "\\vartheta",
+        Greek::Iota => "\\iota",
+        Greek::Kappa => "\\kappa",
+        Greek::Lambda => "\\lambda",
+        Greek::ULambda => "\\Lambda",
+        Greek::Mu => "\\mu",
+        Greek::Nu => "\\nu",
+        Greek::Xi => "\\xi",
+        Greek::UXi => "\\Xi",
+        Greek::Pi => "\\pi",
+        Greek::UPi => "\\Pi",
+        Greek::Rho => "\\rho",
+        Greek::Sigma => "\\sigma",
+        Greek::USigma => "\\Sigma",
+        Greek::Tau => "\\tau",
+        Greek::Upsilon => "\\upsilon",
+        Greek::Phi => "\\phi",
+        Greek::UPhi => "\\Phi",
+        Greek::VarPhi => "\\varphi",
+        Greek::Chi => "\\chi",
+        Greek::Psi => "\\psi",
+        Greek::UPsi => "\\Psi",
+        Greek::Omega => "\\omega",
+        Greek::UOmega => "\\Omega",
+    }
+}
+
+fn operation_to_latex(operation: &Operation) -> &'static str {
+    match operation {
+        Operation::Plus => "+",
+        Operation::Minus => "-",
+        Operation::CDot => "\\cdot",
+        Operation::Ast => "\\ast",
+        Operation::Star => "\\star",
+        Operation::Slash => "/",
+        Operation::Backslash => "\\backslash",
+        Operation::Times => "\\times",
+        Operation::Div => "\\div",
+        Operation::LTimes => "\\ltimes",
+        Operation::RTimes => "\\rtimes",
+        Operation::Bowtie => "\\bowtie",
+        Operation::Circ => "\\circ",
+        Operation::OPlus => "\\oplus",
+        Operation::OTimes => "\\otimes",
+        Operation::ODot => "\\odot",
+        Operation::Sum => "\\sum",
+        Operation::Prod => "\\prod",
+        Operation::Wedge => "\\wedge",
+        Operation::BigWedge => "\\bigwedge",
+        Operation::Vee => "\\vee",
+        Operation::BigVee => "\\bigvee",
+        Operation::Cap => "\\cap",
+        Operation::BigCap => "\\bigcap",
+        Operation::Cup => "\\cup",
+        Operation::BigCup => "\\bigcup",
+    }
+}
+
+fn misc_to_latex(misc: &Misc) -> &'static str {
+    match misc {
+        Misc::Int => "\\int",
+        Misc::OInt => "\\oint",
+        Misc::Del => "\\partial",
+        Misc::Grad => "\\nabla",
+        Misc::PlusMinus => "\\pm",
+        Misc::EmptySet => "\\emptyset",
+        Misc::Infinity => "\\infty",
+        Misc::Aleph => "\\aleph",
+        Misc::Therefore => "\\therefore",
+        Misc::Because => "\\because",
+        Misc::LDots => "\\ldots",
+        Misc::CDots => "\\cdots",
+        Misc::VDots => "\\vdots",
+        Misc::DDots => "\\ddots",
+        Misc::DoublePipes => "\\|",
+        Misc::DoublePipesQuad => "|\\quad|",
+        Misc::Angle => "\\angle",
+        Misc::Frown => "\\frown",
+        Misc::Triangle => "\\triangle",
+        Misc::Diamond => "\\diamond",
+        Misc::Square => "\\square",
+        Misc::LFloor => "\\lfloor",
+        Misc::RFloor => "\\rfloor",
+        Misc::LCeiling => "\\lceil",
+        Misc::RCeiling => "\\rceil",
+        Misc::Lim => "\\lim",
+        Misc::CC => "\\mathbb{C}",
+        Misc::NN => "\\mathbb{N}",
+        Misc::QQ => "\\mathbb{Q}",
+        Misc::RR => "\\mathbb{R}",
+        Misc::ZZ => "\\mathbb{Z}",
+    }
+}
+
+fn relational_to_latex(relational: &Relational) -> &'static str {
+    match relational {
+        Relational::Equals => "=",
+        Relational::NotEquals => "\\ne",
+        Relational::Lt => "<",
+        Relational::Gt => ">",
+        Relational::Lte => "\\le",
+        Relational::Gte => "\\ge",
+        Relational::Mlt => "\\ll",
+        Relational::Mgt => "\\gg",
+        Relational::Prec => "\\prec",
+        Relational::PrecEq => "\\preceq",
+        Relational::Succ => "\\succ",
+        Relational::SuccEq => "\\succeq",
+        Relational::In => "\\in",
+        Relational::NotIn => "\\notin",
+        Relational::Sub => "\\subset",
+        Relational::Sup => "\\supset",
+        Relational::SubEq => "\\subseteq",
+        Relational::SupEq => "\\supseteq",
+        Relational::Equiv => "\\equiv",
+        Relational::Cong => "\\cong",
+        Relational::Approx => "\\approx",
+        Relational::Prop => "\\propto",
+    }
+}
+
+fn arrow_to_latex(arrow: &Arrow) -> &'static str {
+    match arrow {
+        Arrow::UpArrow => "\\uparrow",
+        Arrow::DownArrow => "\\downarrow",
+        Arrow::RightArrow => "\\rightarrow",
+        Arrow::ToArrow => "\\to",
+        Arrow::RightArrowTail => "\\rightarrowtail",
+        Arrow::RightArrowTwoHead => "\\twoheadrightarrow",
+        Arrow::RightArrowTwoHeadTail => "\\twoheadrightarrowtail",
+        Arrow::MapsTo => "\\mapsto",
+        Arrow::LeftArrow => "\\leftarrow",
+        Arrow::LeftRightArrow => "\\leftrightarrow",
+        Arrow::DoubleRightArrow => "\\Rightarrow",
+        Arrow::DoubleLeftArrow => "\\Leftarrow",
+        Arrow::DoubleLeftRightArrow => "\\Leftrightarrow",
+    }
+}
+
+fn logical_to_latex(logical: &Logical) -> &'static str {
+    match logical {
+        Logical::And => "\\text{ and }",
+        Logical::Or => "\\text{ or }",
+        Logical::Not => "\\neg",
+        Logical::Implies => "\\Rightarrow",
+        Logical::If => "\\text{ if }",
+        Logical::Iff => "\\Leftrightarrow",
+        Logical::ForAll => "\\forall",
+        Logical::Exists => "\\exists",
+        Logical::Bot => "\\bot",
+        Logical::Top => "\\top",
+        Logical::VDash => "\\vdash",
+        Logical::Models => "\\models",
+    }
+}
+
+fn function_to_latex(function: &Function) -> &'static str {
+    match function {
+        Function::Sin => "\\sin",
+        Function::Cos => "\\cos",
+        Function::Tan => "\\tan",
+        Function::Sec => "\\sec",
+        Function::Csc => "\\csc",
+        Function::Cot => "\\cot",
+        Function::Arcsin => "\\arcsin",
+        Function::Arccos => "\\arccos",
+        Function::Arctan => "\\arctan",
+        Function::Sinh => "\\sinh",
+        Function::Cosh => "\\cosh",
+        Function::Tanh => "\\tanh",
+        Function::Sech => "\\operatorname{sech}",
+        Function::Csch => "\\operatorname{csch}",
+        Function::Coth => "\\coth",
+        Function::Exp => "\\exp",
+        Function::Log => "\\log",
+        Function::Ln => "\\ln",
+        Function::Det => "\\det",
+        Function::Dim => "\\dim",
+        Function::Mod => "\\operatorname{mod}",
+        Function::Gcd => "\\gcd",
+        Function::Lcm => "\\operatorname{lcm}",
+        Function::Lub => "\\operatorname{lub}",
+        Function::Glb => "\\operatorname{glb}",
+        Function::Min => "\\min",
+        Function::Max => "\\max",
+        Function::F => "f",
+        Function::G => "g",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::to_ast;
+
+    use super::to_latex;
+
+    fn latex(asciimath: &str) -> String {
+        to_latex(&to_ast(asciimath))
+    }
+
+    #[test]
+    fn test_expressions() {
+        assert_eq!(latex("(a+b)/6"), "\\frac{a + b}{6}");
+        assert_eq!(latex("sum_(i=1)^n i^3"), "\\sum_{i = 1}^{n} i^{3}");
+        assert_eq!(latex("sqrt(x) != alpha"), "\\sqrt{x} \\ne \\alpha");
+        assert_eq!(latex("root(3)(x)"), "\\sqrt[3]{x}");
+        assert_eq!(latex("abs(x)"), "\\left| x \\right|");
+        assert_eq!(
+            latex("f(x) ~~ RR"),
+            "f \\left( x \\right) \\approx \\mathbb{R}"
+        );
+        assert_eq!(latex("\"a_b\""), "\\text{a\\_b}");
+    }
+
+    #[test]
+    fn test_tables() {
+        assert_eq!(
+            latex("[[1,2],[3,4]]"),
+            "\\begin{bmatrix} 1 & 2 \\\\ 3 & 4 \\end{bmatrix}"
+        );
+        assert_eq!(
+            latex("((1),(2))"),
+            "\\begin{pmatrix} 1 \\\\ 2 \\end{pmatrix}"
+        );
+        assert_eq!(
+            latex("{:(1,2),(3,4):}"),
+            "\\left. \\begin{matrix} 1 & 2 \\\\ 3 & 4 \\end{matrix} \\right."
+        );
+        assert_eq!(
+            latex("{(x, x > 0), (-x, \"otherwise\"):}"),
+            "\\begin{cases} x & x > 0 \\\\ - x & \\text{otherwise} \\end{cases}"
+        );
+        assert_eq!(
+            latex("{(2x,+,y,=,3),(x,-,y,=,0):}"),
+            "\\left\\{ \\begin{array}{rcrcl} 2 x & + & y & = & 3 \\\\ x & - & y & = & 0 \\end{array} \\right."
+        );
+        assert_eq!(
+            latex("[[1,2],[-,-]]"),
+            "\\begin{bmatrix} 1 & 2 \\\\ - & - \\end{bmatrix}"
+        );
+        assert_eq!(
+            latex("[[1, |, 2], [a, |, b]]"),
+            "\\left[ \\begin{array}{c|c} 1 & 2 \\\\ a & b \\end{array} \\right]"
+        );
+    }
+}
@@ -1,18 +1,45 @@
-use ast::predictive::convert;
+use ast::{predictive::convert, AST};
 use cst::predictive::parse;
-use mathml::to_mathml;
+use diagnostics::{check, Diagnostic};
+use mathml::{to_mathml_with_options, Options};
 use tracing::debug;
 use transformations::transform;
 
 use crate::tokens::tokenize;
 
+pub mod analysis;
 pub mod ast;
+pub mod calculus;
 pub mod cst;
+pub mod diagnostics;
+pub mod document;
+pub mod equivalence;
+pub mod eval;
+pub mod incremental;
+pub mod latex;
+#[cfg(feature = "pulldown-cmark")]
+pub mod markdown;
 pub mod mathml;
+pub mod search;
+pub mod semantics;
+pub mod symbols;
+pub mod text;
 pub mod transformations;
 pub mod tokens;
 
 pub fn to_math_ml(content: &str) -> String {
+    to_math_ml_with_options(content, &Options::default())
+}
+
+pub fn to_math_ml_with_options(content: &str, options: &Options) -> String {
+    to_mathml_with_options(&to_ast(content), options)
+}
+
+///
+/// Runs tokenizer, parser and transformations, returning the AST every
+/// backend works with.
+///
+pub fn to_ast(content: &str) -> AST<'_> {
     let tokens = tokenize(content);
     debug!("Tokens: {tokens:#?}");
     let cst = parse(&tokens);
@@ -22,13 +49,44 @@ pub fn to_math_ml(content: &str) -> String {
     let t_ast = transform(ast);
     debug!("AST after transformation: {t_ast:#?}");
 
-    to_mathml(&t_ast)
+    t_ast
+}
+
+///
+/// Output formats [render] supports.
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Format {
+    #[default]
+    MathMl,
+    Latex,
+    Text,
+}
+
+///
+/// Converts `content` to `format`. The options only affect MathML output.
+///
+pub fn render(content: &str, format: Format, options: &Options) -> String {
+    let ast = to_ast(content);
+
+    match format {
+        Format::MathMl => to_mathml_with_options(&ast, options),
+        Format::Latex => latex::to_latex(&ast),
+        Format::Text => text::to_text(&ast),
+    }
+}
+
+///
+/// Reports problems in `content` the parser silently recovers from.
+///
+pub fn diagnose(content: &str) -> Vec<Diagnostic> {
+    check(&to_ast(content))
 }
 
 #[cfg(test)]
 mod test {
     // use tracing::Level;
-    use crate::to_math_ml;
+    use crate::{mathml::Options, to_math_ml, to_math_ml_with_options};
 
     macro_rules! test_parse {
         ($name:ident, $input:expr, $expected:expr) => {
@@ -56,13 +114,13 @@ mod test {
     test_parse!(
         test_vector,
         "[[1,2], [2, 23]]",
-        "<math display=\"block\"><mrow><mo>[</mo><mtable columnlines=\"none\"><mtr><mtd><mn>1</mn></mtd><mtd><mn>2</mn></mtd></mtr><mtr><mtd><mn>2</mn></mtd><mtd><mn>23</mn></mtd></mtr></mtable><mo>]</mo></mrow></math>"
+        "<math display=\"block\"><mrow><mo>[</mo><mtable columnlines=\"none\" rowlines=\"none\" columnalign=\"center center\" rowspacing=\"1.0ex\" columnspacing=\"0.8em\"><mtr><mtd><mn>1</mn></mtd><mtd><mn>2</mn></mtd></mtr><mtr><mtd><mn>2</mn></mtd><mtd><mn>23</mn></mtd></mtr></mtable><mo>]</mo></mrow></math>"
     );
 
    test_parse!(
         test_vector_sum,
         "[[1],[sum_1^2a]]",
-        "<math display=\"block\"><mrow><mo>[</mo><mtable columnlines=\"\"><mtr><mtd><mn>1</mn></mtd></mtr><mtr><mtd><munderover><mo>&#x2211;</mo><mn>1</mn><mn>2</mn></munderover><mi>a</mi></mtd></mtr></mtable><mo>]</mo></mrow></math>"
+        "<math display=\"block\"><mrow><mo>[</mo><mtable columnlines=\"\" rowlines=\"none\" columnalign=\"center\" rowspacing=\"1.0ex\" columnspacing=\"0.8em\"><mtr><mtd><mn>1</mn></mtd></mtr><mtr><mtd><munderover><mo>&#x2211;</mo><mn>1</mn><mn>2</mn></munderover><mi>a</mi></mtd></mtr></mtable><mo>]</mo></mrow></math>"
     );
 
     test_parse!(
@@ -74,19 +132,31 @@ mod test {
     test_parse!(
         test_recusrsive_matrices, 
         "[[[[1],[2]]], [[[a], [b]]]]", 
-        "<math display=\"block\"><mrow><mo>[</mo><mtable columnlines=\"\"><mtr><mtd><mrow><mo>[</mo><mtable columnlines=\"\"><mtr><mtd><mn>1</mn></mtd></mtr><mtr><mtd><mn>2</mn></mtd></mtr></mtable><mo>]</mo></mrow></mtd></mtr><mtr><mtd><mrow><mo>[</mo><mtable columnlines=\"\"><mtr><mtd><mi>a</mi></mtd></mtr><mtr><mtd><mi>b</mi></mtd></mtr></mtable><mo>]</mo></mrow></mtd></mtr></mtable><mo>]</mo></mrow></math>"
+        "<math display=\"block\"><mrow><mo>[</mo><mtable columnlines=\"\" rowlines=\"none\" columnalign=\"center\" rowspacing=\"1.0ex\" columnspacing=\"0.8em\"><mtr><mtd><mrow><mo>[</mo><mtable columnlines=\"\" rowlines=\"none\" columnalign=\"center\" rowspacing=\"1.0ex\" columnspacing=\"0.8em\"><mtr><mtd><mn>1</mn></mtd></mtr><mtr><mtd><mn>2</mn></mtd></mtr></mtable><mo>]</mo></mrow></mtd></mtr><mtr><mtd><mrow><mo>[</mo><mtable columnlines=\"\" rowlines=\"none\" columnalign=\"center\" rowspacing=\"1.0ex\" columnspacing=\"0.8em\"><mtr><mtd><mi>a</mi></mtd></mtr><mtr><mtd><mi>b</mi></mtd></mtr></mtable><mo>]</mo></mrow></mtd></mtr></mtable><mo>]</mo></mrow></math>"
     );
 
     test_parse!(
         test_table_bar, 
         "[[1, |, 2], [a, b, c]]", 
-        "<math display=\"block\"><mrow><mo>[</mo><mtable columnlines=\"solid none\"><mtr><mtd><mn>1</mn></mtd><mtd><mn>2</mn></mtd><mtd></mtd></mtr><mtr><mtd><mi>a</mi></mtd><mtd><mi>b</mi></mtd><mtd><mi>c</mi></mtd></mtr></mtable><mo>]</mo></mrow></math>"
+        "<math display=\"block\"><mrow><mo>[</mo><mtable columnlines=\"solid none\" rowlines=\"none\" columnalign=\"center center center\" rowspacing=\"1.0ex\" columnspacing=\"0.8em\"><mtr><mtd><mn>1</mn></mtd><mtd><mn>2</mn></mtd><mtd></mtd></mtr><mtr><mtd><mi>a</mi></mtd><mtd><mi>b</mi></mtd><mtd><mi>c</mi></mtd></mtr></mtable><mo>]</mo></mrow></math>"
+    );
+
+    test_parse!(
+        test_table_minus_rows,
+        "((1),(-),(2))",
+        "<math display=\"block\"><mrow><mo>(</mo><mtable columnlines=\"\" rowlines=\"none none\" columnalign=\"center\" rowspacing=\"1.0ex\" columnspacing=\"0.8em\"><mtr><mtd><mn>1</mn></mtd></mtr><mtr><mtd><mo>-</mo></mtd></mtr><mtr><mtd><mn>2</mn></mtd></mtr></mtable><mo>)</mo></mrow></math>"
+    );
+
+    test_parse!(
+        test_table_single_row,
+        "[[1, 2]]",
+        "<math display=\"block\"><mrow><mo>[</mo><mtable columnlines=\"none\" columnalign=\"center center\" rowspacing=\"1.0ex\" columnspacing=\"0.8em\"><mtr><mtd><mn>1</mn></mtd><mtd><mn>2</mn></mtd></mtr></mtable><mo>]</mo></mrow></math>"
     );
 
     test_parse!(
         test_table_complex,
         "{:[log_2 16 = 4,], [(2*5-3)/6, ]}",
-        "<math display=\"block\"><mrow><mtable columnlines=\"none\"><mtr><mtd><msub><mi>log</mi><mn>2</mn></msub><mn>16</mn><mo>=</mo><mn>4</mn></mtd><mtd></mtd></mtr><mtr><mtd><mfrac><mrow><mn>2</mn><mo>&#x22C5;</mo><mn>5</mn><mo>-</mo><mn>3</mn></mrow><mn>6</mn></mfrac></mtd><mtd></mtd></mtr></mtable><mo>}</mo></mrow></math>"
+        "<math display=\"block\"><mrow><mtable columnlines=\"none\" rowlines=\"none\" columnalign=\"center center\" rowspacing=\"1.0ex\" columnspacing=\"0.8em\"><mtr><mtd><msub><mi>log</mi><mn>2</mn></msub><mn>16</mn><mo>=</mo><mn>4</mn></mtd><mtd></mtd></mtr><mtr><mtd><mfrac><mrow><mn>2</mn><mo>&#x22C5;</mo><mn>5</mn><mo>-</mo><mn>3</mn></mrow><mn>6</mn></mfrac></mtd><mtd></mtd></mtr></mtable><mo>}</mo></mrow></math>"
     );
     
     test_parse!(
@@ -141,7 +211,7 @@ mod test {
     test_parse!(
         test_ubrace_with_nested_group,
         "ubrace({(2x,+,17y,=,23),(x,-,y,=,5):})_(\"equation system\")",
-        "<math display=\"block\"><munder><munder><munder><mrow><mrow><mo>{</mo><mtable columnlines=\"none none none none\"><mtr><mtd><mn>2</mn><mi>x</mi></mtd><mtd><mo>+</mo></mtd><mtd><mn>17</mn><mi>y</mi></mtd><mtd><mo>=</mo></mtd><mtd><mn>23</mn></mtd></mtr><mtr><mtd><mi>x</mi></mtd><mtd><mo>-</mo></mtd><mtd><mi>y</mi></mtd><mtd><mo>=</mo></mtd><mtd><mn>5</mn></mtd></mtr></mtable></mrow></mrow><mo>&#x23DF;</mo></munder></munder><mrow><mtext>equation system</mtext></mrow></munder></math>"
+        "<math display=\"block\"><munder><munder><munder><mrow><mrow><mo>{</mo><mtable columnlines=\"none none none none\" rowlines=\"none\" columnalign=\"right center right center left\" rowspacing=\"1.0ex\" columnspacing=\"0em\"><mtr><mtd><mn>2</mn><mi>x</mi></mtd><mtd><mo>+</mo></mtd><mtd><mn>17</mn><mi>y</mi></mtd><mtd><mo>=</mo></mtd><mtd><mn>23</mn></mtd></mtr><mtr><mtd><mi>x</mi></mtd><mtd><mo>-</mo></mtd><mtd><mi>y</mi></mtd><mtd><mo>=</mo></mtd><mtd><mn>5</mn></mtd></mtr></mtable></mrow></mrow><mo>&#x23DF;</mo></munder></munder><mrow><mtext>equation system</mtext></mrow></munder></math>"
     );
 
     test_parse!(
@@ -161,4 +231,25 @@ mod test {
         "root (abc)(d) ",
         "<math display=\"block\"><mroot><mrow><mi>d</mi></mrow><mrow><mi>a</mi><mi>b</mi><mi>c</mi></mrow></mroot></math>"
     );
+
+    #[test]
+    fn test_source_positions() {
+        let options = Options {
+            source_positions: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            to_math_ml_with_options("(a+b)/6", &options),
+            "<math display=\"block\"><mfrac data-src-start=\"0\" data-src-end=\"7\"><mrow data-src-start=\"0\" data-src-end=\"5\"><mi data-src-start=\"1\" data-src-end=\"2\">a</mi><mo data-src-start=\"2\" data-src-end=\"3\">+</mo><mi data-src-start=\"3\" data-src-end=\"4\">b</mi></mrow><mn data-src-start=\"6\" data-src-end=\"7\">6</mn></mfrac></math>"
+        );
+        assert_eq!(
+            to_math_ml_with_options("abs x", &options),
+            "<math display=\"block\"><mrow data-src-start=\"0\" data-src-end=\"5\"><mo data-src-start=\"0\" data-src-end=\"3\">|</mo><mi data-src-start=\"4\" data-src-end=\"5\">x</mi><mo data-src-start=\"0\" data-src-end=\"3\">|</mo></mrow></math>"
+        );
+        assert_eq!(
+            to_math_ml_with_options("\"a<b\" mlt c", &options),
+            "<math display=\"block\"><mtext data-src-start=\"1\" data-src-end=\"4\">a&lt;b</mtext><mrow data-src-start=\"6\" data-src-end=\"9\"><mi>m</mi><mo>&lt;</mo></mrow><mi data-src-start=\"10\" data-src-end=\"11\">c</mi></math>"
+        );
+    }
 }
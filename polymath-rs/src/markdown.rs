@@ -0,0 +1,154 @@
+//!
+//! # Markdown
+//!
+//! Adapter for [pulldown_cmark] event streams, available with the
+//! `pulldown-cmark` feature. Fenced code blocks tagged `asciimath` or `math`
+//! become block formulas. Inline code spans stay code unless
+//! [Options::inline_code] is set, Markdown cannot tag them.
+//!
+//! ```
+//! use pulldown_cmark::Parser;
+//! use polymath_rs::markdown::{MathEvents, Options};
+//!
+//! let options = Options {
+//!     inline_code: true,
+//!     ..Options::default()
+//! };
+//! let events = MathEvents::with_options(Parser::new("Euler: `e^(i pi) + 1 = 0`"), options);
+//! # assert_eq!(events.count(), 4);
+//! ```
+//!
+
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Tag, TagEnd};
+
+use crate::{
+    mathml::{self, Display},
+    to_math_ml_with_options,
+};
+
+/// Info strings of fenced code blocks containing AsciiMath
+pub const LANGUAGES: [&str; 2] = ["asciimath", "math"];
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Treat every inline code span as a formula, like asciimath.js does
+    pub inline_code: bool,
+    /// Used for every formula, `display` is overridden by the kind of event
+    pub mathml: mathml::Options,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            inline_code: false,
+            mathml: mathml::Options::default(),
+        }
+    }
+}
+
+pub struct MathEvents<I> {
+    events: I,
+    options: Options,
+}
+
+impl<'a, I: Iterator<Item = Event<'a>>> MathEvents<I> {
+    pub fn new(events: I) -> Self {
+        Self::with_options(events, Options::default())
+    }
+
+    pub fn with_options(events: I, options: Options) -> Self {
+        Self { events, options }
+    }
+
+    fn render(&self, source: &str, display: Display) -> CowStr<'a> {
+        let options = mathml::Options {
+            display,
+            ..self.options.mathml
+        };
+
+        to_math_ml_with_options(source, &options).into()
+    }
+}
+
+impl<'a, I: Iterator<Item = Event<'a>>> Iterator for MathEvents<I> {
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.events.next()? {
+            Event::Code(code) if self.options.inline_code => {
+                Some(Event::InlineHtml(self.render(&code, Display::Inline)))
+            }
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) if is_math(&info) => {
+                let mut source = String::new();
+
+                for event in self.events.by_ref() {
+                    match event {
+                        // the tokenizer only skips spaces, lines are joined instead
+                        Event::Text(text) => {
+                            source.extend(text.lines().map(|line| format!("{line} ")))
+                        }
+                        Event::End(TagEnd::CodeBlock) => break,
+                        _ => {}
+                    }
+                }
+
+                let mut html = self.render(&source, Display::Block).into_string();
+                html.push('\n');
+
+                Some(Event::Html(html.into()))
+            }
+            event => Some(event),
+        }
+    }
+}
+
+fn is_math(info: &str) -> bool {
+    info.split_whitespace()
+        .next()
+        .is_some_and(|language| LANGUAGES.contains(&language))
+}
+
+#[cfg(test)]
+mod test {
+    use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+
+    use super::{MathEvents, Options};
+
+    #[test]
+    fn test_inline_code() {
+        let events = MathEvents::new(Parser::new("a `fn main()`")).collect::<Vec<_>>();
+
+        assert_eq!(events[2], Event::Code("fn main()".into()));
+
+        let options = Options {
+            inline_code: true,
+            ..Default::default()
+        };
+        let events = MathEvents::with_options(Parser::new("a `x^2`"), options);
+
+        assert_eq!(
+            events.collect::<Vec<_>>()[2],
+            Event::InlineHtml(
+                "<math display=\"inline\"><msup><mi>x</mi><mn>2</mn></msup></math>".into()
+            )
+        );
+    }
+
+    #[test]
+    fn test_code_blocks() {
+        let markdown = "```math\nsqrt\n2\n```\n\n```rust\nfn main() {}\n```\n";
+        let events = MathEvents::new(Parser::new(markdown)).collect::<Vec<_>>();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::Html("<math display=\"block\"><msqrt><mn>2</mn></msqrt></math>\n".into()),
+                Event::Start(Tag::CodeBlock(pulldown_cmark::CodeBlockKind::Fenced(
+                    "rust".into()
+                ))),
+                Event::Text("fn main() {}\n".into()),
+                Event::End(TagEnd::CodeBlock),
+            ]
+        );
+    }
+}
@@ -1,7 +1,9 @@
+use std::ops::Range;
+
 use itertools::Itertools;
 
 use crate::{
-    ast::{Expression, Expressions, Literal, Table, TableRow, AST},
+    ast::{ColumnAlign, Expression, Expressions, Literal, Table, TableKind, TableRow, AST},
     tokens::{
         types::{
             Arrow, BinaryOperator, Function, Greek, LBrace, Logical, Misc, Operation, RBrace,
@@ -11,137 +13,253 @@ use crate::{
     },
 };
 
+pub(crate) const MATH_CLOSE: &str = "</math>";
+
+///
+/// Whether a formula is rendered as a block of its own or inline with
+/// surrounding text.
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Display {
+    #[default]
+    Block,
+    Inline,
+}
+
+///
+/// Options for the MathML backend.
+///
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    pub display: Display,
+    /// Annotate emitted elements with the byte range of the source they have
+    /// been rendered from, using `data-src-start` and `data-src-end`.
+    pub source_positions: bool,
+}
+
+pub(crate) fn math_open(options: &Options) -> &'static str {
+    match options.display {
+        Display::Block => "<math display=\"block\">",
+        Display::Inline => "<math display=\"inline\">",
+    }
+}
+
 pub fn to_mathml(ast: &AST) -> String {
+    to_mathml_with_options(ast, &Options::default())
+}
+
+pub fn to_mathml_with_options(ast: &AST, options: &Options) -> String {
     format!(
-        "<math display=\"block\">{}</math>",
-        expressions_to_mathml(&ast.expressions)
+        "{}{}{MATH_CLOSE}",
+        math_open(options),
+        expressions_to_mathml(&ast.expressions, options)
     )
 }
 
-fn expressions_to_mathml(expressions: &Expressions) -> String {
+fn expressions_to_mathml(expressions: &Expressions, options: &Options) -> String {
     expressions
         .expressions
         .iter()
-        .map(|expression| expression_to_mathml(expression))
+        .map(|expression| expression_to_mathml(expression, options))
         .join("")
 }
 
-fn expression_to_mathml(expr: &Expression) -> String {
+///
+/// Renders a single expression without the surrounding `<math>` element.
+///
+pub fn expression_to_mathml(expr: &Expression, options: &Options) -> String {
+    let attributes = attributes(|| expr.span(), options);
+
     match expr {
-        Expression::Frac(frac) => frac_to_mathml(frac),
-        Expression::Sub(bi_expression) => sub_to_mathml(bi_expression),
-        Expression::Pow(bi_expression) => pow_to_mathml(bi_expression),
-        Expression::SubPow(tri_expression) => sub_pow_to_mathml(tri_expression),
-        Expression::Group(group) => group_to_mathml(group),
-        Expression::Unary(unary) => unary_to_mathml(unary),
-        Expression::Binary(binary) => binary_to_mathml(binary),
-        Expression::Literal(literal) => literal_to_mathml(literal),
-        Expression::Expressions(expressions) => expressions_to_mathml(expressions),
+        Expression::Frac(frac) => frac_to_mathml(frac, &attributes, options),
+        Expression::Sub(bi_expression) => sub_to_mathml(bi_expression, &attributes, options),
+        Expression::Pow(bi_expression) => pow_to_mathml(bi_expression, &attributes, options),
+        Expression::SubPow(tri_expression) => {
+            sub_pow_to_mathml(tri_expression, &attributes, options)
+        }
+        Expression::Group(group) => group_to_mathml(group, &attributes, options),
+        Expression::Unary(unary) => unary_to_mathml(unary, &attributes, options),
+        Expression::Binary(binary) => binary_to_mathml(binary, &attributes, options),
+        Expression::Literal(literal) => literal_to_mathml(literal, &attributes, options),
+        Expression::Expressions(expressions) => {
+            mrow(expressions_to_mathml(expressions, options), &attributes)
+        }
         Expression::Unit => unreachable!(),
     }
 }
 
-fn binary_to_mathml(binary: &crate::ast::Binary) -> String {
+///
+/// Text content of rendered MathML with character references resolved.
+///
+pub fn text_content(mathml: &str) -> String {
+    let mut text = String::new();
+    let mut rest = mathml;
+
+    while let Some(c) = rest.chars().next() {
+        if c == '<' {
+            rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+        } else if let Some((entity, tail)) = rest
+            .strip_prefix('&')
+            .and_then(|entity| entity.split_once(';'))
+        {
+            let decoded = match entity {
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "amp" => Some('&'),
+                _ => entity
+                    .strip_prefix("#x")
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .and_then(char::from_u32),
+            };
+            text.push(decoded.unwrap_or(c));
+            rest = if decoded.is_some() { tail } else { &rest[1..] };
+        } else {
+            text.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    text
+}
+
+///
+/// The attributes carrying the source position of an element, empty unless
+/// requested. Builders write them into the element they create.
+///
+fn attributes<F: FnOnce() -> Option<Range<usize>>>(span: F, options: &Options) -> String {
+    if !options.source_positions {
+        return String::new();
+    }
+
+    match span() {
+        Some(span) => format!(
+            " data-src-start=\"{}\" data-src-end=\"{}\"",
+            span.start, span.end
+        ),
+        None => String::new(),
+    }
+}
+
+///
+/// Groups sibling elements in an `mrow` carrying `attributes`, if there are
+/// any to carry.
+///
+fn mrow(mathml: String, attributes: &str) -> String {
+    match attributes.is_empty() || mathml.is_empty() {
+        true => mathml,
+        false => format!("<mrow{attributes}>{mathml}</mrow>"),
+    }
+}
+
+fn binary_to_mathml(binary: &crate::ast::Binary, attributes: &str, options: &Options) -> String {
     match binary.operator.token_type {
         crate::tokens::types::TokenType::BinaryOperator(BinaryOperator::Root) => format!(
-            "<mroot><mrow>{}</mrow><mrow>{}</mrow></mroot>",
+            "<mroot{attributes}><mrow>{}</mrow><mrow>{}</mrow></mroot>",
             match binary.expression_2.as_ref() {
-                Expression::Group(group) => expressions_to_mathml(&group.expressions),
-                _ => expression_to_mathml(&binary.expression_2),
+                Expression::Group(group) => expressions_to_mathml(&group.expressions, options),
+                _ => expression_to_mathml(&binary.expression_2, options),
             },
             match binary.expression_1.as_ref() {
-                Expression::Group(group) => expressions_to_mathml(&group.expressions),
-                _ => expression_to_mathml(&binary.expression_1),
+                Expression::Group(group) => expressions_to_mathml(&group.expressions, options),
+                _ => expression_to_mathml(&binary.expression_1, options),
             }
         ),
         crate::tokens::types::TokenType::BinaryOperator(BinaryOperator::Overset) => format!(
-            "<mover><mrow>{}</mrow><mrow>{}</mrow></mover>",
+            "<mover{attributes}><mrow>{}</mrow><mrow>{}</mrow></mover>",
             match binary.expression_2.as_ref() {
-                Expression::Group(group) => expressions_to_mathml(&group.expressions),
-                _ => expression_to_mathml(&binary.expression_2),
+                Expression::Group(group) => expressions_to_mathml(&group.expressions, options),
+                _ => expression_to_mathml(&binary.expression_2, options),
             },
             match binary.expression_1.as_ref() {
-                Expression::Group(group) => expressions_to_mathml(&group.expressions),
-                _ => expression_to_mathml(&binary.expression_1),
+                Expression::Group(group) => expressions_to_mathml(&group.expressions, options),
+                _ => expression_to_mathml(&binary.expression_1, options),
             }
         ),
         crate::tokens::types::TokenType::BinaryOperator(BinaryOperator::Underset) => format!(
-            "<munder><mrow>{}</mrow><mrow>{}</mrow></munder>",
+            "<munder{attributes}><mrow>{}</mrow><mrow>{}</mrow></munder>",
             match binary.expression_2.as_ref() {
-                Expression::Group(group) => expressions_to_mathml(&group.expressions),
-                _ => expression_to_mathml(&binary.expression_2),
+                Expression::Group(group) => expressions_to_mathml(&group.expressions, options),
+                _ => expression_to_mathml(&binary.expression_2, options),
             },
             match binary.expression_1.as_ref() {
-                Expression::Group(group) => expressions_to_mathml(&group.expressions),
-                _ => expression_to_mathml(&binary.expression_1),
+                Expression::Group(group) => expressions_to_mathml(&group.expressions, options),
+                _ => expression_to_mathml(&binary.expression_1, options),
             }
         ),
         crate::tokens::types::TokenType::BinaryOperator(BinaryOperator::Color) => format!(
-            "<mstyle mathcolor=\"{}\">{}</mstyle>",
+            "<mstyle{attributes} mathcolor=\"{}\">{}</mstyle>",
             match binary.expression_1.as_ref() {
                 Expression::Group(group) => group
                     .expressions
                     .expressions
                     .iter()
                     .map(|expression| match expression {
-                        Expression::Literal(Literal::Literal(literal)) => literal.span.text,
-                        _ => "",
+                        Expression::Literal(Literal::Literal(literal)) => {
+                            escape_text(literal.span.text).replace('"', "&quot;")
+                        }
+                        _ => "".to_string(),
                     })
                     .join(""),
                 _ => "".to_string(),
             },
             match binary.expression_2.as_ref() {
-                Expression::Group(group) => expressions_to_mathml(&group.expressions),
-                _ => expression_to_mathml(&binary.expression_2),
+                Expression::Group(group) => expressions_to_mathml(&group.expressions, options),
+                _ => expression_to_mathml(&binary.expression_2, options),
             },
         ),
-        _ => format!(
-            "{}{}{}",
-            binary.operator.span.text,
-            expression_to_mathml(&binary.expression_1),
-            expression_to_mathml(&binary.expression_2),
+        _ => mrow(
+            format!(
+                "{}{}{}",
+                binary.operator.span.text,
+                expression_to_mathml(&binary.expression_1, options),
+                expression_to_mathml(&binary.expression_2, options),
+            ),
+            attributes,
         ),
     }
 }
 
-fn group_to_mathml(group: &crate::ast::Group) -> String {
+fn group_to_mathml(group: &crate::ast::Group, attributes: &str, options: &Options) -> String {
     format!(
-        "<mrow>{}{}{}</mrow>",
-        l_brace_to_math_ml(&group.l_brace),
+        "<mrow{attributes}>{}{}{}</mrow>",
+        l_brace_to_math_ml(&group.l_brace, options),
         group
             .expressions
             .expressions
             .iter()
-            .map(|expr| expression_to_mathml(expr))
+            .map(|expr| expression_to_mathml(expr, options))
             .join(""),
-        r_brace_to_math_ml(&group.r_brace),
+        r_brace_to_math_ml(&group.r_brace, options),
     )
 }
 
-fn sub_pow_to_mathml(tri_expression: &crate::ast::TriExpression) -> String {
+fn sub_pow_to_mathml(
+    tri_expression: &crate::ast::TriExpression,
+    attributes: &str,
+    options: &Options,
+) -> String {
     if let Expression::Literal(Literal::Literal(literal)) = tri_expression.expression_1.as_ref() {
         match &literal.token_type {
-            TokenType::Operation(op) => match op {
+            TokenType::Operation(
                 Operation::Sum
                 | Operation::Prod
                 | Operation::BigWedge
                 | Operation::BigCap
-                | Operation::BigCup => {
-                    return format!(
-                        "<munderover>{}{}{}</munderover>",
-                        expression_to_mathml_braceless(&tri_expression.expression_1),
-                        expression_to_mathml_braceless(&tri_expression.expression_2),
-                        expression_to_mathml_braceless(&tri_expression.expression_3)
-                    )
-                }
-                _ => {}
-            },
+                | Operation::BigCup,
+            ) => {
+                return format!(
+                    "<munderover{attributes}>{}{}{}</munderover>",
+                    expression_to_mathml_braceless(&tri_expression.expression_1, options),
+                    expression_to_mathml_braceless(&tri_expression.expression_2, options),
+                    expression_to_mathml_braceless(&tri_expression.expression_3, options)
+                )
+            }
             TokenType::Misc(Misc::Lim) => {
                 return format!(
-                    "<munderover>{}{}{}</munderover>",
-                    expression_to_mathml_braceless(&tri_expression.expression_1),
-                    expression_to_mathml_braceless(&tri_expression.expression_2),
-                    expression_to_mathml_braceless(&tri_expression.expression_3)
+                    "<munderover{attributes}>{}{}{}</munderover>",
+                    expression_to_mathml_braceless(&tri_expression.expression_1, options),
+                    expression_to_mathml_braceless(&tri_expression.expression_2, options),
+                    expression_to_mathml_braceless(&tri_expression.expression_3, options)
                 )
             }
             _ => {}
@@ -150,53 +268,56 @@ fn sub_pow_to_mathml(tri_expression: &crate::ast::TriExpression) -> String {
         match unary.operator.token_type {
             TokenType::UnaryOperator(UnaryOperator::UBrace) => {
                 return format!(
-                    "<munderover><munder>{}</munder>{}{}</munderover>",
-                    expression_to_mathml_braceless(&tri_expression.expression_1),
-                    expression_to_mathml_braceless(&tri_expression.expression_2),
-                    expression_to_mathml_braceless(&tri_expression.expression_3)
+                    "<munderover{attributes}><munder>{}</munder>{}{}</munderover>",
+                    expression_to_mathml_braceless(&tri_expression.expression_1, options),
+                    expression_to_mathml_braceless(&tri_expression.expression_2, options),
+                    expression_to_mathml_braceless(&tri_expression.expression_3, options)
                 )
             }
             TokenType::UnaryOperator(UnaryOperator::OBrace) => {
                 return format!(
-                    "<munderover><mover>{}</mover>{}{}</munderover>",
-                    expression_to_mathml_braceless(&tri_expression.expression_1),
-                    expression_to_mathml_braceless(&tri_expression.expression_2),
-                    expression_to_mathml_braceless(&tri_expression.expression_3)
+                    "<munderover{attributes}><mover>{}</mover>{}{}</munderover>",
+                    expression_to_mathml_braceless(&tri_expression.expression_1, options),
+                    expression_to_mathml_braceless(&tri_expression.expression_2, options),
+                    expression_to_mathml_braceless(&tri_expression.expression_3, options)
                 )
             }
             _ => {}
         }
     }
     format!(
-        "<msubsup>{}{}{}</msubsup>",
-        expression_to_mathml(&tri_expression.expression_1),
-        expression_to_mathml(&tri_expression.expression_2),
-        expression_to_mathml(&tri_expression.expression_3)
+        "<msubsup{attributes}>{}{}{}</msubsup>",
+        expression_to_mathml(&tri_expression.expression_1, options),
+        expression_to_mathml(&tri_expression.expression_2, options),
+        expression_to_mathml(&tri_expression.expression_3, options)
     )
 }
 
-fn pow_to_mathml(bi_expression: &crate::ast::BiExpression) -> String {
+fn pow_to_mathml(
+    bi_expression: &crate::ast::BiExpression,
+    attributes: &str,
+    options: &Options,
+) -> String {
     if let Expression::Literal(Literal::Literal(token)) = bi_expression.expression_1.as_ref() {
         match &token.token_type {
-            TokenType::Operation(op) => match op {
+            TokenType::Operation(
                 Operation::Sum
                 | Operation::Prod
                 | Operation::BigWedge
                 | Operation::BigCap
-                | Operation::BigCup => {
-                    return format!(
-                        "<mover>{}{}</mover>",
-                        expression_to_mathml_braceless(&bi_expression.expression_1),
-                        expression_to_mathml_braceless(&bi_expression.expression_2)
-                    )
-                }
-                _ => {}
-            },
+                | Operation::BigCup,
+            ) => {
+                return format!(
+                    "<mover{attributes}>{}{}</mover>",
+                    expression_to_mathml_braceless(&bi_expression.expression_1, options),
+                    expression_to_mathml_braceless(&bi_expression.expression_2, options)
+                )
+            }
             TokenType::Misc(Misc::Lim) => {
                 return format!(
-                    "<mover>{}{}</mover>",
-                    expression_to_mathml_braceless(&bi_expression.expression_1),
-                    expression_to_mathml_braceless(&bi_expression.expression_2)
+                    "<mover{attributes}>{}{}</mover>",
+                    expression_to_mathml_braceless(&bi_expression.expression_1, options),
+                    expression_to_mathml_braceless(&bi_expression.expression_2, options)
                 )
             }
             _ => {}
@@ -204,42 +325,45 @@ fn pow_to_mathml(bi_expression: &crate::ast::BiExpression) -> String {
     } else if let Expression::Unary(unary) = bi_expression.expression_1.as_ref() {
         if let TokenType::UnaryOperator(UnaryOperator::OBrace) = unary.operator.token_type {
             return format!(
-                "<mover><mover>{}</mover>{}</mover>",
-                expression_to_mathml_braceless(&bi_expression.expression_1),
-                expression_to_mathml_braceless(&bi_expression.expression_2),
+                "<mover{attributes}><mover>{}</mover>{}</mover>",
+                expression_to_mathml_braceless(&bi_expression.expression_1, options),
+                expression_to_mathml_braceless(&bi_expression.expression_2, options),
             );
         }
     }
 
     format!(
-        "<msup>{}{}</msup>",
-        expression_to_mathml(&bi_expression.expression_1),
-        expression_to_mathml(&bi_expression.expression_2)
+        "<msup{attributes}>{}{}</msup>",
+        expression_to_mathml(&bi_expression.expression_1, options),
+        expression_to_mathml(&bi_expression.expression_2, options)
     )
 }
 
-fn sub_to_mathml(bi_expression: &crate::ast::BiExpression) -> String {
+fn sub_to_mathml(
+    bi_expression: &crate::ast::BiExpression,
+    attributes: &str,
+    options: &Options,
+) -> String {
     if let Expression::Literal(Literal::Literal(literal)) = bi_expression.expression_1.as_ref() {
         match &literal.token_type {
-            TokenType::Operation(op) => match op {
+            TokenType::Operation(
                 Operation::Sum
                 | Operation::Prod
                 | Operation::BigWedge
                 | Operation::BigCap
-                | Operation::BigCup => {
-                    return format!(
-                        "<munder>{}{}</munder>",
-                        expression_to_mathml_braceless(&bi_expression.expression_1),
-                        expression_to_mathml_braceless(&bi_expression.expression_2)
-                    )
-                }
-                _ => {}
-            },
+                | Operation::BigCup,
+            ) => {
+                return format!(
+                    "<munder{attributes}>{}{}</munder>",
+                    expression_to_mathml_braceless(&bi_expression.expression_1, options),
+                    expression_to_mathml_braceless(&bi_expression.expression_2, options)
+                )
+            }
             TokenType::Misc(Misc::Lim) => {
                 return format!(
-                    "<munder>{}{}</munder>",
-                    expression_to_mathml_braceless(&bi_expression.expression_1),
-                    expression_to_mathml_braceless(&bi_expression.expression_2)
+                    "<munder{attributes}>{}{}</munder>",
+                    expression_to_mathml_braceless(&bi_expression.expression_1, options),
+                    expression_to_mathml_braceless(&bi_expression.expression_2, options)
                 )
             }
             _ => {}
@@ -247,38 +371,40 @@ fn sub_to_mathml(bi_expression: &crate::ast::BiExpression) -> String {
     } else if let Expression::Unary(unary) = bi_expression.expression_1.as_ref() {
         if let TokenType::UnaryOperator(UnaryOperator::UBrace) = unary.operator.token_type {
             return format!(
-                "<munder><munder>{}</munder>{}</munder>",
-                expression_to_mathml_braceless(&bi_expression.expression_1),
-                expression_to_mathml_braceless(&bi_expression.expression_2),
+                "<munder{attributes}><munder>{}</munder>{}</munder>",
+                expression_to_mathml_braceless(&bi_expression.expression_1, options),
+                expression_to_mathml_braceless(&bi_expression.expression_2, options),
             );
         }
     }
 
     format!(
-        "<msub>{}{}</msub>",
-        expression_to_mathml(&bi_expression.expression_1),
-        expression_to_mathml(&bi_expression.expression_2)
+        "<msub{attributes}>{}{}</msub>",
+        expression_to_mathml(&bi_expression.expression_1, options),
+        expression_to_mathml(&bi_expression.expression_2, options)
     )
 }
 
-fn expression_to_mathml_braceless(expression: &Expression) -> String {
+fn expression_to_mathml_braceless(expression: &Expression, options: &Options) -> String {
     match expression {
-        Expression::Group(group) => {
-            format!("<mrow>{}</mrow>", expressions_to_mathml(&group.expressions))
-        }
-        _ => expression_to_mathml(expression),
+        Expression::Group(group) => format!(
+            "<mrow{}>{}</mrow>",
+            attributes(|| expression.span(), options),
+            expressions_to_mathml(&group.expressions, options)
+        ),
+        _ => expression_to_mathml(expression, options),
     }
 }
 
-fn frac_to_mathml(frac: &crate::ast::BiExpression) -> String {
+fn frac_to_mathml(frac: &crate::ast::BiExpression, attributes: &str, options: &Options) -> String {
     format!(
-        "<mfrac>{}{}</mfrac>",
-        expression_to_mathml_braceless(&frac.expression_1),
-        expression_to_mathml_braceless(&frac.expression_2)
+        "<mfrac{attributes}>{}{}</mfrac>",
+        expression_to_mathml_braceless(&frac.expression_1, options),
+        expression_to_mathml_braceless(&frac.expression_2, options)
     )
 }
 
-fn unary_to_mathml(unary: &crate::ast::Unary) -> String {
+fn unary_to_mathml(unary: &crate::ast::Unary, attributes: &str, options: &Options) -> String {
     match unary.operator.token_type {
         TokenType::UnaryOperator(UnaryOperator::Hat)
         | TokenType::UnaryOperator(UnaryOperator::Bar)
@@ -299,9 +425,9 @@ fn unary_to_mathml(unary: &crate::ast::Unary) -> String {
             };
 
             format!(
-                "<mover>{}<mo>{}</mo></mover>",
-                expression_to_mathml_braceless(&unary.expression),
-                symbol
+                "<mover{attributes}>{}{}</mover>",
+                expression_to_mathml_braceless(&unary.expression, options),
+                operator_to_mathml(symbol, &unary.operator, options)
             )
         }
         TokenType::UnaryOperator(UnaryOperator::Ul)
@@ -313,9 +439,9 @@ fn unary_to_mathml(unary: &crate::ast::Unary) -> String {
             };
 
             format!(
-                "<munder>{}<mo>{}</mo></munder>",
-                expression_to_mathml_braceless(&unary.expression),
-                symbol
+                "<munder{attributes}>{}{}</munder>",
+                expression_to_mathml_braceless(&unary.expression, options),
+                operator_to_mathml(symbol, &unary.operator, options)
             )
         }
         TokenType::UnaryOperator(UnaryOperator::Abs)
@@ -330,136 +456,227 @@ fn unary_to_mathml(unary: &crate::ast::Unary) -> String {
                 _ => ("", ""),
             };
 
-            format!(
-                "<mo>{}</mo>{}<mo>{}</mo>",
-                left_symbol,
-                expression_to_mathml_braceless(&unary.expression),
-                right_symbol
+            mrow(
+                format!(
+                    "{}{}{}",
+                    operator_to_mathml(left_symbol, &unary.operator, options),
+                    expression_to_mathml_braceless(&unary.expression, options),
+                    operator_to_mathml(right_symbol, &unary.operator, options)
+                ),
+                attributes,
             )
         }
         TokenType::UnaryOperator(UnaryOperator::Cancel) => format!(
-            "<menclose notation=\"updiagonalstrike\">{}</menclose>",
-            expression_to_mathml_braceless(&unary.expression)
+            "<menclose{attributes} notation=\"updiagonalstrike\">{}</menclose>",
+            expression_to_mathml_braceless(&unary.expression, options)
         ),
         TokenType::UnaryOperator(UnaryOperator::Sqrt) => {
             format!(
-                "<msqrt>{}</msqrt>",
-                expression_to_mathml_braceless(&unary.expression)
+                "<msqrt{attributes}>{}</msqrt>",
+                expression_to_mathml_braceless(&unary.expression, options)
             )
         }
         TokenType::UnaryOperator(UnaryOperator::Text) => {
             format!(
-                "<mtext>{}</mtext>",
-                expression_to_mathml_braceless(&unary.expression)
+                "<mtext{attributes}>{}</mtext>",
+                expression_to_mathml_braceless(&unary.expression, options)
             )
         }
         _ => "".to_string(),
     }
 }
 
-fn literal_to_mathml(literal: &crate::ast::Literal) -> String {
+fn operator_to_mathml(symbol: &str, operator: &Token, options: &Options) -> String {
+    format!(
+        "<mo{}>{symbol}</mo>",
+        attributes(|| Some(operator.span.start..operator.span.end), options)
+    )
+}
+
+fn literal_to_mathml(literal: &crate::ast::Literal, attributes: &str, options: &Options) -> String {
     match literal {
-        Literal::Literal(token) => token_to_mathml(token),
-        Literal::Table(table) => table_to_mathml(table),
+        Literal::Literal(token) => token_to_mathml(token, attributes),
+        Literal::Table(table) => table_to_mathml(table, attributes, options),
     }
 }
 
-fn table_to_mathml(table: &crate::ast::Table) -> String {
+fn table_to_mathml(table: &crate::ast::Table, attributes: &str, options: &Options) -> String {
     format!(
-        "<mrow>{}<mtable {}>{}</mtable>{}</mrow>",
-        l_brace_to_math_ml(&table.l_brace),
-        format_column_line(table),
-        expressions_to_mathml_table(&table.rows),
-        r_brace_to_math_ml(&table.r_brace)
+        "<mrow{attributes}>{}<mtable {}>{}</mtable>{}</mrow>",
+        l_brace_to_math_ml(&table.l_brace, options),
+        format_table_layout(table),
+        expressions_to_mathml_table(&table.rows, table.dimensions().1, options),
+        r_brace_to_math_ml(&table.r_brace, options)
     )
 }
 
-fn format_column_line(table: &Table) -> String {
-    table
-        .rows
-        .first()
-        .map(|row| {
-            format!(
-                "columnlines=\"{}\"",
-                row.cols
-                    .iter()
-                    .enumerate()
-                    .skip(1)
-                    .map(|(index, _)| {
-                        if table.seperators.contains(&index) {
-                            "solid".to_string()
-                        } else {
-                            "none".to_string()
-                        }
-                    })
-                    .join(" ")
-            )
+///
+/// Lines, alignment and spacing of a table. Equation systems with columns
+/// of operators get no extra space between the columns, the operators
+/// bring their own.
+///
+fn format_table_layout(table: &Table) -> String {
+    let (rows, _) = table.dimensions();
+    let alignment = table.column_alignment();
+
+    // an empty list is no valid value, single rows go without
+    let row_lines = match rows {
+        0 | 1 => String::new(),
+        _ => format!(" rowlines=\"{}\"", vec!["none"; rows - 1].join(" ")),
+    };
+    let column_align = alignment
+        .iter()
+        .map(|align| match align {
+            ColumnAlign::Left => "left",
+            ColumnAlign::Center => "center",
+            ColumnAlign::Right => "right",
         })
-        .unwrap_or("".to_string())
+        .join(" ");
+    let column_spacing = match table.kind() {
+        TableKind::EquationSystem if alignment.contains(&ColumnAlign::Center) => "0em",
+        TableKind::Cases => "1em",
+        _ => "0.8em",
+    };
+
+    format!(
+        "{}{row_lines} columnalign=\"{column_align}\" rowspacing=\"1.0ex\" columnspacing=\"{column_spacing}\"",
+        format_column_line(table)
+    )
+}
+
+fn format_column_line(table: &Table) -> String {
+    format!(
+        "columnlines=\"{}\"",
+        (1..table.dimensions().1)
+            .map(|index| {
+                if table.seperators.contains(&index) {
+                    "solid".to_string()
+                } else {
+                    "none".to_string()
+                }
+            })
+            .join(" ")
+    )
 }
 
-fn expressions_to_mathml_table(rows: &[TableRow]) -> String {
+///
+/// Rows shorter than `columns` are filled up with empty cells, the
+/// diagnostics report them.
+///
+fn expressions_to_mathml_table(rows: &[TableRow], columns: usize, options: &Options) -> String {
     rows.iter()
         .map(|row| {
+            let row_attributes = attributes(
+                || {
+                    let spans = row.cols.iter().filter_map(|col| col.span());
+                    spans.reduce(|first, last| first.start.min(last.start)..first.end.max(last.end))
+                },
+                options,
+            );
+
             format!(
-                "<mtr>{}</mtr>",
+                "<mtr{row_attributes}>{}{}</mtr>",
                 row.cols
                     .iter()
-                    .map(|col| format!("<mtd>{}</mtd>", expressions_to_mathml(col)))
-                    .join("")
+                    .map(|col| format!(
+                        "<mtd{}>{}</mtd>",
+                        attributes(|| col.span(), options),
+                        expressions_to_mathml(col, options)
+                    ))
+                    .join(""),
+                "<mtd></mtd>".repeat(columns.saturating_sub(row.cols.len()))
             )
         })
         .join("")
 }
 
-fn l_brace_to_math_ml(token: &Token) -> String {
+fn l_brace_to_math_ml(token: &Token, options: &Options) -> String {
+    let attributes = attributes(|| Some(token.span.start..token.span.end), options);
+
     match &token.token_type {
         TokenType::LBrace(lbrace) => match lbrace {
-            LBrace::LParen => "<mo>(</mo>".to_string(),
-            LBrace::LBracket => "<mo>[</mo>".to_string(),
-            LBrace::LBrace => "<mo>{</mo>".to_string(),
-            LBrace::LColonBrace => "".to_string(),
-            LBrace::LAngle => "<mo><</mo>".to_string(),
+            LBrace::LParen => format!("<mo{attributes}>(</mo>"),
+            LBrace::LBracket => format!("<mo{attributes}>[</mo>"),
+            LBrace::LBrace => format!("<mo{attributes}>{{</mo>"),
+            LBrace::LColonBrace => String::new(),
+            LBrace::LAngle => format!("<mo{attributes}><</mo>"),
         },
-        _ => token.span.text.to_string(),
+        _ => mrow(token.span.text.to_string(), &attributes),
     }
 }
 
-fn r_brace_to_math_ml(token: &Token) -> String {
+fn r_brace_to_math_ml(token: &Token, options: &Options) -> String {
+    let attributes = attributes(|| Some(token.span.start..token.span.end), options);
+
     match &token.token_type {
         TokenType::RBrace(rbrace) => match rbrace {
-            RBrace::RParen => "<mo>)</mo>".to_string(),
-            RBrace::RBracket => "<mo>]</mo>".to_string(),
-            RBrace::RBrace => "<mo>}</mo>".to_string(),
-            RBrace::RColonBrace => "".to_string(),
-            RBrace::RAngle => "<mo>></mo>".to_string(),
+            RBrace::RParen => format!("<mo{attributes}>)</mo>"),
+            RBrace::RBracket => format!("<mo{attributes}>]</mo>"),
+            RBrace::RBrace => format!("<mo{attributes}>}}</mo>"),
+            RBrace::RColonBrace => String::new(),
+            RBrace::RAngle => format!("<mo{attributes}>></mo>"),
         },
-        _ => token.span.text.to_string(),
+        _ => mrow(token.span.text.to_string(), &attributes),
+    }
+}
+
+///
+/// Renders a single token on its own, brackets included.
+///
+pub(crate) fn symbol_to_mathml(token: &Token) -> String {
+    let options = Options::default();
+
+    match token.token_type {
+        TokenType::LBrace(_) => l_brace_to_math_ml(token, &options),
+        TokenType::RBrace(_) => r_brace_to_math_ml(token, &options),
+        _ => token_to_mathml(token, ""),
     }
 }
 
-fn token_to_mathml(token: &Token) -> String {
+fn token_to_mathml(token: &Token, attributes: &str) -> String {
     match &token.token_type {
-        TokenType::Symbol => format!("<mi>{}</mi>", token.span.text),
-        TokenType::Greek(greek) => greek_to_mathml(greek),
-        TokenType::Operation(op) => format!("<mo>{}</mo>", operation_to_mathml(op)),
-        TokenType::Misc(misc) => misc_to_mathml(misc),
-        TokenType::Relational(relational) => relational_to_mathml(relational),
-        TokenType::Arrow(arrow) => format!("<mo>{}</mo>", arrow_to_mathml(arrow)),
-        TokenType::Logical(logical) => logical_to_mathml(logical),
-        TokenType::Number => format!("<mn>{}</mn>", token.span.text),
-        TokenType::Text => format!("<mtext>{}</mtext>", token.span.text),
-        TokenType::Function(function) => format!("<mi>{}</mi>", function_to_mathml(function)),
+        TokenType::Symbol => format!("<mi{attributes}>{}</mi>", escape_text(token.span.text)),
+        TokenType::Greek(greek) => greek_to_mathml(greek, attributes),
+        TokenType::Operation(op) => format!("<mo{attributes}>{}</mo>", operation_to_mathml(op)),
+        TokenType::Misc(misc) => misc_to_mathml(misc, attributes),
+        TokenType::Relational(relational) => relational_to_mathml(relational, attributes),
+        TokenType::Arrow(arrow) => format!("<mo{attributes}>{}</mo>", arrow_to_mathml(arrow)),
+        TokenType::Logical(logical) => logical_to_mathml(logical, attributes),
+        TokenType::Number => format!("<mn{attributes}>{}</mn>", token.span.text),
+        TokenType::Text => {
+            format!(
+                "<mtext{attributes}>{}</mtext>",
+                escape_text(token.span.text)
+            )
+        }
+        TokenType::Function(function) => {
+            format!("<mi{attributes}>{}</mi>", function_to_mathml(function))
+        }
         TokenType::None => "".to_string(),
-        _ => format!("<mi>{}</mi>", token.span.text),
+        _ => format!("<mi{attributes}>{}</mi>", escape_text(token.span.text)),
     }
 }
 
-fn greek_to_mathml(greek: &Greek) -> String {
+///
+/// Escapes text taken over from the source, the output ends up in HTML
+/// documents as is.
+///
+fn escape_text(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '&' => "&amp;".to_string(),
+            '<' => "&lt;".to_string(),
+            '>' => "&gt;".to_string(),
+            _ => c.to_string(),
+        })
+        .collect()
+}
+
+fn greek_to_mathml(greek: &Greek, attributes: &str) -> String {
     let symbol = match greek {
         Greek::Alpha => "&#x3B1;",
         Greek::Beta => "&#x3B2;",
-        Greek::Gamma => "&#x3B2;",
+        Greek::Gamma => "&#x3B3;",
         Greek::UGamma => "&#x393;",
         Greek::Delta => "&#x3B4;",
         Greek::UDelta => "&#x394;",
@@ -495,10 +712,10 @@ fn greek_to_mathml(greek: &Greek) -> String {
         Greek::UOmega => "&#x3A9;",
     };
 
-    format!("<mi>{symbol}</mi>")
+    format!("<mi{attributes}>{symbol}</mi>")
 }
 
-fn logical_to_mathml(logical: &Logical) -> String {
+fn logical_to_mathml(logical: &Logical, attributes: &str) -> String {
     match logical {
         Logical::Not
         | Logical::Implies
@@ -522,7 +739,7 @@ fn logical_to_mathml(logical: &Logical) -> String {
                 _ => "",
             };
 
-            format!("<mo>{symbol}</mo>")
+            format!("<mo{attributes}>{symbol}</mo>")
         }
         Logical::And | Logical::Or | Logical::If => {
             let symbol = match logical {
@@ -532,7 +749,7 @@ fn logical_to_mathml(logical: &Logical) -> String {
                 _ => "",
             };
 
-            format!("<mrow><mspace width=\"1ex\" /><mtext>{symbol}</mtext><msapce with=\"1ex\" /></mrow>")
+            format!("<mrow{attributes}><mspace width=\"1ex\" /><mtext>{symbol}</mtext><msapce with=\"1ex\" /></mrow>")
         }
     }
 }
@@ -589,7 +806,7 @@ fn arrow_to_mathml(arrow: &Arrow) -> &'static str {
     }
 }
 
-fn relational_to_mathml(relational: &Relational) -> String {
+fn relational_to_mathml(relational: &Relational, attributes: &str) -> String {
     match relational {
         Relational::Equals
         | Relational::NotEquals
@@ -635,20 +852,23 @@ fn relational_to_mathml(relational: &Relational) -> String {
                 _ => "",
             };
 
-            format!("<mo>{}</mo>", symbol)
+            format!("<mo{attributes}>{symbol}</mo>")
         }
-        Relational::Mlt => "<mi>m</mi><mo>&lt;</mo>".to_string(),
-        Relational::Mgt => "<mi>m</mi><mo>&gt;</mo>".to_string(),
+        Relational::Mlt => mrow("<mi>m</mi><mo>&lt;</mo>".to_string(), attributes),
+        Relational::Mgt => mrow("<mi>m</mi><mo>&gt;</mo>".to_string(), attributes),
     }
 }
 
-fn misc_to_mathml(misc: &Misc) -> String {
+fn misc_to_mathml(misc: &Misc, attributes: &str) -> String {
     match misc {
         Misc::DoublePipes => {
-            return "<mrow><mo>&#x2223;</mo></mrow><mrow><mo>&#x2223;</mo></mrow>".to_string()
+            return mrow(
+                "<mrow><mo>&#x2223;</mo></mrow><mrow><mo>&#x2223;</mo></mrow>".to_string(),
+                attributes,
+            )
         }
         Misc::DoublePipesQuad => {
-            return "<mrow><mo>|</mo><mo>&#xA0;&#xA0;</mo><mo>|</mo></mrow>".to_string()
+            return format!("<mrow{attributes}><mo>|</mo><mo>&#xA0;&#xA0;</mo><mo>|</mo></mrow>")
         }
         _ => {}
     }
@@ -687,7 +907,7 @@ fn misc_to_mathml(misc: &Misc) -> String {
         Misc::Lim => "lim",
     };
 
-    format!("<mo>{symbol}</mo>")
+    format!("<mo{attributes}>{symbol}</mo>")
 }
 
 fn operation_to_mathml(op: &Operation) -> &'static str {
@@ -0,0 +1,933 @@
+//!
+//! # Search
+//!
+//! An in-memory index of formulas, queried with AsciiMath patterns. In a
+//! pattern `?` followed by letters is a named wildcard, a lone `?` one
+//! without name. Wildcards in operand positions match the operand, in
+//! sequences one or more expressions, `?f` in `int_?^? ?f dx` matches the
+//! whole integrand. Repeated names have to match the same expressions.
+//! Everything else is compared by the structure of the AST, independent of
+//! whitespace, the style of brackets and alias spellings.
+//!
+//! ```
+//! use polymath_rs::search::{Index, Pattern};
+//!
+//! let mut index = Index::new();
+//! index.insert(1, "x^2 + y^2 = z^2");
+//! index.insert(2, "a^2 + b^2 = c^3");
+//!
+//! let matches = index.search(&Pattern::new("?a^2 + ?b^2 = ?c^2"));
+//! assert_eq!(matches.len(), 1);
+//! assert_eq!(matches[0].id, 1);
+//! assert_eq!(matches[0].occurrences[0].bindings[0].span, 0..1);
+//! ```
+//!
+
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    ops::Range,
+    slice,
+};
+
+use crate::{
+    ast::{Expression, Expressions, Literal},
+    to_ast,
+    tokens::{types::TokenType, Token},
+};
+
+///
+/// Owned view of an AST, which borrows its source.
+///
+#[derive(Debug, Clone, PartialEq)]
+enum Tree {
+    Token {
+        key: String,
+        span: Range<usize>,
+    },
+    /// Only in patterns, `None` for a lone `?`
+    Wildcard(Option<String>),
+    Node {
+        /// Kind of the node along with its operator, e.g. `frac` or `unary Sqrt`
+        shape: String,
+        children: Vec<Tree>,
+        span: Range<usize>,
+    },
+    Sequence {
+        items: Vec<Tree>,
+        span: Range<usize>,
+    },
+}
+
+impl Tree {
+    fn span(&self) -> Range<usize> {
+        match self {
+            Tree::Token { span, .. } | Tree::Node { span, .. } | Tree::Sequence { span, .. } => {
+                span.clone()
+            }
+            Tree::Wildcard(_) => 0..0,
+        }
+    }
+
+    ///
+    /// The expressions of a sequence, a single one otherwise.
+    ///
+    fn items(&self) -> &[Tree] {
+        match self {
+            Tree::Sequence { items, .. } => items,
+            _ => slice::from_ref(self),
+        }
+    }
+}
+
+///
+/// A query, see the [module documentation](self).
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    tree: Tree,
+    /// Tokens and shapes every match contains
+    features: Vec<String>,
+}
+
+impl Pattern {
+    pub fn new(asciimath: &str) -> Self {
+        let source = wildcards_as_text(asciimath);
+        let tree = sequence(&to_ast(&source).expressions, true);
+
+        let mut features = HashSet::new();
+        collect_features(&tree, &mut features);
+
+        Self {
+            tree,
+            features: features.into_iter().collect(),
+        }
+    }
+
+    ///
+    /// The occurrences of the pattern in `asciimath`, without an index.
+    ///
+    pub fn find(&self, asciimath: &str) -> Vec<Occurrence> {
+        let mut occurrences = Vec::new();
+        scan(
+            &self.tree,
+            &sequence(&to_ast(asciimath).expressions, false),
+            &mut occurrences,
+        );
+        occurrences
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Binding {
+    pub name: String,
+    /// Byte range of the expressions the wildcard matched
+    pub span: Range<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Occurrence {
+    /// Byte range of the matched expressions
+    pub span: Range<usize>,
+    /// Named wildcards in order of their first occurrence in the pattern
+    pub bindings: Vec<Binding>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match<K> {
+    pub id: K,
+    /// Occurrences in source order, nested ones included
+    pub occurrences: Vec<Occurrence>,
+}
+
+#[derive(Debug)]
+struct Entry<K> {
+    id: K,
+    source: String,
+    tree: Tree,
+}
+
+///
+/// Formulas by ID. Lookups go through an inverted index of the tokens and
+/// node kinds of the formulas, only formulas containing all of those of a
+/// pattern are matched against it.
+///
+#[derive(Debug)]
+pub struct Index<K> {
+    /// `None` for removed entries
+    entries: Vec<Option<Entry<K>>>,
+    slots: HashMap<K, usize>,
+    /// Slots of the entries containing a feature, ascending
+    postings: HashMap<String, Vec<usize>>,
+}
+
+impl<K: Clone + Eq + Hash> Default for Index<K> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+            slots: HashMap::new(),
+            postings: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Clone + Eq + Hash> Index<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Adds the formula `asciimath`, replacing the one with the same `id`.
+    ///
+    pub fn insert(&mut self, id: K, asciimath: &str) {
+        self.remove(&id);
+
+        let tree = sequence(&to_ast(asciimath).expressions, false);
+        let slot = self.entries.len();
+
+        let mut features = HashSet::new();
+        collect_features(&tree, &mut features);
+        for feature in features {
+            self.postings.entry(feature).or_default().push(slot);
+        }
+
+        self.slots.insert(id.clone(), slot);
+        self.entries.push(Some(Entry {
+            id,
+            source: asciimath.to_string(),
+            tree,
+        }));
+    }
+
+    ///
+    /// Removes the formula `id`, returns whether there was one.
+    ///
+    pub fn remove(&mut self, id: &K) -> bool {
+        let Some(slot) = self.slots.remove(id) else {
+            return false;
+        };
+        let Some(entry) = self.entries[slot].take() else {
+            return false;
+        };
+
+        let mut features = HashSet::new();
+        collect_features(&entry.tree, &mut features);
+        for feature in features {
+            if let Some(slots) = self.postings.get_mut(&feature) {
+                if let Ok(index) = slots.binary_search(&slot) {
+                    slots.remove(index);
+                }
+                if slots.is_empty() {
+                    self.postings.remove(&feature);
+                }
+            }
+        }
+
+        if 2 * self.slots.len() < self.entries.len() {
+            self.compact();
+        }
+        true
+    }
+
+    ///
+    /// Drops the removed entries, moving the others down in their order.
+    ///
+    fn compact(&mut self) {
+        let mut moved = vec![0; self.entries.len()];
+        let mut next = 0;
+        for (slot, entry) in self.entries.iter().enumerate() {
+            moved[slot] = next;
+            next += usize::from(entry.is_some());
+        }
+
+        self.entries.retain(Option::is_some);
+        for slot in self.slots.values_mut() {
+            *slot = moved[*slot];
+        }
+        for slots in self.postings.values_mut() {
+            for slot in slots.iter_mut() {
+                *slot = moved[*slot];
+            }
+        }
+    }
+
+    pub fn get(&self, id: &K) -> Option<&str> {
+        let entry = self.entries[*self.slots.get(id)?].as_ref()?;
+        Some(&entry.source)
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    ///
+    /// The formulas containing `pattern`, in order of insertion.
+    ///
+    pub fn search(&self, pattern: &Pattern) -> Vec<Match<K>> {
+        self.candidates(pattern)
+            .into_iter()
+            .filter_map(|slot| {
+                let entry = self.entries[slot].as_ref()?;
+                let mut occurrences = Vec::new();
+                scan(&pattern.tree, &entry.tree, &mut occurrences);
+
+                (!occurrences.is_empty()).then(|| Match {
+                    id: entry.id.clone(),
+                    occurrences,
+                })
+            })
+            .collect()
+    }
+
+    ///
+    /// Slots of the entries containing all features of `pattern`.
+    ///
+    fn candidates(&self, pattern: &Pattern) -> Vec<usize> {
+        let mut postings = Vec::new();
+        for feature in &pattern.features {
+            match self.postings.get(feature) {
+                Some(slots) => postings.push(slots),
+                None => return Vec::new(),
+            }
+        }
+        postings.sort_by_key(|slots| slots.len());
+
+        let Some((rarest, rest)) = postings.split_first() else {
+            return (0..self.entries.len()).collect();
+        };
+        rarest
+            .iter()
+            .copied()
+            .filter(|slot| rest.iter().all(|slots| slots.binary_search(slot).is_ok()))
+            .collect()
+    }
+}
+
+///
+/// Rewrites `?a` into `"?a"`, so that wildcards are single tokens.
+///
+fn wildcards_as_text(asciimath: &str) -> String {
+    let mut source = String::with_capacity(asciimath.len());
+    let mut chars = asciimath.chars().peekable();
+    let mut quoted = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                source.push(c);
+            }
+            '?' if !quoted => {
+                source.push_str("\"?");
+                while let Some(letter) = chars.next_if(|c| c.is_alphabetic()) {
+                    source.push(letter);
+                }
+                source.push('"');
+            }
+            _ => source.push(c),
+        }
+    }
+
+    source
+}
+
+fn sequence(expressions: &Expressions, pattern: bool) -> Tree {
+    Tree::Sequence {
+        items: expressions
+            .expressions
+            .iter()
+            .filter(|expression| !matches!(expression, Expression::Unit))
+            .map(|expression| tree(expression, pattern))
+            .collect(),
+        span: expressions.span().unwrap_or(0..0),
+    }
+}
+
+fn tree(expression: &Expression, pattern: bool) -> Tree {
+    let node = |shape: String, children: Vec<&Expression>| Tree::Node {
+        shape,
+        children: children
+            .into_iter()
+            .map(|child| tree(child, pattern))
+            .collect(),
+        span: expression.span().unwrap_or(0..0),
+    };
+
+    match expression {
+        Expression::Frac(frac) => node(
+            "frac".to_string(),
+            vec![&frac.expression_1, &frac.expression_2],
+        ),
+        Expression::Sub(sub) => node(
+            "sub".to_string(),
+            vec![&sub.expression_1, &sub.expression_2],
+        ),
+        Expression::Pow(pow) => node(
+            "pow".to_string(),
+            vec![&pow.expression_1, &pow.expression_2],
+        ),
+        Expression::SubPow(sub_pow) => node(
+            "subpow".to_string(),
+            vec![
+                &sub_pow.expression_1,
+                &sub_pow.expression_2,
+                &sub_pow.expression_3,
+            ],
+        ),
+        // brackets of any style
+        Expression::Group(group) => Tree::Node {
+            shape: "group".to_string(),
+            children: vec![sequence(&group.expressions, pattern)],
+            span: expression.span().unwrap_or(0..0),
+        },
+        Expression::Unary(unary) => node(
+            format!("unary {}", token_key(&unary.operator)),
+            vec![&unary.expression],
+        ),
+        Expression::Binary(binary) => node(
+            format!("binary {}", token_key(&binary.operator)),
+            vec![&binary.expression_1, &binary.expression_2],
+        ),
+        Expression::Literal(Literal::Literal(token)) => match token.span.text.strip_prefix('?') {
+            Some(name) if pattern && token.token_type == TokenType::Text => {
+                Tree::Wildcard((!name.is_empty()).then(|| name.to_string()))
+            }
+            _ => Tree::Token {
+                key: token_key(token),
+                span: token.span.start..token.span.end,
+            },
+        },
+        Expression::Literal(Literal::Table(table)) => {
+            let columns: Vec<String> = table
+                .rows
+                .iter()
+                .map(|row| row.cols.len().to_string())
+                .collect();
+            Tree::Node {
+                shape: format!("table {}", columns.join(" ")),
+                children: table
+                    .rows
+                    .iter()
+                    .flat_map(|row| row.cols.iter())
+                    .map(|col| sequence(col, pattern))
+                    .collect(),
+                span: expression.span().unwrap_or(0..0),
+            }
+        }
+        Expression::Expressions(expressions) => sequence(expressions, pattern),
+        Expression::Unit => Tree::Sequence {
+            items: Vec::new(),
+            span: 0..0,
+        },
+    }
+}
+
+///
+/// Tokens of the same meaning have the same key, spelled symbols and
+/// numbers by their text.
+///
+fn token_key(token: &Token) -> String {
+    match token.token_type {
+        TokenType::Symbol | TokenType::Number | TokenType::Text => {
+            format!("{:?} {}", token.token_type, token.span.text)
+        }
+        _ => format!("{:?}", token.token_type),
+    }
+}
+
+fn collect_features(tree: &Tree, features: &mut HashSet<String>) {
+    match tree {
+        Tree::Token { key, .. } => {
+            features.insert(key.clone());
+        }
+        Tree::Wildcard(_) => {}
+        Tree::Node {
+            shape, children, ..
+        } => {
+            features.insert(shape.clone());
+            for child in children {
+                collect_features(child, features);
+            }
+        }
+        Tree::Sequence { items, .. } => {
+            for item in items {
+                collect_features(item, features);
+            }
+        }
+    }
+}
+
+///
+/// Collects the occurrences of `pattern` in every sequence of `tree`. In a
+/// sequence they don't overlap, start as early as possible and the wildcards
+/// take as many expressions as they can.
+///
+fn scan(pattern: &Tree, tree: &Tree, occurrences: &mut Vec<Occurrence>) {
+    let items = tree.items();
+    let patterns = pattern.items();
+
+    // no occurrence starts after the last of a token every one contains
+    let last_start = patterns
+        .iter()
+        .filter(|pattern| matches!(pattern, Tree::Token { .. }))
+        .map(|pattern| {
+            items
+                .iter()
+                .rposition(|item| may_match(pattern, item))
+                .map(|position| position as isize)
+                .unwrap_or(-1)
+        })
+        .min();
+
+    let mut start = 0;
+    while start < items.len() && last_start.is_none_or(|last| start as isize <= last) {
+        let found = match patterns.first() {
+            Some(first) if may_match(first, &items[start]) => {
+                let mut bindings = Vec::new();
+                let mut left = 0;
+                let goal = Goal {
+                    patterns,
+                    targets: &items[start..],
+                    open: true,
+                };
+                solve(&[goal], &mut bindings, &mut left)
+                    .then(|| (items.len() - left, bindings))
+                    .filter(|(end, _)| *end > start)
+            }
+            _ => None,
+        };
+
+        match found {
+            Some((end, bindings)) => {
+                occurrences.push(Occurrence {
+                    span: items[start].span().start..items[end - 1].span().end,
+                    bindings: bindings
+                        .into_iter()
+                        .map(|binding| Binding {
+                            name: binding.name,
+                            span: binding.span,
+                        })
+                        .collect(),
+                });
+                start = end;
+            }
+            None => start += 1,
+        }
+    }
+
+    for item in items {
+        match item {
+            Tree::Node { children, .. } => {
+                for child in children {
+                    scan(pattern, child, occurrences);
+                }
+            }
+            Tree::Sequence { .. } => scan(pattern, item, occurrences),
+            _ => {}
+        }
+    }
+
+    occurrences.sort_by_key(|occurrence| (occurrence.span.start, occurrence.span.end));
+}
+
+///
+/// Whether `pattern` can match `target`, judged by their heads only.
+///
+fn may_match(pattern: &Tree, target: &Tree) -> bool {
+    match (pattern, target) {
+        (Tree::Token { key, .. }, Tree::Token { key: other, .. }) => key == other,
+        (Tree::Node { shape, .. }, Tree::Node { shape: other, .. }) => shape == other,
+        (Tree::Token { .. } | Tree::Node { .. }, Tree::Token { .. } | Tree::Node { .. }) => false,
+        _ => true,
+    }
+}
+
+///
+/// Whether the tokens and nodes of `patterns` can be found in `targets` in
+/// the same order, which every match of `patterns` requires.
+///
+fn fits(patterns: &[Tree], targets: &[Tree]) -> bool {
+    let mut targets = targets.iter();
+    patterns
+        .iter()
+        .filter(|pattern| matches!(pattern, Tree::Token { .. } | Tree::Node { .. }))
+        .all(|pattern| targets.any(|target| may_match(pattern, target)))
+}
+
+///
+/// Patterns that have to match all of the targets, or a prefix of them if
+/// the goal is `open`.
+///
+#[derive(Clone, Copy)]
+struct Goal<'p, 't> {
+    patterns: &'p [Tree],
+    targets: &'t [Tree],
+    open: bool,
+}
+
+struct Bound {
+    name: String,
+    /// Structure of the matched expressions, equal for equal expressions
+    key: String,
+    span: Range<usize>,
+}
+
+///
+/// Whether all goals can be met at once, backtracking over the number of
+/// expressions the wildcards in sequences take. `left` receives the number
+/// of targets an open goal leaves over.
+///
+fn solve(goals: &[Goal], bindings: &mut Vec<Bound>, left: &mut usize) -> bool {
+    let Some((goal, rest)) = goals.split_first() else {
+        return true;
+    };
+    let Some((pattern, patterns)) = goal.patterns.split_first() else {
+        if goal.open {
+            *left = goal.targets.len();
+        }
+        return (goal.open || goal.targets.is_empty()) && solve(rest, bindings, left);
+    };
+
+    match pattern {
+        Tree::Wildcard(name) => {
+            if !fits(patterns, &goal.targets[1.min(goal.targets.len())..]) {
+                return false;
+            }
+
+            // every following pattern takes at least one expression
+            let longest = goal.targets.len().saturating_sub(patterns.len());
+            let shortest = match goal.open || !patterns.is_empty() {
+                true => 1,
+                false => longest.max(1),
+            };
+            let bound = bindings
+                .iter()
+                .find(|bound| Some(&bound.name) == name.as_ref());
+            let mut takes: Vec<usize> = match bound {
+                Some(bound) => equal_runs(&bound.key, &goal.targets[..longest])
+                    .into_iter()
+                    .filter(|taken| *taken >= shortest)
+                    .collect(),
+                None => (shortest..=longest).collect(),
+            };
+            if goal.open {
+                takes.reverse();
+            }
+
+            takes.into_iter().any(|taken| {
+                // the wildcard ends where the next pattern can start
+                if let (Some(next), Some(target)) = (patterns.first(), goal.targets.get(taken)) {
+                    if !may_match(next, target) {
+                        return false;
+                    }
+                }
+
+                let mark = bindings.len();
+                let run = &goal.targets[..taken];
+                if bind(name, run, bindings) {
+                    let next = Goal {
+                        patterns,
+                        targets: &goal.targets[taken..],
+                        open: goal.open,
+                    };
+                    if solve(&[&[next], rest].concat(), bindings, left) {
+                        return true;
+                    }
+                }
+                bindings.truncate(mark);
+                false
+            })
+        }
+        _ => {
+            let Some((target, targets)) = goal.targets.split_first() else {
+                return false;
+            };
+
+            let mut next = vec![];
+            match (pattern, target) {
+                (Tree::Token { key, .. }, Tree::Token { key: other, .. }) if key == other => {}
+                (
+                    Tree::Node {
+                        shape, children, ..
+                    },
+                    Tree::Node {
+                        shape: other_shape,
+                        children: other_children,
+                        ..
+                    },
+                ) if shape == other_shape && children.len() == other_children.len() => {
+                    next.extend(
+                        children
+                            .iter()
+                            .zip(other_children)
+                            .map(|(pattern, target)| Goal {
+                                patterns: pattern.items(),
+                                targets: target.items(),
+                                open: false,
+                            }),
+                    );
+                }
+                (Tree::Sequence { .. }, _) | (_, Tree::Sequence { .. }) => {
+                    next.push(Goal {
+                        patterns: pattern.items(),
+                        targets: target.items(),
+                        open: false,
+                    });
+                }
+                _ => return false,
+            }
+
+            next.push(Goal {
+                patterns,
+                targets,
+                open: goal.open,
+            });
+            next.extend_from_slice(rest);
+            solve(&next, bindings, left)
+        }
+    }
+}
+
+///
+/// Binds `name` to `run`, fails if it is bound to other expressions.
+///
+fn bind(name: &Option<String>, run: &[Tree], bindings: &mut Vec<Bound>) -> bool {
+    let Some(name) = name else {
+        return true;
+    };
+
+    let mut key = String::new();
+    write_key(run, &mut key);
+
+    match bindings.iter().find(|bound| bound.name == *name) {
+        Some(bound) => bound.key == key,
+        None => {
+            bindings.push(Bound {
+                name: name.clone(),
+                key,
+                span: run[0].span().start..run[run.len() - 1].span().end,
+            });
+            true
+        }
+    }
+}
+
+///
+/// Lengths of the runs at the start of `targets` whose structure is `key`,
+/// in ascending order.
+///
+fn equal_runs(key: &str, targets: &[Tree]) -> Vec<usize> {
+    let mut takes = vec![];
+    let mut run = String::new();
+    for (index, target) in targets.iter().enumerate() {
+        if index > 0 {
+            run.push(' ');
+        }
+        write_items(slice::from_ref(target), &mut run);
+
+        let equal = match index {
+            0 => {
+                let mut single = String::new();
+                write_key(slice::from_ref(target), &mut single);
+                single == key
+            }
+            _ => run == key,
+        };
+        if equal {
+            takes.push(index + 1);
+        }
+        // keys only grow with the run, apart from unwrapped groups
+        if index > 0 && run.len() > key.len() {
+            break;
+        }
+    }
+    takes
+}
+
+///
+/// Writes the structure of `run`, `(a+b)` like `a+b`.
+///
+fn write_key(run: &[Tree], key: &mut String) {
+    let run = match run {
+        [Tree::Node {
+            shape, children, ..
+        }] if shape == "group" => children[0].items(),
+        [Tree::Sequence { items, .. }] => items,
+        _ => run,
+    };
+    write_items(run, key);
+}
+
+///
+/// Writes the structure of each expression in `run`.
+///
+fn write_items(run: &[Tree], key: &mut String) {
+    for (index, tree) in run.iter().enumerate() {
+        if index > 0 {
+            key.push(' ');
+        }
+        match tree {
+            Tree::Token { key: token, .. } => key.push_str(token),
+            Tree::Wildcard(_) => key.push('?'),
+            Tree::Node {
+                shape, children, ..
+            } => {
+                key.push_str(shape);
+                key.push('(');
+                for (index, child) in children.iter().enumerate() {
+                    if index > 0 {
+                        key.push(',');
+                    }
+                    write_key(slice::from_ref(child), key);
+                }
+                key.push(')');
+            }
+            Tree::Sequence { items, .. } => {
+                key.push('[');
+                write_key(items, key);
+                key.push(']');
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Index, Pattern};
+    use std::time::{Duration, Instant};
+
+    fn spans(pattern: &str, asciimath: &str) -> Vec<String> {
+        Pattern::new(pattern)
+            .find(asciimath)
+            .into_iter()
+            .map(|occurrence| asciimath[occurrence.span].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_structure() {
+        assert_eq!(spans("a+b", "x + a + b"), vec!["a + b"]);
+        assert_eq!(spans("x != y", "x ne y, x != y"), vec!["x ne y", "x != y"]);
+        assert_eq!(spans("(x+1)^2", "[x + 1]^2 - (x+2)^2"), vec!["[x + 1]^2"]);
+        assert_eq!(spans("sqrt x", "1/sqrt x"), vec!["sqrt x"]);
+        assert!(spans("a+b", "b+a").is_empty());
+    }
+
+    #[test]
+    fn test_wildcards() {
+        assert_eq!(
+            spans("?a^2 + ?b^2 = ?c^2", "x + (3)^2 + 4^2 = 5^2"),
+            vec!["(3)^2 + 4^2 = 5^2"]
+        );
+        assert_eq!(
+            spans("int_?^? ?f dx", "int_0^1 x^2 + 1 dx = 4/3"),
+            vec!["int_0^1 x^2 + 1 dx"]
+        );
+        assert_eq!(spans("?a/?b", "1/2 + (x+1)/y"), vec!["1/2", "(x+1)/y"]);
+        assert_eq!(spans("sqrt(?)", "sqrt(x^2) + sqrt 2"), vec!["sqrt(x^2)"]);
+
+        let occurrences = Pattern::new("?x + ?x").find("2 + a + b + a + b");
+        assert_eq!(occurrences.len(), 1);
+        assert_eq!(occurrences[0].span, 4..17);
+        assert_eq!(occurrences[0].bindings[0].name, "x");
+        assert_eq!(occurrences[0].bindings[0].span, 4..9);
+    }
+
+    #[test]
+    fn test_unification() {
+        assert_eq!(spans("?a - ?a", "x - y + x - x"), vec!["x - x"]);
+        assert_eq!(
+            spans("?a^2 = ?a ?a", "(x+1)^2 = (x+1)(x+1)"),
+            vec!["(x+1)^2 = (x+1)(x+1)"]
+        );
+        assert_eq!(spans("?f(?x) = ?f ?x", "g(t) = g t"), vec!["g(t) = g t"]);
+        assert!(spans("?a^2 = ?a ?a", "(x+1)^2 = (x+1)(x-1)").is_empty());
+    }
+
+    #[test]
+    fn test_nested() {
+        assert_eq!(spans("?a^2", "(x^2)^2"), vec!["(x^2)^2", "x^2"]);
+    }
+
+    #[test]
+    fn test_index() {
+        let mut index = Index::new();
+        index.insert("pythagoras", "a^2 + b^2 = c^2");
+        index.insert("binomial", "(a+b)^2 = a^2 + 2ab + b^2");
+        index.insert("euler", "e^(i pi) + 1 = 0");
+        assert_eq!(index.len(), 3);
+
+        let pattern = Pattern::new("?x^2 + ?y^2");
+        let ids: Vec<_> = index
+            .search(&pattern)
+            .into_iter()
+            .map(|found| found.id)
+            .collect();
+        assert_eq!(ids, vec!["pythagoras"]);
+
+        index.insert("pythagoras", "x^2 + y^2 = r^2");
+        index.insert("binomial", "(a+b)^2 = a^2 + b^2");
+        assert!(index.remove(&"euler"));
+        assert!(!index.remove(&"euler"));
+
+        let ids: Vec<_> = index
+            .search(&pattern)
+            .into_iter()
+            .map(|found| found.id)
+            .collect();
+        assert_eq!(ids, vec!["pythagoras", "binomial"]);
+        assert_eq!(index.get(&"pythagoras"), Some("x^2 + y^2 = r^2"));
+        assert!(index.search(&Pattern::new("pi")).is_empty());
+    }
+
+    #[test]
+    fn test_many() {
+        let mut index = Index::new();
+        for id in 0..2_000 {
+            index.insert(id, &format!("x_{id} + {id}^2 = int_0^{id} t^2 dt"));
+        }
+
+        let found = index.search(&Pattern::new("?a + 42^2 = ?b"));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, 42);
+    }
+
+    #[test]
+    fn test_churn() {
+        let mut index = Index::new();
+        for round in 0..100 {
+            for id in 0..10 {
+                index.insert(id, &format!("x_{round} + {id}"));
+            }
+        }
+        assert_eq!(index.len(), 10);
+        assert!(index.entries.len() <= 20);
+        assert!(index.postings.len() <= 40);
+        assert!(index
+            .postings
+            .values()
+            .flatten()
+            .all(|slot| index.entries[*slot].is_some()));
+
+        let found = index.search(&Pattern::new("x_99 + 7"));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, 7);
+        assert!(index.search(&Pattern::new("x_98")).is_empty());
+    }
+
+    #[test]
+    fn test_long() {
+        let terms: Vec<_> = (0..200).map(|index| format!("x_{index}")).collect();
+        let sum = terms.join(" + ");
+        let started = Instant::now();
+
+        assert_eq!(spans("?a + ?b + ?c = ?d", &format!("y = {sum}")).len(), 0);
+        assert_eq!(spans("?a + ?b + ?c = ?d", &format!("{sum} = y")).len(), 1);
+        assert_eq!(spans("?a + ?b + ?a = ?d", &format!("y = {sum}")).len(), 0);
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,1477 @@
+//!
+//! # Semantics
+//!
+//! The AST keeps the flat expression sequences of the source, `a+b*c=d` is
+//! five siblings. This module reads them into a tree with operator
+//! precedence, weakest first: commas, relations, `+`/`-`, `*`/`/`/`mod`,
+//! implicit multiplication (`2x`), signs and finally operands, where
+//! functions are applied to their arguments (`sin 2x`, `f(x, y)`) and big
+//! operators to their bodies (`sum_(i=1)^n i`). Powers, fractions and roots
+//! already are nested in the AST. Every node links back to the source by
+//! its span. Nodes print as AsciiMath again, with the parentheses their
+//! nesting needs, so derived trees can go through the usual backends.
+//!
+
+use std::{fmt, ops::Range};
+
+use crate::{
+    ast::{Expression, Expressions, Group, Literal, TriExpression, AST},
+    symbols,
+    tokens::{
+        types::{
+            Arrow, BinaryOperator, Function, Misc, Operation, Relational, TokenType, UnaryOperator,
+        },
+        Token,
+    },
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub kind: Kind,
+    /// Byte range of the source, `None` for nodes that have been made up
+    pub span: Option<Range<usize>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Kind {
+    Number(f64),
+    /// Variables as spelled, subscripted ones like `x_1` included
+    Identifier(String),
+    /// Symbols with a fixed meaning like `oo`
+    Constant(Misc),
+    Negate(Box<Node>),
+    Binary(Operator, Box<Node>, Box<Node>),
+    Relation(Relation, Box<Node>, Box<Node>),
+    Apply {
+        function: Callee,
+        /// `2` in `log_2 x`
+        subscript: Option<Box<Node>>,
+        arguments: Vec<Node>,
+    },
+    /// `sqrt x` has no index
+    Root {
+        index: Option<Box<Node>>,
+        radicand: Box<Node>,
+    },
+    /// `abs`, `floor`, `ceil`, `norm` and accents like `hat`
+    Unary(UnaryOperator, Box<Node>),
+    BigOperator {
+        operator: BigOperator,
+        /// `i` in `sum_(i=1)^n`, `x` in `int f(x) dx` or `lim_(x->0)`
+        variable: Option<String>,
+        lower: Option<Box<Node>>,
+        upper: Option<Box<Node>>,
+        body: Box<Node>,
+    },
+    /// Comma separated expressions outside of argument lists
+    Tuple(Vec<Node>),
+    Table(Vec<Vec<Node>>),
+    Text(String),
+    /// Anything without a meaning in this tree, e.g. `overset`, as spelled
+    Other(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Add,
+    Subtract,
+    /// `implicit` for juxtaposed operands like `2x`
+    Multiply {
+        implicit: bool,
+    },
+    Divide,
+    Modulo,
+    Power,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Relation {
+    Relational(Relational),
+    Arrow(Arrow),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Callee {
+    Builtin(Function),
+    /// A symbol applied like a function, see [build_with_functions]
+    Named(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BigOperator {
+    Sum,
+    Prod,
+    Int,
+    OInt,
+    Lim,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    pub kind: ErrorKind,
+    /// Byte range of the source that doesn't fit the grammar
+    pub span: Range<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// An operand the parser made up or that is missing at the end
+    MissingOperand,
+    /// A token where an operand or operator was expected, e.g. a stray `^`
+    Unexpected(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ErrorKind::MissingOperand => write!(f, "missing operand"),
+            ErrorKind::Unexpected(text) => write!(f, "unexpected `{text}`"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+type Result<T> = std::result::Result<T, Error>;
+
+impl Node {
+    pub fn new(kind: Kind, span: Option<Range<usize>>) -> Self {
+        Self { kind, span }
+    }
+
+    ///
+    /// The nodes directly below this one, in source order.
+    ///
+    pub fn children(&self) -> Vec<&Node> {
+        match &self.kind {
+            Kind::Number(_)
+            | Kind::Identifier(_)
+            | Kind::Constant(_)
+            | Kind::Text(_)
+            | Kind::Other(_) => vec![],
+            Kind::Negate(operand) | Kind::Unary(_, operand) => vec![operand],
+            Kind::Binary(_, left, right) | Kind::Relation(_, left, right) => vec![left, right],
+            Kind::Apply {
+                subscript,
+                arguments,
+                ..
+            } => subscript
+                .iter()
+                .map(|node| &**node)
+                .chain(arguments)
+                .collect(),
+            Kind::Root { index, radicand } => index
+                .iter()
+                .map(|node| &**node)
+                .chain([&**radicand])
+                .collect(),
+            Kind::BigOperator {
+                lower, upper, body, ..
+            } => lower
+                .iter()
+                .chain(upper.iter())
+                .map(|node| &**node)
+                .chain([&**body])
+                .collect(),
+            Kind::Tuple(nodes) => nodes.iter().collect(),
+            Kind::Table(rows) => rows.iter().flatten().collect(),
+        }
+    }
+
+    pub fn children_mut(&mut self) -> Vec<&mut Node> {
+        match &mut self.kind {
+            Kind::Number(_)
+            | Kind::Identifier(_)
+            | Kind::Constant(_)
+            | Kind::Text(_)
+            | Kind::Other(_) => vec![],
+            Kind::Negate(operand) | Kind::Unary(_, operand) => vec![operand],
+            Kind::Binary(_, left, right) | Kind::Relation(_, left, right) => vec![left, right],
+            Kind::Apply {
+                subscript,
+                arguments,
+                ..
+            } => subscript
+                .iter_mut()
+                .map(|node| &mut **node)
+                .chain(arguments)
+                .collect(),
+            Kind::Root { index, radicand } => index
+                .iter_mut()
+                .map(|node| &mut **node)
+                .chain([&mut **radicand])
+                .collect(),
+            Kind::BigOperator {
+                lower, upper, body, ..
+            } => lower
+                .iter_mut()
+                .chain(upper.iter_mut())
+                .map(|node| &mut **node)
+                .chain([&mut **body])
+                .collect(),
+            Kind::Tuple(nodes) => nodes.iter_mut().collect(),
+            Kind::Table(rows) => rows.iter_mut().flatten().collect(),
+        }
+    }
+
+    ///
+    /// Whether both trees are equal apart from their spans, i.e. whether
+    /// they mean the same wherever they have been written.
+    ///
+    pub fn same_as(&self, other: &Node) -> bool {
+        self.without_spans() == other.without_spans()
+    }
+
+    fn without_spans(&self) -> Node {
+        let mut node = self.clone();
+        let mut pending = vec![&mut node];
+        while let Some(node) = pending.pop() {
+            node.span = None;
+            pending.extend(node.children_mut());
+        }
+        node
+    }
+}
+
+///
+/// Prints the tree as AsciiMath, e.g. `2x^2 + sin(x)`. Parentheses are added
+/// where the nesting needs them, the ones of the source aren't kept.
+///
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        write(&mut out, self);
+        f.write_str(&out)
+    }
+}
+
+///
+/// Builds the semantic tree of `ast`. Symbols followed by parentheses are
+/// multiplied, `a(b+c)`, only `f` and `g` are applied.
+///
+pub fn build(ast: &AST) -> Result<Node> {
+    build_with_functions(ast, &|_| false)
+}
+
+///
+/// Like [build], but symbols for which `is_function` returns `true` are
+/// applied to the parenthesized argument list following them, `h(x, y)`.
+///
+pub fn build_with_functions(ast: &AST, is_function: &dyn Fn(&str) -> bool) -> Result<Node> {
+    let end = ast.expressions.span().map_or(0, |span| span.end);
+    Builder {
+        is_function,
+        integrals: 0,
+    }
+    .sequence(
+        ast.expressions.expressions.iter().collect(),
+        end,
+        ast.expressions.span(),
+    )
+}
+
+struct Builder<'f> {
+    is_function: &'f dyn Fn(&str) -> bool,
+    /// Nesting depth of integral bodies, which end at `dx`
+    integrals: usize,
+}
+
+///
+/// Cursor over the expressions of a sequence.
+///
+struct Terms<'s, 'a> {
+    items: Vec<&'s Expression<'a>>,
+    position: usize,
+    /// Where a missing trailing operand is reported
+    end: usize,
+}
+
+impl<'s, 'a> Terms<'s, 'a> {
+    fn peek(&self) -> Option<&'s Expression<'a>> {
+        self.items.get(self.position).copied()
+    }
+
+    fn peek_token(&self) -> Option<&'s Token<'a>> {
+        literal(self.peek()?)
+    }
+
+    fn next(&mut self) -> Result<&'s Expression<'a>> {
+        let item = self.peek().ok_or(Error {
+            kind: ErrorKind::MissingOperand,
+            span: self.end..self.end,
+        })?;
+        self.position += 1;
+        Ok(item)
+    }
+}
+
+impl<'f> Builder<'f> {
+    fn sequence(
+        &mut self,
+        items: Vec<&Expression>,
+        end: usize,
+        span: Option<Range<usize>>,
+    ) -> Result<Node> {
+        // trailing whitespace leaves a made up token behind, missing operands
+        // are reported at the end anyway
+        let mut terms = Terms {
+            items: items
+                .into_iter()
+                .filter(|item| {
+                    !matches!(item, Expression::Unit)
+                        && literal(item).is_none_or(|token| token.token_type != TokenType::None)
+                })
+                .collect(),
+            position: 0,
+            end,
+        };
+
+        let mut nodes = vec![self.relation(&mut terms)?];
+        while terms.peek().is_some_and(is_comma) {
+            terms.next()?;
+            nodes.push(self.relation(&mut terms)?);
+        }
+
+        if let Some(rest) = terms.peek() {
+            return Err(unexpected(rest));
+        }
+
+        Ok(match nodes.len() {
+            1 => nodes.pop().unwrap(),
+            _ => Node::new(Kind::Tuple(nodes), span),
+        })
+    }
+
+    fn expressions(&mut self, expressions: &Expressions, end: usize) -> Result<Node> {
+        self.sequence(
+            expressions.expressions.iter().collect(),
+            end,
+            expressions.span(),
+        )
+    }
+
+    ///
+    /// A single expression, e.g. an operand of a fraction. Parentheses
+    /// around it only show up in the span.
+    ///
+    fn operand(&mut self, expression: &Expression) -> Result<Node> {
+        let span = span(expression);
+        let node = match expression {
+            Expression::Group(group) => {
+                self.expressions(&group.expressions, group.r_brace.span.start)?
+            }
+            Expression::Expressions(expressions) => self.expressions(expressions, span.end)?,
+            _ => self.sequence(vec![expression], span.end, Some(span.clone()))?,
+        };
+
+        Ok(Node {
+            span: Some(span),
+            ..node
+        })
+    }
+
+    fn relation(&mut self, terms: &mut Terms) -> Result<Node> {
+        let mut node = self.additive(terms)?;
+
+        loop {
+            let relation = match terms.peek_token().map(|token| &token.token_type) {
+                Some(TokenType::Relational(relational)) => Relation::Relational(relational.clone()),
+                Some(TokenType::Arrow(arrow)) => Relation::Arrow(arrow.clone()),
+                _ => return Ok(node),
+            };
+            terms.next()?;
+            let right = self.additive(terms)?;
+            node = binary(Kind::Relation, relation, node, right);
+        }
+    }
+
+    fn additive(&mut self, terms: &mut Terms) -> Result<Node> {
+        let mut node = self.multiplicative(terms)?;
+
+        loop {
+            let operator = match operation(terms.peek()) {
+                Some(Operation::Plus) => Operator::Add,
+                Some(Operation::Minus) => Operator::Subtract,
+                _ => return Ok(node),
+            };
+            terms.next()?;
+            let right = self.multiplicative(terms)?;
+            node = binary(Kind::Binary, operator, node, right);
+        }
+    }
+
+    fn multiplicative(&mut self, terms: &mut Terms) -> Result<Node> {
+        let mut node = self.implicit(terms)?;
+
+        loop {
+            let operator = match operation(terms.peek()) {
+                Some(Operation::CDot | Operation::Ast | Operation::Star | Operation::Times) => {
+                    Operator::Multiply { implicit: false }
+                }
+                Some(Operation::Div | Operation::Slash) => Operator::Divide,
+                _ if terms.peek().is_some_and(is_mod) => Operator::Modulo,
+                _ => return Ok(node),
+            };
+            terms.next()?;
+            let right = self.implicit(terms)?;
+            node = binary(Kind::Binary, operator, node, right);
+        }
+    }
+
+    ///
+    /// Juxtaposed operands, `2x` or `pi r^2`.
+    ///
+    fn implicit(&mut self, terms: &mut Terms) -> Result<Node> {
+        let mut node = self.signed(terms)?;
+
+        while terms.peek().is_some_and(is_operand) && !self.at_differential(terms) {
+            let right = self.signed(terms)?;
+            node = binary(
+                Kind::Binary,
+                Operator::Multiply { implicit: true },
+                node,
+                right,
+            );
+        }
+
+        Ok(node)
+    }
+
+    fn signed(&mut self, terms: &mut Terms) -> Result<Node> {
+        match operation(terms.peek()) {
+            Some(Operation::Minus) => {
+                let sign = span(terms.next()?);
+                let operand = self.signed(terms)?;
+                let span = merge(&Some(sign), &operand.span);
+                Ok(Node::new(Kind::Negate(Box::new(operand)), span))
+            }
+            Some(Operation::Plus) => {
+                terms.next()?;
+                self.signed(terms)
+            }
+            _ => self.application(terms),
+        }
+    }
+
+    ///
+    /// An operand, applying functions and big operators to the expressions
+    /// following them.
+    ///
+    fn application(&mut self, terms: &mut Terms) -> Result<Node> {
+        let item = terms.next()?;
+
+        match item {
+            Expression::Literal(Literal::Literal(token)) => match &token.token_type {
+                TokenType::Function(function) if *function != Function::Mod => {
+                    self.apply(terms, item, callee(function, token), None)
+                }
+                TokenType::Symbol
+                    if (self.is_function)(token.span.text)
+                        && matches!(terms.peek(), Some(Expression::Group(_))) =>
+                {
+                    let function = Callee::Named(token.span.text.to_string());
+                    self.apply(terms, item, function, None)
+                }
+                _ => match big_operator(item) {
+                    Some(operator) => self.big_operator(terms, item, operator, None, None),
+                    None => self.atom(item),
+                },
+            },
+            Expression::Sub(sub) => {
+                match (literal(&sub.expression_1), big_operator(&sub.expression_1)) {
+                    // `log_2 x`
+                    (Some(token), _) if token.token_type == TokenType::Function(Function::Log) => {
+                        let base = self.operand(&sub.expression_2)?;
+                        self.apply(terms, item, Callee::Builtin(Function::Log), Some(base))
+                    }
+                    (_, Some(operator)) => {
+                        self.big_operator(terms, item, operator, Some(&sub.expression_2), None)
+                    }
+                    _ => self.atom(item),
+                }
+            }
+            // `sin^2 x`
+            Expression::Pow(pow) => {
+                match (literal(&pow.expression_1), big_operator(&pow.expression_1)) {
+                    (
+                        Some(
+                            token @ Token {
+                                token_type: TokenType::Function(function),
+                                ..
+                            },
+                        ),
+                        _,
+                    ) if *function != Function::Mod => {
+                        let exponent = self.operand(&pow.expression_2)?;
+                        let node = self.apply(terms, item, callee(function, token), None)?;
+                        Ok(binary(Kind::Binary, Operator::Power, node, exponent))
+                    }
+                    (_, Some(operator)) => {
+                        self.big_operator(terms, item, operator, None, Some(&pow.expression_2))
+                    }
+                    _ => self.atom(item),
+                }
+            }
+            Expression::SubPow(sub_pow) => self.sub_pow(terms, item, sub_pow),
+            _ => self.atom(item),
+        }
+    }
+
+    fn sub_pow(
+        &mut self,
+        terms: &mut Terms,
+        item: &Expression,
+        sub_pow: &TriExpression,
+    ) -> Result<Node> {
+        if let Some(operator) = big_operator(&sub_pow.expression_1) {
+            return self.big_operator(
+                terms,
+                item,
+                operator,
+                Some(&sub_pow.expression_2),
+                Some(&sub_pow.expression_3),
+            );
+        }
+
+        match literal(&sub_pow.expression_1) {
+            Some(token) if token.token_type == TokenType::Function(Function::Log) => {
+                let base = self.operand(&sub_pow.expression_2)?;
+                let exponent = self.operand(&sub_pow.expression_3)?;
+                let node = self.apply(terms, item, Callee::Builtin(Function::Log), Some(base))?;
+                Ok(binary(Kind::Binary, Operator::Power, node, exponent))
+            }
+            _ => self.atom(item),
+        }
+    }
+
+    ///
+    /// Applies a function to a parenthesized argument list or the following
+    /// operands. In `sin(x)^2` the result is squared, not the argument.
+    ///
+    fn apply(
+        &mut self,
+        terms: &mut Terms,
+        item: &Expression,
+        function: Callee,
+        subscript: Option<Node>,
+    ) -> Result<Node> {
+        let (arguments, exponent, end) = match terms.peek() {
+            Some(argument @ Expression::Group(group)) => {
+                terms.next()?;
+                (self.arguments(group)?, None, span(argument))
+            }
+            Some(Expression::Pow(pow)) => match &*pow.expression_1 {
+                Expression::Group(group) => {
+                    terms.next()?;
+                    let exponent = self.operand(&pow.expression_2)?;
+                    let end = span(&pow.expression_1);
+                    (self.arguments(group)?, Some(exponent), end)
+                }
+                _ => {
+                    let argument = self.argument(terms)?;
+                    let end = argument.span.clone().unwrap_or_default();
+                    (vec![argument], None, end)
+                }
+            },
+            Some(_) => {
+                let argument = self.argument(terms)?;
+                let end = argument.span.clone().unwrap_or_default();
+                (vec![argument], None, end)
+            }
+            None => {
+                let end = span(item).end;
+                return Err(Error {
+                    kind: ErrorKind::MissingOperand,
+                    span: end..end,
+                });
+            }
+        };
+
+        let span = merge(&Some(span(item)), &Some(end));
+        let node = Node::new(
+            Kind::Apply {
+                function,
+                subscript: subscript.map(Box::new),
+                arguments,
+            },
+            span,
+        );
+
+        Ok(match exponent {
+            Some(exponent) => binary(Kind::Binary, Operator::Power, node, exponent),
+            None => node,
+        })
+    }
+
+    ///
+    /// The argument of a function without parentheses, the juxtaposed
+    /// operands up to the next function: `sin 2x` is `sin(2x)`, but
+    /// `sin x cos x` is `sin(x) cos(x)`.
+    ///
+    fn argument(&mut self, terms: &mut Terms) -> Result<Node> {
+        let mut node = self.signed(terms)?;
+
+        while terms
+            .peek()
+            .is_some_and(|next| is_operand(next) && !is_application(next))
+            && !self.at_differential(terms)
+        {
+            let right = self.signed(terms)?;
+            node = binary(
+                Kind::Binary,
+                Operator::Multiply { implicit: true },
+                node,
+                right,
+            );
+        }
+
+        Ok(node)
+    }
+
+    ///
+    /// The comma separated expressions of `group`.
+    ///
+    fn arguments(&mut self, group: &Group) -> Result<Vec<Node>> {
+        let mut arguments = Vec::new();
+        let mut argument = Vec::new();
+
+        for expression in group.expressions.expressions.iter() {
+            if is_comma(expression) {
+                let end = span(expression).start;
+                let items = std::mem::take(&mut argument);
+                arguments.push(self.sequence(items, end, None)?);
+            } else {
+                argument.push(expression);
+            }
+        }
+        arguments.push(self.sequence(argument, group.r_brace.span.start, None)?);
+
+        Ok(arguments)
+    }
+
+    ///
+    /// `sum` and `prod` take the following product as their body,
+    /// integrals everything up to `dx`. Lower bounds like `(i=1)` and
+    /// `(x->0)` bind their variable.
+    ///
+    fn big_operator(
+        &mut self,
+        terms: &mut Terms,
+        item: &Expression,
+        operator: BigOperator,
+        lower: Option<&Expression>,
+        upper: Option<&Expression>,
+    ) -> Result<Node> {
+        let mut variable = None;
+        let lower = match lower {
+            Some(lower) => {
+                let node = self.operand(lower)?;
+                Some(match node.kind {
+                    Kind::Relation(
+                        Relation::Relational(Relational::Equals)
+                        | Relation::Arrow(Arrow::ToArrow | Arrow::RightArrow),
+                        bound,
+                        value,
+                    ) if matches!(bound.kind, Kind::Identifier(_)) => {
+                        let Kind::Identifier(name) = bound.kind else {
+                            unreachable!()
+                        };
+                        variable = Some(name);
+                        *value
+                    }
+                    _ => node,
+                })
+            }
+            None => None,
+        };
+        let upper = upper.map(|upper| self.operand(upper)).transpose()?;
+
+        let body = match operator {
+            BigOperator::Int | BigOperator::OInt => {
+                self.integrals += 1;
+                let body = self.additive(terms);
+                let closed = self.at_differential(terms);
+                self.integrals -= 1;
+                let body = body?;
+
+                if closed {
+                    terms.next()?;
+                    let differential = terms.next()?;
+                    variable = literal(differential).map(|token| token.span.text.to_string());
+                    Node {
+                        span: merge(&body.span, &Some(span(differential))),
+                        ..body
+                    }
+                } else {
+                    body
+                }
+            }
+            _ => self.implicit(terms)?,
+        };
+
+        let span = merge(&Some(span(item)), &body.span);
+        Ok(Node::new(
+            Kind::BigOperator {
+                operator,
+                variable,
+                lower: lower.map(Box::new),
+                upper: upper.map(Box::new),
+                body: Box::new(body),
+            },
+            span,
+        ))
+    }
+
+    ///
+    /// Whether the terms continue with the `dx` closing an integral.
+    ///
+    fn at_differential(&self, terms: &Terms) -> bool {
+        self.integrals > 0
+            && terms.peek_token().is_some_and(|token| {
+                token.token_type == TokenType::Symbol && token.span.text == "d"
+            })
+            && terms.items.get(terms.position + 1).is_some_and(|next| {
+                literal(next).is_some_and(|token| {
+                    matches!(token.token_type, TokenType::Symbol | TokenType::Greek(_))
+                })
+            })
+    }
+
+    fn atom(&mut self, expression: &Expression) -> Result<Node> {
+        let span = Some(span(expression));
+        let kind = match expression {
+            Expression::Literal(Literal::Literal(token)) => return self.token(token),
+            Expression::Group(_) | Expression::Expressions(_) => return self.operand(expression),
+            Expression::Frac(frac) => Kind::Binary(
+                Operator::Divide,
+                Box::new(self.operand(&frac.expression_1)?),
+                Box::new(self.operand(&frac.expression_2)?),
+            ),
+            Expression::Pow(pow) => Kind::Binary(
+                Operator::Power,
+                Box::new(self.operand(&pow.expression_1)?),
+                Box::new(self.operand(&pow.expression_2)?),
+            ),
+            Expression::Sub(sub) => subscripted(&sub.expression_1, &sub.expression_2)
+                .unwrap_or_else(|| Kind::Other(source(expression))),
+            Expression::SubPow(sub_pow) => {
+                let base = match subscripted(&sub_pow.expression_1, &sub_pow.expression_2) {
+                    Some(kind) => Node::new(
+                        kind,
+                        merge(&sub_pow.expression_1.span(), &sub_pow.expression_2.span()),
+                    ),
+                    None => return Ok(Node::new(Kind::Other(source(expression)), span)),
+                };
+                Kind::Binary(
+                    Operator::Power,
+                    Box::new(base),
+                    Box::new(self.operand(&sub_pow.expression_3)?),
+                )
+            }
+            Expression::Unary(unary) => {
+                let operand = self.operand(&unary.expression)?;
+                match &unary.operator.token_type {
+                    TokenType::UnaryOperator(UnaryOperator::Sqrt) => Kind::Root {
+                        index: None,
+                        radicand: Box::new(operand),
+                    },
+                    TokenType::UnaryOperator(UnaryOperator::Text) => {
+                        Kind::Text(source(&unary.expression))
+                    }
+                    TokenType::UnaryOperator(operator) => {
+                        Kind::Unary(operator.clone(), Box::new(operand))
+                    }
+                    _ => Kind::Other(source(expression)),
+                }
+            }
+            Expression::Binary(binary) => match binary.operator.token_type {
+                TokenType::BinaryOperator(BinaryOperator::Root) => Kind::Root {
+                    index: Some(Box::new(self.operand(&binary.expression_1)?)),
+                    radicand: Box::new(self.operand(&binary.expression_2)?),
+                },
+                // colors don't change the meaning
+                TokenType::BinaryOperator(BinaryOperator::Color) => {
+                    return self.operand(&binary.expression_2)
+                }
+                _ => Kind::Other(source(expression)),
+            },
+            Expression::Literal(Literal::Table(table)) => Kind::Table(
+                table
+                    .rows
+                    .iter()
+                    .map(|row| {
+                        row.cols
+                            .iter()
+                            .map(|col| {
+                                let end = col.span().map_or(0, |span| span.end);
+                                self.expressions(col, end)
+                            })
+                            .collect::<Result<_>>()
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+            Expression::Unit => {
+                return Err(Error {
+                    kind: ErrorKind::MissingOperand,
+                    span: 0..0,
+                })
+            }
+        };
+
+        Ok(Node::new(kind, span))
+    }
+
+    fn token(&self, token: &Token) -> Result<Node> {
+        let text = token.span.text;
+        let span = token.span.start..token.span.end;
+        let unexpected = || Error {
+            kind: ErrorKind::Unexpected(text.to_string()),
+            span: span.clone(),
+        };
+
+        let kind = match &token.token_type {
+            TokenType::Number => Kind::Number(text.parse().map_err(|_| unexpected())?),
+            TokenType::Symbol if text.chars().all(char::is_alphabetic) => {
+                Kind::Identifier(text.to_string())
+            }
+            TokenType::Greek(_) => Kind::Identifier(text.to_string()),
+            TokenType::Misc(misc) => Kind::Constant(misc.clone()),
+            TokenType::Text => Kind::Text(text.to_string()),
+            TokenType::None => {
+                return Err(Error {
+                    kind: ErrorKind::MissingOperand,
+                    span,
+                })
+            }
+            _ => return Err(unexpected()),
+        };
+
+        Ok(Node::new(kind, Some(span)))
+    }
+}
+
+fn callee(function: &Function, token: &Token) -> Callee {
+    match function {
+        Function::F | Function::G => Callee::Named(token.span.text.to_string()),
+        _ => Callee::Builtin(function.clone()),
+    }
+}
+
+fn big_operator(expression: &Expression) -> Option<BigOperator> {
+    match literal(expression)?.token_type {
+        TokenType::Operation(Operation::Sum) => Some(BigOperator::Sum),
+        TokenType::Operation(Operation::Prod) => Some(BigOperator::Prod),
+        TokenType::Misc(Misc::Int) => Some(BigOperator::Int),
+        TokenType::Misc(Misc::OInt) => Some(BigOperator::OInt),
+        TokenType::Misc(Misc::Lim) => Some(BigOperator::Lim),
+        _ => None,
+    }
+}
+
+///
+/// A variable with a subscript like `x_1` or `a_(ij)`, named as spelled.
+///
+fn subscripted(base: &Expression, subscript: &Expression) -> Option<Kind> {
+    let base = literal(base)
+        .filter(|token| matches!(token.token_type, TokenType::Symbol | TokenType::Greek(_)))?;
+    let subscript = match subscript {
+        Expression::Group(group) => source(&Expression::Expressions(group.expressions.clone())),
+        _ => literal(subscript)?.span.text.to_string(),
+    };
+
+    (!subscript.is_empty()).then(|| Kind::Identifier(format!("{}_{subscript}", base.span.text)))
+}
+
+fn binary<O>(
+    kind: fn(O, Box<Node>, Box<Node>) -> Kind,
+    operator: O,
+    left: Node,
+    right: Node,
+) -> Node {
+    let span = merge(&left.span, &right.span);
+    Node::new(kind(operator, Box::new(left), Box::new(right)), span)
+}
+
+fn merge(a: &Option<Range<usize>>, b: &Option<Range<usize>>) -> Option<Range<usize>> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.start.min(b.start)..a.end.max(b.end)),
+        (Some(span), None) | (None, Some(span)) => Some(span.clone()),
+        (None, None) => None,
+    }
+}
+
+///
+/// The spelling of `expression`, its tokens without whitespace.
+///
+fn source(expression: &Expression) -> String {
+    let mut source = String::new();
+    expression.for_each_token(&mut |token| source.push_str(token.span.text));
+    source
+}
+
+fn literal<'s, 'a>(expression: &'s Expression<'a>) -> Option<&'s Token<'a>> {
+    match expression {
+        Expression::Literal(Literal::Literal(token)) => Some(token),
+        _ => None,
+    }
+}
+
+fn operation<'s>(expression: Option<&'s Expression>) -> Option<&'s Operation> {
+    match &literal(expression?)?.token_type {
+        TokenType::Operation(operation) => Some(operation),
+        _ => None,
+    }
+}
+
+fn is_comma(expression: &Expression) -> bool {
+    literal(expression)
+        .is_some_and(|token| token.token_type == TokenType::Symbol && token.span.text == ",")
+}
+
+fn is_mod(expression: &Expression) -> bool {
+    literal(expression).is_some_and(|token| token.token_type == TokenType::Function(Function::Mod))
+}
+
+///
+/// Whether `expression` is a builtin function or a big operator, possibly
+/// with a subscript or exponent.
+///
+fn is_application(expression: &Expression) -> bool {
+    let head = match expression {
+        Expression::Sub(sub) => &*sub.expression_1,
+        Expression::Pow(pow) => &*pow.expression_1,
+        Expression::SubPow(sub_pow) => &*sub_pow.expression_1,
+        _ => expression,
+    };
+
+    big_operator(head).is_some()
+        || literal(head).is_some_and(|token| {
+            matches!(&token.token_type, TokenType::Function(function) if *function != Function::Mod)
+        })
+}
+
+///
+/// Whether `expression` can start an operand of an implicit multiplication.
+///
+fn is_operand(expression: &Expression) -> bool {
+    match literal(expression) {
+        Some(token) => match &token.token_type {
+            TokenType::Symbol => token.span.text != ",",
+            TokenType::Function(function) => *function != Function::Mod,
+            TokenType::Number
+            | TokenType::Greek(_)
+            | TokenType::Misc(_)
+            | TokenType::Text
+            | TokenType::None => true,
+            TokenType::Operation(operation) => {
+                matches!(operation, Operation::Sum | Operation::Prod)
+            }
+            _ => false,
+        },
+        None => true,
+    }
+}
+
+fn unexpected(expression: &Expression) -> Error {
+    Error {
+        kind: ErrorKind::Unexpected(source(expression)),
+        span: span(expression),
+    }
+}
+
+///
+/// Byte range of `expression`, for operands the parser made up the position
+/// they are missing at.
+///
+fn span(expression: &Expression) -> Range<usize> {
+    expression.span().unwrap_or_else(|| {
+        let mut span = None;
+        expression.for_each_token(&mut |token| {
+            span.get_or_insert(token.span.start..token.span.end);
+        });
+        span.unwrap_or(0..0)
+    })
+}
+
+/// Binding strength of printed nodes, weakest first
+const SEQUENCE: u8 = 0;
+const RELATION: u8 = 1;
+const ADDITIVE: u8 = 2;
+const MULTIPLICATIVE: u8 = 3;
+const IMPLICIT: u8 = 4;
+const SIGNED: u8 = 5;
+const FRACTION: u8 = 6;
+const POWER: u8 = 7;
+const ATOM: u8 = 8;
+
+fn precedence(node: &Node) -> u8 {
+    match &node.kind {
+        Kind::Tuple(_) => SEQUENCE,
+        Kind::Relation(..) => RELATION,
+        Kind::Binary(Operator::Add | Operator::Subtract, ..) => ADDITIVE,
+        Kind::Binary(Operator::Multiply { implicit: false } | Operator::Modulo, ..) => {
+            MULTIPLICATIVE
+        }
+        Kind::Binary(Operator::Multiply { implicit: true }, ..) | Kind::BigOperator { .. } => {
+            IMPLICIT
+        }
+        Kind::Negate(_) => SIGNED,
+        Kind::Number(number) if number.is_sign_negative() => SIGNED,
+        Kind::Binary(Operator::Divide, ..) => FRACTION,
+        Kind::Binary(Operator::Power, ..) => POWER,
+        _ => ATOM,
+    }
+}
+
+///
+/// The weakest operator whose operands a node followed by one would take
+/// in, `sum_i i` continues into `sum_i i x`. Closed nodes reach nothing.
+///
+fn reach(node: &Node) -> u8 {
+    match &node.kind {
+        Kind::BigOperator {
+            operator: BigOperator::Int | BigOperator::OInt,
+            variable,
+            ..
+        } => match variable {
+            Some(_) => ATOM + 1,
+            None => ADDITIVE,
+        },
+        Kind::BigOperator { .. } => IMPLICIT,
+        Kind::Negate(operand) => reach(operand),
+        Kind::Binary(_, _, right) | Kind::Relation(_, _, right) => reach(right),
+        _ => ATOM + 1,
+    }
+}
+
+///
+/// Operands of `/` have to be single expressions, and functions would
+/// take the fraction as their argument.
+///
+fn fraction_operand(node: &Node) -> bool {
+    match &node.kind {
+        Kind::Apply { .. } => false,
+        Kind::Binary(Operator::Power, base, _) => fraction_operand(base),
+        _ => precedence(node) >= POWER,
+    }
+}
+
+///
+/// Arguments a builtin function takes without parentheses.
+///
+fn bare_argument(node: &Node) -> bool {
+    match &node.kind {
+        Kind::Number(number) => number.is_finite() && *number >= 0.0,
+        Kind::Identifier(_) | Kind::Constant(_) => true,
+        _ => false,
+    }
+}
+
+fn write_callee(out: &mut String, function: &Callee, subscript: &Option<Box<Node>>) {
+    match function {
+        Callee::Builtin(function) => {
+            out.push_str(token_spelling(&TokenType::Function(function.clone())))
+        }
+        Callee::Named(name) => out.push_str(name),
+    }
+    if let Some(subscript) = subscript {
+        out.push('_');
+        write_operand(out, subscript, ATOM);
+    }
+}
+
+fn write(out: &mut String, node: &Node) {
+    match &node.kind {
+        Kind::Number(number) if number.is_infinite() => {
+            out.push_str(if *number < 0.0 { "-oo" } else { "oo" })
+        }
+        Kind::Number(number) => out.push_str(&number.to_string()),
+        Kind::Identifier(name) => match name.split_once('_') {
+            Some((base, subscript)) if subscript.chars().count() > 1 => {
+                out.push_str(&format!("{base}_({subscript})"))
+            }
+            _ => out.push_str(name),
+        },
+        Kind::Constant(misc) => out.push_str(token_spelling(&TokenType::Misc(misc.clone()))),
+        Kind::Negate(operand) => {
+            out.push('-');
+            let start = out.len();
+            write_operand(out, operand, IMPLICIT);
+
+            // `--x` would be read as a single token
+            if out[start..].starts_with('-') {
+                out.truncate(start);
+                parenthesized(out, operand);
+            }
+        }
+        Kind::Binary(Operator::Divide, left, right) => {
+            for (index, operand) in [left, right].into_iter().enumerate() {
+                if index > 0 {
+                    out.push('/');
+                }
+                match (fraction_operand(operand), &operand.kind) {
+                    (true, _) => write(out, operand),
+                    // `(sin x)/x` rather than `(sin(x))/x`
+                    (
+                        false,
+                        Kind::Apply {
+                            function: function @ Callee::Builtin(_),
+                            subscript,
+                            arguments,
+                        },
+                    ) if matches!(&arguments[..], [argument] if bare_argument(argument)) => {
+                        out.push('(');
+                        write_callee(out, function, subscript);
+                        out.push(' ');
+                        write(out, &arguments[0]);
+                        out.push(')');
+                    }
+                    (false, _) => parenthesized(out, operand),
+                }
+            }
+        }
+        Kind::Binary(Operator::Power, base, exponent) => {
+            write_operand(out, base, ATOM);
+            out.push('^');
+            write_operand(out, exponent, ATOM);
+        }
+        Kind::Binary(operator, left, right) => {
+            let level = precedence(node);
+            match reach(left) <= level {
+                true => parenthesized(out, left),
+                false => write_operand(out, left, level),
+            }
+
+            let separator = match operator {
+                Operator::Add => " + ",
+                Operator::Subtract => " - ",
+                Operator::Multiply { implicit: false } => " * ",
+                Operator::Modulo => " mod ",
+                _ => " ",
+            };
+            // a sign would turn `x (-y)` into a subtraction
+            let start = out.len();
+            write_operand(
+                out,
+                right,
+                if level == IMPLICIT {
+                    FRACTION
+                } else {
+                    level + 1
+                },
+            );
+
+            // `2x`, but `x y` so that the letters don't form a word
+            let glued = separator == " "
+                && matches!(left.kind, Kind::Number(_))
+                && out[start..].starts_with(|c: char| c.is_ascii_alphabetic());
+            if !glued {
+                out.insert_str(start, separator);
+            }
+        }
+        Kind::Relation(relation, left, right) => {
+            write_operand(out, left, RELATION);
+            out.push(' ');
+            out.push_str(token_spelling(&match relation {
+                Relation::Relational(relational) => TokenType::Relational(relational.clone()),
+                Relation::Arrow(arrow) => TokenType::Arrow(arrow.clone()),
+            }));
+            out.push(' ');
+            write_operand(out, right, ADDITIVE);
+        }
+        Kind::Apply {
+            function,
+            subscript,
+            arguments,
+        } => {
+            write_callee(out, function, subscript);
+            write_list(out, "(", arguments, ")");
+        }
+        Kind::Root { index, radicand } => {
+            match index {
+                Some(index) => {
+                    out.push_str("root");
+                    parenthesized(out, index);
+                }
+                None => out.push_str("sqrt"),
+            }
+            parenthesized(out, radicand);
+        }
+        Kind::Unary(operator, operand) => {
+            out.push_str(token_spelling(&TokenType::UnaryOperator(operator.clone())));
+            parenthesized(out, operand);
+        }
+        Kind::BigOperator {
+            operator,
+            variable,
+            lower,
+            upper,
+            body,
+        } => {
+            out.push_str(token_spelling(&match operator {
+                BigOperator::Sum => TokenType::Operation(Operation::Sum),
+                BigOperator::Prod => TokenType::Operation(Operation::Prod),
+                BigOperator::Int => TokenType::Misc(Misc::Int),
+                BigOperator::OInt => TokenType::Misc(Misc::OInt),
+                BigOperator::Lim => TokenType::Misc(Misc::Lim),
+            }));
+
+            let integral = matches!(operator, BigOperator::Int | BigOperator::OInt);
+            if let Some(lower) = lower {
+                out.push('_');
+                match (variable, operator) {
+                    (Some(variable), BigOperator::Sum | BigOperator::Prod | BigOperator::Lim) => {
+                        let relation = if *operator == BigOperator::Lim {
+                            "->"
+                        } else {
+                            "="
+                        };
+                        out.push_str(&format!("({variable}{relation}"));
+                        write(out, lower);
+                        out.push(')');
+                    }
+                    _ => write_operand(out, lower, ATOM),
+                }
+            }
+            if let Some(upper) = upper {
+                out.push('^');
+                write_operand(out, upper, ATOM);
+            }
+
+            out.push(' ');
+            match (integral, variable) {
+                (true, Some(variable)) => {
+                    write_operand(out, body, ADDITIVE);
+                    out.push_str(&format!(" d{variable}"));
+                }
+                (true, None) => write_operand(out, body, ADDITIVE),
+                (false, _) => write_operand(out, body, IMPLICIT),
+            }
+        }
+        Kind::Tuple(nodes) => write_list(out, "", nodes, ""),
+        Kind::Table(rows) => {
+            out.push('[');
+            for (index, row) in rows.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                write_list(out, "[", row, "]");
+            }
+            out.push(']');
+        }
+        Kind::Text(text) if text.contains('"') => out.push_str(&format!("text({text})")),
+        Kind::Text(text) => out.push_str(&format!("\"{text}\"")),
+        Kind::Other(source) => out.push_str(source),
+    }
+}
+
+///
+/// Writes `node`, in parentheses if it binds weaker than `min`.
+///
+fn write_operand(out: &mut String, node: &Node, min: u8) {
+    match precedence(node) < min {
+        true => parenthesized(out, node),
+        false => write(out, node),
+    }
+}
+
+fn parenthesized(out: &mut String, node: &Node) {
+    out.push('(');
+    write(out, node);
+    out.push(')');
+}
+
+fn write_list(out: &mut String, open: &str, nodes: &[Node], close: &str) {
+    out.push_str(open);
+    for (index, node) in nodes.iter().enumerate() {
+        if index > 0 {
+            out.push_str(", ");
+        }
+        write_operand(out, node, RELATION);
+    }
+    out.push_str(close);
+}
+
+fn token_spelling(token_type: &TokenType) -> &'static str {
+    symbols::spelling(token_type).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::to_ast;
+
+    use super::{build, BigOperator, Callee, Error, ErrorKind, Kind, Node, Operator};
+
+    ///
+    /// Prefix notation of `node`, e.g. `(+ a (* b c))`.
+    ///
+    fn sexp(node: &Node) -> String {
+        let list = |head: &str, nodes: &[&Node]| {
+            let nodes: Vec<_> = nodes.iter().map(|node| sexp(node)).collect();
+            format!("({head} {})", nodes.join(" "))
+        };
+
+        match &node.kind {
+            Kind::Number(number) => number.to_string(),
+            Kind::Identifier(name) => name.clone(),
+            Kind::Negate(operand) => list("-", &[operand]),
+            Kind::Binary(operator, left, right) => {
+                let head = match operator {
+                    Operator::Add => "+",
+                    Operator::Subtract => "-",
+                    Operator::Multiply { implicit: true } => "*i",
+                    Operator::Multiply { implicit: false } => "*",
+                    Operator::Divide => "/",
+                    Operator::Modulo => "mod",
+                    Operator::Power => "^",
+                };
+                list(head, &[left, right])
+            }
+            Kind::Relation(..) => {
+                let Kind::Relation(_, left, right) = &node.kind else {
+                    unreachable!()
+                };
+                list("rel", &[left, right])
+            }
+            Kind::Apply {
+                function,
+                arguments,
+                ..
+            } => {
+                let head = match function {
+                    Callee::Builtin(function) => format!("{function:?}"),
+                    Callee::Named(name) => name.clone(),
+                };
+                list(&head, &arguments.iter().collect::<Vec<_>>())
+            }
+            Kind::BigOperator {
+                operator,
+                variable,
+                lower,
+                upper,
+                body,
+            } => {
+                let head = format!("{operator:?} {}", variable.as_deref().unwrap_or("_"));
+                let bounds: Vec<&Node> = lower.iter().chain(upper.iter()).map(|b| &**b).collect();
+                list(&head, &[bounds, vec![&**body]].concat())
+            }
+            Kind::Tuple(nodes) => list("tuple", &nodes.iter().collect::<Vec<_>>()),
+            kind => format!("{kind:?}"),
+        }
+    }
+
+    fn tree(asciimath: &str) -> String {
+        sexp(&build(&to_ast(asciimath)).unwrap())
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!(tree("a+b*c=d"), "(rel (+ a (* b c)) d)");
+        assert_eq!(tree("a-b-c"), "(- (- a b) c)");
+        assert_eq!(tree("2x+3y/4"), "(+ (*i 2 x) (*i 3 (/ y 4)))");
+        assert_eq!(tree("-x^2 * -2"), "(* (- (^ x 2)) (- 2))");
+        assert_eq!(tree("a(b+c) mod 2"), "(mod (*i a (+ b c)) 2)");
+        assert_eq!(tree("(1+2)/3, x_1"), "(tuple (/ (+ 1 2) 3) x_1)");
+    }
+
+    #[test]
+    fn test_functions() {
+        assert_eq!(tree("sin x cos y"), "(*i (Sin x) (Cos y))");
+        assert_eq!(tree("sin 2x"), "(Sin (*i 2 x))");
+        assert_eq!(tree("2 sin x y + 1"), "(+ (*i 2 (Sin (*i x y))) 1)");
+        assert_eq!(tree("sin(2)x"), "(*i (Sin 2) x)");
+        assert_eq!(tree("sinh x + cosh^2 x"), "(+ (Sinh x) (^ (Cosh x) 2))");
+        assert_eq!(tree("sin^2 x"), "(^ (Sin x) 2)");
+        assert_eq!(tree("sin(x)^2"), "(^ (Sin x) 2)");
+        assert_eq!(tree("f(x, y) + log_2 8"), "(+ (f x y) (Log 8))");
+    }
+
+    #[test]
+    fn test_big_operators() {
+        assert_eq!(tree("sum_(i=1)^n i^2 + 1"), "(+ (Sum i 1 n (^ i 2)) 1)");
+        assert_eq!(tree("int_0^1 x^2 + 1 dx"), "(Int x 0 1 (+ (^ x 2) 1))");
+        assert_eq!(
+            tree("lim_(x->oo) 1/x"),
+            "(Lim x Constant(Infinity) (/ 1 x))"
+        );
+
+        let Kind::BigOperator { operator, .. } = build(&to_ast("prod k")).unwrap().kind else {
+            panic!("expected a big operator")
+        };
+        assert_eq!(operator, BigOperator::Prod);
+    }
+
+    #[test]
+    fn test_display() {
+        let printed = |asciimath| build(&to_ast(asciimath)).unwrap().to_string();
+
+        assert_eq!(printed("a+b*c != d"), "a + b * c != d");
+        assert_eq!(printed("2x (a-b)^2 - -y"), "2x (a - b)^2 - -y");
+        assert_eq!(
+            printed("(sin x)/x_(ij) + sqrt(x/2)"),
+            "(sin x)/x_(ij) + sqrt(x/2)"
+        );
+        assert_eq!(printed("x(-y) + -(-z)"), "x (-y) + -(-z)");
+        assert_eq!(printed("(sum_(i=1)^n i) k"), "(sum_(i=1)^n i) k");
+        assert_eq!(printed("int_0^1 x + 1 dx"), "int_0^1 x + 1 dx");
+        assert_eq!(printed("log_2(x), \"a\""), "log_2(x), \"a\"");
+
+        for asciimath in [
+            "a - (b - c)",
+            "(a/b)^(c/d)",
+            "(cos x)/(log_2 y) + (sin(2x))/y",
+            "lim_(x->oo) 1/x",
+            "[[1, x], [y, 2]]",
+        ] {
+            let node = build(&to_ast(asciimath)).unwrap();
+            assert!(build(&to_ast(&node.to_string())).unwrap().same_as(&node));
+        }
+    }
+
+    #[test]
+    fn test_spans() {
+        let node = build(&to_ast("a + b*c")).unwrap();
+        let Kind::Binary(_, left, right) = &node.kind else {
+            panic!("expected a binary node")
+        };
+
+        assert_eq!(node.span, Some(0..7));
+        assert_eq!(left.span, Some(0..1));
+        assert_eq!(right.span, Some(4..7));
+    }
+
+    #[test]
+    fn test_errors() {
+        // a power of a power has no agreed reading
+        assert_eq!(
+            build(&to_ast("2^3^2")).unwrap_err(),
+            Error {
+                kind: ErrorKind::Unexpected("^".to_string()),
+                span: 3..4
+            }
+        );
+
+        // missing operands are reported where they are missing
+        assert_eq!(
+            build(&to_ast("1 +")).unwrap_err(),
+            Error {
+                kind: ErrorKind::MissingOperand,
+                span: 3..3
+            }
+        );
+        assert_eq!(
+            build(&to_ast("2 sin")).unwrap_err(),
+            Error {
+                kind: ErrorKind::MissingOperand,
+                span: 5..5
+            }
+        );
+        assert_eq!(
+            build(&to_ast("x = ")).unwrap_err().to_string(),
+            "missing operand"
+        );
+
+        // trailing whitespace is not a missing operand
+        assert!(build(&to_ast("a + b ")).is_ok());
+    }
+}
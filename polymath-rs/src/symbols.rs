@@ -0,0 +1,492 @@
+//!
+//! # Symbols
+//!
+//! Queryable view of the symbol tables in [crate::tokens::types], e.g. to
+//! generate palettes or autocompletion. Every table entry becomes a
+//! [Symbol], its first spelling is the canonical name, the others are
+//! aliases.
+//!
+
+use std::sync::OnceLock;
+
+use crate::{
+    mathml::{symbol_to_mathml, text_content},
+    tokens::{
+        types::{
+            Arrow, BinaryOperator, Function, Greek, LBrace, Logical, Misc, Operation, RBrace,
+            Relational, TokenType, UnaryOperator, ARROWS, BINARY_OPERATORS, FUNCTION, GREEK,
+            LBRACES, LOGICAL, MISC, OPERATION, RBRACES, RELATIONAL, UNARY_OPERATORS,
+        },
+        Span, Token,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category {
+    UnaryOperator,
+    BinaryOperator,
+    Arrow,
+    Operation,
+    Greek,
+    Misc,
+    Relational,
+    Logical,
+    Function,
+    LBrace,
+    RBrace,
+}
+
+impl Category {
+    /// All categories in the order the tokenizer tries them
+    pub const ALL: [Category; 11] = [
+        Category::UnaryOperator,
+        Category::BinaryOperator,
+        Category::Arrow,
+        Category::Operation,
+        Category::Greek,
+        Category::Misc,
+        Category::Relational,
+        Category::Logical,
+        Category::Function,
+        Category::LBrace,
+        Category::RBrace,
+    ];
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Category::UnaryOperator => "unary operator",
+            Category::BinaryOperator => "binary operator",
+            Category::Arrow => "arrow",
+            Category::Operation => "operation",
+            Category::Greek => "greek letter",
+            Category::Misc => "miscellaneous symbol",
+            Category::Relational => "relation",
+            Category::Logical => "logical symbol",
+            Category::Function => "function",
+            Category::LBrace => "opening bracket",
+            Category::RBrace => "closing bracket",
+        }
+    }
+
+    fn table(self) -> &'static [(&'static [&'static str], TokenType)] {
+        match self {
+            Category::UnaryOperator => UNARY_OPERATORS,
+            Category::BinaryOperator => BINARY_OPERATORS,
+            Category::Arrow => ARROWS,
+            Category::Operation => OPERATION,
+            Category::Greek => GREEK,
+            Category::Misc => MISC,
+            Category::Relational => RELATIONAL,
+            Category::Logical => LOGICAL,
+            Category::Function => FUNCTION,
+            Category::LBrace => LBRACES,
+            Category::RBrace => RBRACES,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    /// Canonical spelling
+    pub name: &'static str,
+    /// Further spellings of the same symbol
+    pub aliases: &'static [&'static str],
+    pub category: Category,
+    pub token_type: TokenType,
+    /// Character the symbol is rendered as, if it is a single one
+    pub code_point: Option<char>,
+    pub description: String,
+}
+
+impl Symbol {
+    pub fn spellings(&self) -> impl Iterator<Item = &'static str> {
+        std::iter::once(self.name).chain(self.aliases.iter().copied())
+    }
+}
+
+///
+/// Every symbol, grouped by category in tokenizer order.
+///
+pub fn all() -> &'static [Symbol] {
+    static SYMBOLS: OnceLock<Vec<Symbol>> = OnceLock::new();
+
+    SYMBOLS.get_or_init(|| {
+        Category::ALL
+            .into_iter()
+            .flat_map(|category| {
+                category
+                    .table()
+                    .iter()
+                    .map(move |(spellings, token_type)| symbol(category, spellings, token_type))
+            })
+            .collect()
+    })
+}
+
+///
+/// The symbol `spelling` belongs to.
+///
+pub fn lookup(spelling: &str) -> Option<&'static Symbol> {
+    all()
+        .iter()
+        .find(|symbol| symbol.spellings().any(|candidate| candidate == spelling))
+}
+
+///
+/// The canonical spelling of `token_type`, e.g. `!=` for
+/// `Relational(NotEquals)`.
+///
+pub fn spelling(token_type: &TokenType) -> Option<&'static str> {
+    all()
+        .iter()
+        .find(|symbol| symbol.token_type == *token_type)
+        .map(|symbol| symbol.name)
+}
+
+///
+/// Spellings starting with `prefix` along with their symbol, shortest first.
+///
+pub fn search(prefix: &str) -> Vec<(&'static str, &'static Symbol)> {
+    let mut found = all()
+        .iter()
+        .flat_map(|symbol| symbol.spellings().map(move |spelling| (spelling, symbol)))
+        .filter(|(spelling, _)| spelling.starts_with(prefix))
+        .collect::<Vec<_>>();
+
+    found.sort_by_key(|(spelling, _)| (spelling.len(), *spelling));
+    found
+}
+
+fn symbol(
+    category: Category,
+    spellings: &'static [&'static str],
+    token_type: &TokenType,
+) -> Symbol {
+    let token = Token {
+        span: Span {
+            text: spellings[0],
+            start: 0,
+            end: spellings[0].len(),
+        },
+        token_type: token_type.clone(),
+    };
+
+    // operators are rendered around their arguments, they have no single code point
+    let text = text_content(&symbol_to_mathml(&token));
+    let mut chars = text.chars();
+    let code_point = match (chars.next(), chars.next(), &token.token_type) {
+        (_, _, TokenType::UnaryOperator(_) | TokenType::BinaryOperator(_)) => None,
+        (Some(c), None, _) => Some(c),
+        _ => None,
+    };
+
+    Symbol {
+        name: spellings[0],
+        aliases: &spellings[1..],
+        category,
+        token_type: token_type.clone(),
+        code_point,
+        description: describe(token_type).to_string(),
+    }
+}
+
+///
+/// Short description of a symbol for palettes and completions.
+///
+fn describe(token_type: &TokenType) -> &'static str {
+    match token_type {
+        TokenType::UnaryOperator(operator) => match operator {
+            UnaryOperator::Hat => "hat accent",
+            UnaryOperator::Bar => "overline",
+            UnaryOperator::Ul => "underline",
+            UnaryOperator::Vec => "vector arrow",
+            UnaryOperator::Tilde => "tilde accent",
+            UnaryOperator::Dot => "dot accent",
+            UnaryOperator::DDot => "double dot accent",
+            UnaryOperator::UBrace => "underbrace",
+            UnaryOperator::OBrace => "overbrace",
+            UnaryOperator::Cancel => "cancellation stroke",
+            UnaryOperator::Sqrt => "square root",
+            UnaryOperator::Text => "text",
+            UnaryOperator::Abs => "absolute value",
+            UnaryOperator::Floor => "floor",
+            UnaryOperator::Ceil => "ceiling",
+            UnaryOperator::Norm => "norm",
+        },
+        TokenType::BinaryOperator(operator) => match operator {
+            BinaryOperator::Root => "n-th root",
+            BinaryOperator::Overset => "symbol set over another",
+            BinaryOperator::Underset => "symbol set under another",
+            BinaryOperator::Color => "colored expression",
+        },
+        TokenType::Arrow(arrow) => match arrow {
+            Arrow::UpArrow => "upwards arrow",
+            Arrow::DownArrow => "downwards arrow",
+            Arrow::RightArrow => "rightwards arrow",
+            Arrow::ToArrow => "tends to",
+            Arrow::RightArrowTail => "rightwards arrow with tail",
+            Arrow::RightArrowTwoHead => "rightwards two-headed arrow",
+            Arrow::RightArrowTwoHeadTail => "rightwards two-headed arrow with tail",
+            Arrow::MapsTo => "maps to",
+            Arrow::LeftArrow => "leftwards arrow",
+            Arrow::LeftRightArrow => "left right arrow",
+            Arrow::DoubleRightArrow => "rightwards double arrow",
+            Arrow::DoubleLeftArrow => "leftwards double arrow",
+            Arrow::DoubleLeftRightArrow => "left right double arrow",
+        },
+        TokenType::Operation(operation) => match operation {
+            Operation::Plus => "plus",
+            Operation::Minus => "minus",
+            Operation::CDot => "dot product",
+            Operation::Ast => "asterisk",
+            Operation::Star => "star",
+            Operation::Slash => "slash",
+            Operation::Backslash => "set minus",
+            Operation::Times => "times",
+            Operation::Div => "division sign",
+            Operation::LTimes => "left semidirect product",
+            Operation::RTimes => "right semidirect product",
+            Operation::Bowtie => "natural join",
+            Operation::Circ => "composition",
+            Operation::OPlus => "direct sum",
+            Operation::OTimes => "tensor product",
+            Operation::ODot => "circled dot",
+            Operation::Sum => "summation",
+            Operation::Prod => "product",
+            Operation::Wedge => "logical and",
+            Operation::BigWedge => "n-ary logical and",
+            Operation::Vee => "logical or",
+            Operation::BigVee => "n-ary logical or",
+            Operation::Cap => "intersection",
+            Operation::BigCap => "n-ary intersection",
+            Operation::Cup => "union",
+            Operation::BigCup => "n-ary union",
+        },
+        TokenType::Greek(greek) => match greek {
+            Greek::Alpha => "alpha",
+            Greek::Beta => "beta",
+            Greek::Gamma => "gamma",
+            Greek::UGamma => "capital gamma",
+            Greek::Delta => "delta",
+            Greek::UDelta => "capital delta",
+            Greek::Epsilon => "epsilon",
+            Greek::VarEpsilon => "variant epsilon",
+            Greek::Zeta => "zeta",
+            Greek::Eta => "eta",
+            Greek::Theta => "theta",
+            Greek::UTheta => "capital theta",
+            Greek::VarTheta => "variant theta",
+            Greek::Iota => "iota",
+            Greek::Kappa => "kappa",
+            Greek::Lambda => "lambda",
+            Greek::ULambda => "capital lambda",
+            Greek::Mu => "mu",
+            Greek::Nu => "nu",
+            Greek::Xi => "xi",
+            Greek::UXi => "capital xi",
+            Greek::Pi => "pi",
+            Greek::UPi => "capital pi",
+            Greek::Rho => "rho",
+            Greek::Sigma => "sigma",
+            Greek::USigma => "capital sigma",
+            Greek::Tau => "tau",
+            Greek::Upsilon => "upsilon",
+            Greek::Phi => "phi",
+            Greek::UPhi => "capital phi",
+            Greek::VarPhi => "variant phi",
+            Greek::Chi => "chi",
+            Greek::Psi => "psi",
+            Greek::UPsi => "capital psi",
+            Greek::Omega => "omega",
+            Greek::UOmega => "capital omega",
+        },
+        TokenType::Misc(misc) => match misc {
+            Misc::Int => "integral",
+            Misc::OInt => "contour integral",
+            Misc::Del => "partial derivative",
+            Misc::Grad => "nabla",
+            Misc::PlusMinus => "plus or minus",
+            Misc::EmptySet => "empty set",
+            Misc::Infinity => "infinity",
+            Misc::Aleph => "aleph",
+            Misc::Therefore => "therefore",
+            Misc::Because => "because",
+            Misc::LDots => "horizontal ellipsis",
+            Misc::CDots => "centered ellipsis",
+            Misc::VDots => "vertical ellipsis",
+            Misc::DDots => "diagonal ellipsis",
+            Misc::DoublePipes => "parallel",
+            Misc::DoublePipesQuad => "parallel with space",
+            Misc::Angle => "angle",
+            Misc::Frown => "frown",
+            Misc::Triangle => "triangle",
+            Misc::Diamond => "diamond",
+            Misc::Square => "square",
+            Misc::LFloor => "left floor",
+            Misc::RFloor => "right floor",
+            Misc::LCeiling => "left ceiling",
+            Misc::RCeiling => "right ceiling",
+            Misc::Lim => "limit",
+            Misc::CC => "complex numbers",
+            Misc::NN => "natural numbers",
+            Misc::QQ => "rational numbers",
+            Misc::RR => "real numbers",
+            Misc::ZZ => "integers",
+        },
+        TokenType::Relational(relational) => match relational {
+            Relational::Equals => "equals",
+            Relational::NotEquals => "not equal to",
+            Relational::Lt => "less than",
+            Relational::Gt => "greater than",
+            Relational::Lte => "less than or equal to",
+            Relational::Gte => "greater than or equal to",
+            Relational::Mlt => "much less than",
+            Relational::Mgt => "much greater than",
+            Relational::Prec => "precedes",
+            Relational::PrecEq => "precedes or equals",
+            Relational::Succ => "succeeds",
+            Relational::SuccEq => "succeeds or equals",
+            Relational::In => "element of",
+            Relational::NotIn => "not an element of",
+            Relational::Sub => "subset of",
+            Relational::Sup => "superset of",
+            Relational::SubEq => "subset of or equal to",
+            Relational::SupEq => "superset of or equal to",
+            Relational::Equiv => "identical to",
+            Relational::Cong => "congruent to",
+            Relational::Approx => "approximately equal to",
+            Relational::Prop => "proportional to",
+        },
+        TokenType::Logical(logical) => match logical {
+            Logical::And => "and",
+            Logical::Or => "or",
+            Logical::Not => "not",
+            Logical::Implies => "implies",
+            Logical::If => "if",
+            Logical::Iff => "if and only if",
+            Logical::ForAll => "for all",
+            Logical::Exists => "there exists",
+            Logical::Bot => "contradiction",
+            Logical::Top => "tautology",
+            Logical::VDash => "proves",
+            Logical::Models => "models",
+        },
+        TokenType::Function(function) => match function {
+            Function::Sin => "sine",
+            Function::Cos => "cosine",
+            Function::Tan => "tangent",
+            Function::Sec => "secant",
+            Function::Csc => "cosecant",
+            Function::Cot => "cotangent",
+            Function::Arcsin => "inverse sine",
+            Function::Arccos => "inverse cosine",
+            Function::Arctan => "inverse tangent",
+            Function::Sinh => "hyperbolic sine",
+            Function::Cosh => "hyperbolic cosine",
+            Function::Tanh => "hyperbolic tangent",
+            Function::Sech => "hyperbolic secant",
+            Function::Csch => "hyperbolic cosecant",
+            Function::Coth => "hyperbolic cotangent",
+            Function::Exp => "exponential function",
+            Function::Log => "logarithm",
+            Function::Ln => "natural logarithm",
+            Function::Det => "determinant",
+            Function::Dim => "dimension",
+            Function::Mod => "modulo",
+            Function::Gcd => "greatest common divisor",
+            Function::Lcm => "least common multiple",
+            Function::Lub => "least upper bound",
+            Function::Glb => "greatest lower bound",
+            Function::Min => "minimum",
+            Function::Max => "maximum",
+            Function::F => "function f",
+            Function::G => "function g",
+        },
+        TokenType::LBrace(brace) => match brace {
+            LBrace::LParen => "left parenthesis",
+            LBrace::LBracket => "left square bracket",
+            LBrace::LBrace => "left curly bracket",
+            LBrace::LColonBrace => "invisible left bracket",
+            LBrace::LAngle => "left angle bracket",
+        },
+        TokenType::RBrace(brace) => match brace {
+            RBrace::RParen => "right parenthesis",
+            RBrace::RBracket => "right square bracket",
+            RBrace::RBrace => "right curly bracket",
+            RBrace::RColonBrace => "invisible right bracket",
+            RBrace::RAngle => "right angle bracket",
+        },
+        _ => "symbol",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::tokens::types::{Greek, Relational, TokenType};
+
+    use super::{all, lookup, search, spelling, Category};
+
+    #[test]
+    fn test_lookup() {
+        let symbol = lookup("sube").unwrap();
+
+        assert_eq!(symbol.name, "sube");
+        assert_eq!(symbol.aliases, &["subseteq"]);
+        assert_eq!(symbol.category, Category::Relational);
+        assert_eq!(symbol.token_type, TokenType::Relational(Relational::SubEq));
+        assert_eq!(symbol.code_point, Some('⊆'));
+        assert_eq!(symbol.description, "subset of or equal to");
+
+        let symbol = lookup("Gamma").unwrap();
+        assert_eq!(symbol.token_type, TokenType::Greek(Greek::UGamma));
+        assert_eq!(symbol.code_point, Some('Γ'));
+        assert_eq!(symbol.description, "capital gamma");
+
+        assert_eq!(lookup("sin").unwrap().code_point, None);
+        assert_eq!(lookup("sin").unwrap().description, "sine");
+        assert_eq!(lookup("nope"), None);
+    }
+
+    #[test]
+    fn test_code_points() {
+        let code_point = |spelling| lookup(spelling).and_then(|symbol| symbol.code_point);
+
+        assert_eq!(code_point("alpha"), Some('\u{3B1}'));
+        assert_eq!(code_point("beta"), Some('\u{3B2}'));
+        assert_eq!(code_point("gamma"), Some('\u{3B3}'));
+        assert_eq!(code_point("Omega"), Some('\u{3A9}'));
+        assert_eq!(code_point("!="), Some('\u{2260}'));
+        assert_eq!(code_point("oo"), Some('\u{221E}'));
+        assert_eq!(code_point("RR"), Some('\u{211D}'));
+        assert_eq!(code_point("->"), Some('\u{2192}'));
+    }
+
+    #[test]
+    fn test_spelling() {
+        assert_eq!(
+            spelling(&TokenType::Relational(Relational::NotEquals)),
+            Some("!=")
+        );
+        assert_eq!(spelling(&TokenType::Greek(Greek::UGamma)), Some("Gamma"));
+        assert_eq!(spelling(&TokenType::Number), None);
+    }
+
+    #[test]
+    fn test_search() {
+        let found = search("right")
+            .into_iter()
+            .map(|(spelling, _)| spelling)
+            .collect::<Vec<_>>();
+
+        assert_eq!(found, vec!["rightarrow", "rightarrowtail"]);
+        assert_eq!(search("right")[0].1.name, "rarr");
+    }
+
+    #[test]
+    fn test_all() {
+        assert!(all().iter().all(|symbol| !symbol.description.is_empty()));
+        assert!(all()
+            .iter()
+            .any(|symbol| symbol.category == Category::Function));
+    }
+}
@@ -0,0 +1,214 @@
+//!
+//! # Text
+//!
+//! Plain text backend, linearizes the AST into a single line of Unicode,
+//! e.g. for screen readers, alt texts or terminals. Symbols are rendered as
+//! the characters the MathML backend would show, fractions and scripts use
+//! `/`, `_` and `^` with parentheses where needed.
+//!
+
+use itertools::Itertools;
+
+use crate::{
+    ast::{BiExpression, Binary, Expression, Expressions, Literal, Table, Unary, AST},
+    mathml::{symbol_to_mathml, text_content},
+    tokens::{
+        types::{BinaryOperator, TokenType, UnaryOperator},
+        Token,
+    },
+};
+
+pub fn to_text(ast: &AST) -> String {
+    expressions_to_text(&ast.expressions)
+}
+
+fn expressions_to_text(expressions: &Expressions) -> String {
+    let mut text = String::new();
+
+    for part in expressions.expressions.iter().map(expression_to_text) {
+        // keep words apart, `sin x` must not become `sinx`
+        if text.ends_with(char::is_alphanumeric) && part.starts_with(char::is_alphanumeric) {
+            text.push(' ');
+        }
+        text.push_str(&part);
+    }
+
+    text
+}
+
+///
+/// Renders a single expression.
+///
+pub fn expression_to_text(expression: &Expression) -> String {
+    match expression {
+        Expression::Frac(frac) => format!(
+            "{}/{}",
+            operand(&frac.expression_1),
+            operand(&frac.expression_2)
+        ),
+        Expression::Sub(bi_expression) => script(bi_expression, "_"),
+        Expression::Pow(bi_expression) => script(bi_expression, "^"),
+        Expression::SubPow(tri_expression) => format!(
+            "{}_{}^{}",
+            operand(&tri_expression.expression_1),
+            operand(&tri_expression.expression_2),
+            operand(&tri_expression.expression_3)
+        ),
+        Expression::Group(group) => format!(
+            "{}{}{}",
+            token_to_text(&group.l_brace),
+            expressions_to_text(&group.expressions),
+            token_to_text(&group.r_brace)
+        ),
+        Expression::Unary(unary) => unary_to_text(unary),
+        Expression::Binary(binary) => binary_to_text(binary),
+        Expression::Literal(Literal::Literal(token)) => token_to_text(token),
+        Expression::Literal(Literal::Table(table)) => table_to_text(table),
+        Expression::Expressions(expressions) => expressions_to_text(expressions),
+        Expression::Unit => String::new(),
+    }
+}
+
+///
+/// Renders an operand of `/`, `_` or `^`, parenthesized unless it is a
+/// single symbol or number.
+///
+fn operand(expression: &Expression) -> String {
+    let text = match expression {
+        Expression::Group(group) => expressions_to_text(&group.expressions),
+        _ => expression_to_text(expression),
+    };
+
+    if text.chars().count() <= 1 || text.chars().all(char::is_alphanumeric) {
+        text
+    } else {
+        format!("({text})")
+    }
+}
+
+fn script(bi_expression: &BiExpression, operator: &str) -> String {
+    format!(
+        "{}{operator}{}",
+        operand(&bi_expression.expression_1),
+        operand(&bi_expression.expression_2)
+    )
+}
+
+fn unary_to_text(unary: &Unary) -> String {
+    let TokenType::UnaryOperator(operator) = &unary.operator.token_type else {
+        return operand(&unary.expression);
+    };
+
+    let combining = match operator {
+        UnaryOperator::Sqrt => return format!("√{}", operand(&unary.expression)),
+        UnaryOperator::Abs => return format!("|{}|", inner(&unary.expression)),
+        UnaryOperator::Floor => return format!("⌊{}⌋", inner(&unary.expression)),
+        UnaryOperator::Ceil => return format!("⌈{}⌉", inner(&unary.expression)),
+        UnaryOperator::Norm => return format!("‖{}‖", inner(&unary.expression)),
+        UnaryOperator::Text | UnaryOperator::UBrace | UnaryOperator::OBrace => {
+            return inner(&unary.expression)
+        }
+        UnaryOperator::Hat => '\u{302}',
+        UnaryOperator::Bar => '\u{304}',
+        UnaryOperator::Ul => '\u{332}',
+        UnaryOperator::Vec => '\u{20D7}',
+        UnaryOperator::Tilde => '\u{303}',
+        UnaryOperator::Dot => '\u{307}',
+        UnaryOperator::DDot => '\u{308}',
+        UnaryOperator::Cancel => '\u{336}',
+    };
+
+    inner(&unary.expression)
+        .chars()
+        .flat_map(|c| [c, combining])
+        .collect()
+}
+
+fn inner(expression: &Expression) -> String {
+    match expression {
+        Expression::Group(group) => expressions_to_text(&group.expressions),
+        _ => expression_to_text(expression),
+    }
+}
+
+fn binary_to_text(binary: &Binary) -> String {
+    let first = inner(&binary.expression_1);
+
+    match binary.operator.token_type {
+        TokenType::BinaryOperator(BinaryOperator::Root) => {
+            let radical = match first.as_str() {
+                "3" => "∛".to_string(),
+                "4" => "∜".to_string(),
+                _ => format!("{}√", operand(&binary.expression_1)),
+            };
+            format!("{radical}{}", operand(&binary.expression_2))
+        }
+        TokenType::BinaryOperator(BinaryOperator::Overset) => {
+            format!(
+                "{}^{}",
+                operand(&binary.expression_2),
+                operand(&binary.expression_1)
+            )
+        }
+        TokenType::BinaryOperator(BinaryOperator::Underset) => {
+            format!(
+                "{}_{}",
+                operand(&binary.expression_2),
+                operand(&binary.expression_1)
+            )
+        }
+        _ => inner(&binary.expression_2),
+    }
+}
+
+///
+/// Tables are written row by row, `[1, 2; 3, 4]`.
+///
+fn table_to_text(table: &Table) -> String {
+    format!(
+        "{}{}{}",
+        token_to_text(&table.l_brace),
+        table
+            .rows
+            .iter()
+            .map(|row| row.cols.iter().map(expressions_to_text).join(", "))
+            .join("; "),
+        token_to_text(&table.r_brace)
+    )
+}
+
+fn token_to_text(token: &Token) -> String {
+    match token.token_type {
+        TokenType::Symbol | TokenType::Number | TokenType::Text => token.span.text.to_string(),
+        TokenType::Relational(_) | TokenType::Arrow(_) | TokenType::Logical(_) => {
+            format!(" {} ", text_content(&symbol_to_mathml(token)).trim())
+        }
+        _ => text_content(&symbol_to_mathml(token)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::to_ast;
+
+    use super::to_text;
+
+    fn text(asciimath: &str) -> String {
+        to_text(&to_ast(asciimath))
+    }
+
+    #[test]
+    fn test_expressions() {
+        assert_eq!(text("(a+b)/6"), "(a+b)/6");
+        assert_eq!(text("sum_(i=1)^n i^3"), "∑_(i = 1)^n i^3");
+        assert_eq!(text("sin x le sqrt(2)"), "sin x ≤ √2");
+        assert_eq!(text("root(3)(x) + hat a"), "∛x+a\u{302}");
+        assert_eq!(text("abs(x) \"text\""), "|x|text");
+    }
+
+    #[test]
+    fn test_tables() {
+        assert_eq!(text("[[1,2],[3,4]]"), "[1, 2; 3, 4]");
+        assert_eq!(text("{:[a],[b]:}"), "a; b");
+    }
+}
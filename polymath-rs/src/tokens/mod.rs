@@ -32,18 +32,60 @@ pub struct Cursor {
     pos: RefCell<usize>,
 }
 
-pub fn tokenize(text: &str) -> Vec<Token> {
+pub fn tokenize(text: &str) -> Vec<Token<'_>> {
+    tokenize_from(text, 0).map(|(_, token, _)| token).collect()
+}
+
+///
+/// Tokenizes `text` starting at the byte offset `start`. Every token is
+/// returned together with the offsets the tokenizer started reading it at
+/// (after skipping whitespace) and stopped at.
+///
+pub(crate) fn tokenize_from(
+    text: &str,
+    start: usize,
+) -> impl Iterator<Item = (usize, Token<'_>, usize)> {
     let cursor = Cursor {
-        pos: RefCell::new(0),
+        pos: RefCell::new(start),
     };
-    let mut token_vec = Vec::new();
 
-    while !cursor.eos(text) {
-        cursor.skip_whitespace(text);
-        token_vec.push(parse_token(&cursor, text))
-    }
+    std::iter::from_fn(move || {
+        if cursor.eos(text) {
+            None
+        } else {
+            cursor.skip_whitespace(text);
+            let start = cursor.get_pos();
+            let token = parse_token(&cursor, text);
+            Some((start, token, cursor.get_pos()))
+        }
+    })
+}
 
-    token_vec
+///
+/// Length of the longest pattern any token type is matched against. The
+/// tokenizer never looks further ahead than this, except for numbers and
+/// texts.
+///
+pub(crate) fn longest_pattern() -> usize {
+    [
+        UNARY_OPERATORS,
+        BINARY_OPERATORS,
+        ARROWS,
+        OPERATION,
+        GREEK,
+        MISC,
+        RELATIONAL,
+        LOGICAL,
+        FUNCTION,
+        LBRACES,
+        RBRACES,
+    ]
+    .iter()
+    .flat_map(|table| table.iter())
+    .flat_map(|(patterns, _)| patterns.iter())
+    .map(|pattern| pattern.len())
+    .max()
+    .unwrap_or(1)
 }
 
 impl Cursor {
@@ -148,9 +190,11 @@ impl Cursor {
     }
 
     fn find_next(&self, text: &str, patterns: &[&str], offset: usize) -> Option<usize> {
-        text.char_indices()
-            .skip(self.get_pos() + offset)
-            .map(|(offset, _)| (offset, &text[offset..]))
+        let start = self.get_pos() + offset;
+
+        text[start..]
+            .char_indices()
+            .map(|(offset, _)| (start + offset, &text[start + offset..]))
             .find(|(_offset, substr)| patterns.iter().any(|pattern| substr.starts_with(*pattern)))
             .map(|(offset, _)| offset)
     }
@@ -185,8 +229,8 @@ impl Cursor {
                 )
             });
 
-        if pattern.is_some() {
-            self.set_pos(pattern.as_ref().unwrap().0);
+        if let Some((pos, _)) = &pattern {
+            self.set_pos(*pos);
         }
 
         pattern.map(|tuple| tuple.1)
@@ -214,8 +258,8 @@ impl Cursor {
                 })
         });
 
-        if token.is_some() {
-            self.set_pos(token.as_ref().unwrap().0.end);
+        if let Some((span, _)) = &token {
+            self.set_pos(span.end);
         }
 
         token
@@ -317,3 +361,31 @@ fn parse_token<'a>(cursor: &Cursor, text: &'a str) -> Token<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{
+        tokenize,
+        types::{Function, TokenType},
+    };
+
+    #[test]
+    fn test_text_after_multibyte_characters() {
+        let tokens = tokenize("αβ \"x\" y");
+
+        assert_eq!(tokens[2].token_type, TokenType::Text);
+        assert_eq!(tokens[2].span.text, "x");
+        assert_eq!(tokens[3].span.text, "y");
+    }
+
+    #[test]
+    fn test_hyperbolic_functions() {
+        let tokens = tokenize("sinh x coth y sin h");
+        let types: Vec<_> = tokens.iter().map(|token| &token.token_type).collect();
+
+        assert_eq!(types[0], &TokenType::Function(Function::Sinh));
+        assert_eq!(types[2], &TokenType::Function(Function::Coth));
+        assert_eq!(types[4], &TokenType::Function(Function::Sin));
+        assert_eq!(tokens[0].span.text, "sinh");
+    }
+}
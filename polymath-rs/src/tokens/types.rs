@@ -866,7 +866,14 @@ pub const F_MAX: &str = "max";
 pub const F_F: &str = "f";
 pub const F_G: &str = "g";
 
+// The hyperbolic functions go first, `sinh` would be `sin` and `h` otherwise
 pub const FUNCTION: &[(&[&str], TokenType)] = &[
+    (&[F_SINH], TokenType::Function(Function::Sinh)),
+    (&[F_COSH], TokenType::Function(Function::Cosh)),
+    (&[F_TANH], TokenType::Function(Function::Tanh)),
+    (&[F_SECH], TokenType::Function(Function::Sech)),
+    (&[F_CSCH], TokenType::Function(Function::Csch)),
+    (&[F_COTH], TokenType::Function(Function::Coth)),
     (&[F_SIN], TokenType::Function(Function::Sin)),
     (&[F_COS], TokenType::Function(Function::Cos)),
     (&[F_TAN], TokenType::Function(Function::Tan)),
@@ -876,12 +883,6 @@ pub const FUNCTION: &[(&[&str], TokenType)] = &[
     (&[F_ARCSIN], TokenType::Function(Function::Arcsin)),
     (&[F_ARCCOS], TokenType::Function(Function::Arccos)),
     (&[F_ARCTAN], TokenType::Function(Function::Arctan)),
-    (&[F_SINH], TokenType::Function(Function::Sinh)),
-    (&[F_COSH], TokenType::Function(Function::Cosh)),
-    (&[F_TANH], TokenType::Function(Function::Tanh)),
-    (&[F_SECH], TokenType::Function(Function::Sech)),
-    (&[F_CSCH], TokenType::Function(Function::Csch)),
-    (&[F_COTH], TokenType::Function(Function::Coth)),
     (&[F_EXP], TokenType::Function(Function::Exp)),
     (&[F_LOG], TokenType::Function(Function::Log)),
     (&[F_LN], TokenType::Function(Function::Ln)),
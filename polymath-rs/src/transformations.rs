@@ -226,6 +226,9 @@ fn transform_group<'a>(group: Group<'a>) -> Expression<'a> {
                 })
                 .map(|(index, _)| index)
         })
+        // count the columns that remain in front of the separator
+        .enumerate()
+        .map(|(count, index)| index - count)
         .collect::<Vec<usize>>();
 
     // remove all vertical bars
@@ -248,19 +251,6 @@ fn transform_group<'a>(group: Group<'a>) -> Expression<'a> {
         })
         .collect::<Vec<TableRow>>();
 
-    // fill first row back up again
-    if let Some(second_row) = table_contents.get(1).map(|row| row.cols.len()) {
-        if let Some(first_row) = table_contents.get_mut(0) {
-            for i in 0..second_row {
-                if first_row.cols.get(i).is_none() {
-                    first_row.cols.push(Expressions {
-                        expressions: VecDeque::new(),
-                    });
-                }
-            }
-        }
-    }
-
     // TODO some combinations of parenthesis are actually not accepted for a table layout in the original asciimath
     Expression::Literal(crate::ast::Literal::Table(Table {
         seperators: seperator_pos,
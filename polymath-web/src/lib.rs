@@ -1,5 +1,12 @@
+mod tree;
 mod utils;
 
+use polymath_rs::{
+    diagnose,
+    mathml::{self, Display},
+    render as render_to, to_ast, Format,
+};
+use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
@@ -8,7 +15,131 @@ use wasm_bindgen::prelude::*;
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
+#[wasm_bindgen(typescript_custom_section)]
+const TYPES: &'static str = r#"
+export interface RenderOptions {
+  /** Defaults to `"mathml"` */
+  format?: "mathml" | "latex" | "text";
+  /** MathML only, defaults to `"block"` */
+  display?: "block" | "inline";
+}
+
+/** Offsets count UTF-16 code units, like JavaScript string indices. */
+export interface Span {
+  start: number;
+  end: number;
+}
+
+export interface Diagnostic {
+  start: number;
+  end: number;
+  severity: "error" | "warning";
+  message: string;
+}
+
+export interface Token {
+  text: string;
+  /** Token category, e.g. `"greek"` or `"symbol"` */
+  kind: string;
+  /** Variant within the category, e.g. `"Alpha"` */
+  name: string | null;
+  /** `null` for operands the parser made up */
+  span: Span | null;
+}
+
+export type Node =
+  | { type: "expressions"; span: Span | null; children: Node[] }
+  | { type: "frac"; span: Span | null; numerator: Node; denominator: Node }
+  | { type: "sub"; span: Span | null; base: Node; sub: Node }
+  | { type: "pow"; span: Span | null; base: Node; sup: Node }
+  | { type: "subPow"; span: Span | null; base: Node; sub: Node; sup: Node }
+  | { type: "group"; span: Span | null; open: Token; close: Token; children: Node[] }
+  | { type: "unary"; span: Span | null; operator: Token; operand: Node }
+  | { type: "binary"; span: Span | null; operator: Token; first: Node; second: Node }
+  | { type: "token"; span: Span | null; token: Token }
+  | {
+      type: "table";
+      span: Span | null;
+      open: Token;
+      close: Token;
+      separators: number[];
+      rows: Node[][];
+    };
+
+export interface ParseResult {
+  diagnostics: Diagnostic[];
+  ast: Node;
+}
+"#;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RenderOptions {
+    format: RenderFormat,
+    display: RenderDisplay,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum RenderFormat {
+    #[default]
+    MathMl,
+    Latex,
+    Text,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum RenderDisplay {
+    #[default]
+    Block,
+    Inline,
+}
+
 #[wasm_bindgen]
 pub fn asciimath_to_mathml(input: &str) -> String {
     polymath_rs::to_math_ml(input)
 }
+
+///
+/// Renders `input` as MathML, LaTeX or text. Throws on invalid options.
+///
+#[wasm_bindgen]
+pub fn render(
+    input: &str,
+    #[wasm_bindgen(unchecked_param_type = "RenderOptions | undefined")] options: JsValue,
+) -> Result<String, JsError> {
+    let options: RenderOptions = if options.is_undefined() || options.is_null() {
+        RenderOptions::default()
+    } else {
+        serde_wasm_bindgen::from_value(options)?
+    };
+
+    let format = match options.format {
+        RenderFormat::MathMl => Format::MathMl,
+        RenderFormat::Latex => Format::Latex,
+        RenderFormat::Text => Format::Text,
+    };
+    let display = match options.display {
+        RenderDisplay::Block => Display::Block,
+        RenderDisplay::Inline => Display::Inline,
+    };
+
+    Ok(render_to(
+        input,
+        format,
+        &mathml::Options {
+            display,
+            ..Default::default()
+        },
+    ))
+}
+
+///
+/// Parses `input` into its diagnostics and AST.
+///
+#[wasm_bindgen(unchecked_return_type = "ParseResult")]
+pub fn parse(input: &str) -> Result<JsValue, JsError> {
+    let result = tree::parse_result(input, &to_ast(input), diagnose(input));
+    Ok(result.serialize(&serde_wasm_bindgen::Serializer::json_compatible())?)
+}
@@ -0,0 +1,305 @@
+//!
+//! # Tree
+//!
+//! JavaScript facing view of the AST and diagnostics. Offsets count UTF-16
+//! code units so they can be used as JavaScript string indices directly.
+//!
+
+use polymath_rs::{
+    ast::{Expression, Expressions, Literal, AST},
+    diagnostics::{Diagnostic as RsDiagnostic, Severity},
+    tokens::Token as RsToken,
+};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub start: usize,
+    pub end: usize,
+    pub severity: &'static str,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Token {
+    pub text: String,
+    /// Token category, e.g. `greek` or `symbol`
+    pub kind: String,
+    /// Variant within the category, e.g. `Alpha`
+    pub name: Option<String>,
+    /// `null` for operands the parser made up
+    pub span: Option<Span>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Node {
+    Expressions {
+        span: Option<Span>,
+        children: Vec<Node>,
+    },
+    Frac {
+        span: Option<Span>,
+        numerator: Box<Node>,
+        denominator: Box<Node>,
+    },
+    Sub {
+        span: Option<Span>,
+        base: Box<Node>,
+        sub: Box<Node>,
+    },
+    Pow {
+        span: Option<Span>,
+        base: Box<Node>,
+        sup: Box<Node>,
+    },
+    #[serde(rename = "subPow")]
+    SubPow {
+        span: Option<Span>,
+        base: Box<Node>,
+        sub: Box<Node>,
+        sup: Box<Node>,
+    },
+    Group {
+        span: Option<Span>,
+        open: Token,
+        close: Token,
+        children: Vec<Node>,
+    },
+    Unary {
+        span: Option<Span>,
+        operator: Token,
+        operand: Box<Node>,
+    },
+    Binary {
+        span: Option<Span>,
+        operator: Token,
+        first: Box<Node>,
+        second: Box<Node>,
+    },
+    Token {
+        span: Option<Span>,
+        token: Token,
+    },
+    Table {
+        span: Option<Span>,
+        open: Token,
+        close: Token,
+        /// Column indices preceded by a vertical line
+        separators: Vec<usize>,
+        rows: Vec<Vec<Node>>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ParseResult {
+    pub diagnostics: Vec<Diagnostic>,
+    pub ast: Node,
+}
+
+///
+/// Maps byte offsets of a string to UTF-16 offsets.
+///
+struct Offsets(Vec<usize>);
+
+impl Offsets {
+    fn new(text: &str) -> Self {
+        let mut offsets = vec![0; text.len() + 1];
+        let mut units = 0;
+
+        for (index, c) in text.char_indices() {
+            offsets[index..index + c.len_utf8()].fill(units);
+            units += c.len_utf16();
+        }
+        offsets[text.len()] = units;
+
+        Self(offsets)
+    }
+
+    fn span(&self, span: std::ops::Range<usize>) -> Span {
+        Span {
+            start: self.0[span.start.min(self.0.len() - 1)],
+            end: self.0[span.end.min(self.0.len() - 1)],
+        }
+    }
+}
+
+pub fn parse_result(input: &str, ast: &AST, diagnostics: Vec<RsDiagnostic>) -> ParseResult {
+    let offsets = Offsets::new(input);
+
+    ParseResult {
+        diagnostics: diagnostics
+            .into_iter()
+            .map(|diagnostic| {
+                let span = offsets.span(diagnostic.span);
+                Diagnostic {
+                    start: span.start,
+                    end: span.end,
+                    severity: match diagnostic.severity {
+                        Severity::Error => "error",
+                        Severity::Warning => "warning",
+                    },
+                    message: diagnostic.message,
+                }
+            })
+            .collect(),
+        ast: expressions(&ast.expressions, &offsets),
+    }
+}
+
+fn expressions(expressions: &Expressions, offsets: &Offsets) -> Node {
+    Node::Expressions {
+        span: expressions.span().map(|span| offsets.span(span)),
+        children: children(expressions, offsets),
+    }
+}
+
+fn children(expressions: &Expressions, offsets: &Offsets) -> Vec<Node> {
+    expressions
+        .expressions
+        .iter()
+        .filter(|expression| !matches!(expression, Expression::Unit))
+        .map(|expression| node(expression, offsets))
+        .collect()
+}
+
+fn node(expression: &Expression, offsets: &Offsets) -> Node {
+    let span = expression.span().map(|span| offsets.span(span));
+    let boxed = |expression: &Expression| Box::new(node(expression, offsets));
+
+    match expression {
+        Expression::Frac(frac) => Node::Frac {
+            span,
+            numerator: boxed(&frac.expression_1),
+            denominator: boxed(&frac.expression_2),
+        },
+        Expression::Sub(sub) => Node::Sub {
+            span,
+            base: boxed(&sub.expression_1),
+            sub: boxed(&sub.expression_2),
+        },
+        Expression::Pow(pow) => Node::Pow {
+            span,
+            base: boxed(&pow.expression_1),
+            sup: boxed(&pow.expression_2),
+        },
+        Expression::SubPow(sub_pow) => Node::SubPow {
+            span,
+            base: boxed(&sub_pow.expression_1),
+            sub: boxed(&sub_pow.expression_2),
+            sup: boxed(&sub_pow.expression_3),
+        },
+        Expression::Group(group) => Node::Group {
+            span,
+            open: token(&group.l_brace, offsets),
+            close: token(&group.r_brace, offsets),
+            children: children(&group.expressions, offsets),
+        },
+        Expression::Unary(unary) => Node::Unary {
+            span,
+            operator: token(&unary.operator, offsets),
+            operand: boxed(&unary.expression),
+        },
+        Expression::Binary(binary) => Node::Binary {
+            span,
+            operator: token(&binary.operator, offsets),
+            first: boxed(&binary.expression_1),
+            second: boxed(&binary.expression_2),
+        },
+        Expression::Literal(Literal::Literal(literal)) => Node::Token {
+            span,
+            token: token(literal, offsets),
+        },
+        Expression::Literal(Literal::Table(table)) => Node::Table {
+            span,
+            open: token(&table.l_brace, offsets),
+            close: token(&table.r_brace, offsets),
+            separators: table.seperators.clone(),
+            rows: table
+                .rows
+                .iter()
+                .map(|row| {
+                    row.cols
+                        .iter()
+                        .map(|col| expressions(col, offsets))
+                        .collect()
+                })
+                .collect(),
+        },
+        Expression::Expressions(inner) => expressions(inner, offsets),
+        Expression::Unit => Node::Expressions {
+            span: None,
+            children: vec![],
+        },
+    }
+}
+
+fn token(token: &RsToken, offsets: &Offsets) -> Token {
+    // `Greek(Alpha)` becomes kind `greek` and name `Alpha`
+    let debug = format!("{:?}", token.token_type);
+    let (kind, name) = match debug.split_once('(') {
+        Some((kind, name)) => (kind, Some(name.trim_end_matches(')').to_string())),
+        None => (debug.as_str(), None),
+    };
+
+    let mut chars = kind.chars();
+    let kind = chars
+        .next()
+        .map(|first| first.to_lowercase().chain(chars).collect())
+        .unwrap_or_default();
+
+    let span = match token.token_type {
+        polymath_rs::tokens::types::TokenType::None => None,
+        _ => Some(offsets.span(token.span.start..token.span.end)),
+    };
+
+    Token {
+        text: token.span.text.to_string(),
+        kind,
+        name,
+        span,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use polymath_rs::{diagnose, to_ast};
+    use serde_json::json;
+
+    use super::parse_result;
+
+    #[test]
+    fn test_parse_result() {
+        let input = "α/(b";
+        let result =
+            serde_json::to_value(parse_result(input, &to_ast(input), diagnose(input))).unwrap();
+
+        assert_eq!(
+            result["diagnostics"],
+            json!([{ "start": 2, "end": 3, "severity": "error", "message": "unclosed bracket `(`" }])
+        );
+        assert_eq!(result["ast"]["type"], "expressions");
+        assert_eq!(result["ast"]["children"][0]["type"], "frac");
+        assert_eq!(
+            result["ast"]["children"][0]["numerator"]["token"],
+            json!({ "text": "α", "kind": "symbol", "name": null, "span": { "start": 0, "end": 1 } })
+        );
+    }
+
+    #[test]
+    fn test_missing_operand() {
+        let input = "sqrt";
+        let result = serde_json::to_value(parse_result(input, &to_ast(input), vec![])).unwrap();
+        let unary = &result["ast"]["children"][0];
+
+        assert_eq!(unary["type"], "unary");
+        assert_eq!(unary["operator"]["kind"], "unaryOperator");
+        assert_eq!(unary["operand"]["token"]["span"], json!(null));
+    }
+}
@@ -3,11 +3,65 @@
 #![cfg(target_arch = "wasm32")]
 
 extern crate wasm_bindgen_test;
+use js_sys::{Array, Reflect, JSON};
+use polymath_web::{asciimath_to_mathml, parse, render};
+use wasm_bindgen::JsValue;
 use wasm_bindgen_test::*;
 
 wasm_bindgen_test_configure!(run_in_browser);
 
+fn get(value: &JsValue, key: &str) -> JsValue {
+    Reflect::get(value, &key.into()).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn test_asciimath_to_mathml() {
+    assert_eq!(
+        asciimath_to_mathml("x"),
+        "<math display=\"block\"><mi>x</mi></math>"
+    );
+}
+
 #[wasm_bindgen_test]
-fn pass() {
-    assert_eq!(1 + 1, 2);
+fn test_render() {
+    assert_eq!(
+        render("x", JsValue::UNDEFINED).unwrap(),
+        "<math display=\"block\"><mi>x</mi></math>"
+    );
+    assert_eq!(
+        render("x", JSON::parse(r#"{"display": "inline"}"#).unwrap()).unwrap(),
+        "<math display=\"inline\"><mi>x</mi></math>"
+    );
+    assert_eq!(
+        render("a/b", JSON::parse(r#"{"format": "latex"}"#).unwrap()).unwrap(),
+        "\\frac{a}{b}"
+    );
+    assert_eq!(
+        render("sqrt(2)", JSON::parse(r#"{"format": "text"}"#).unwrap()).unwrap(),
+        "√2"
+    );
+
+    assert!(render("x", JSON::parse(r#"{"format": "png"}"#).unwrap()).is_err());
+    assert!(render("x", JSON::parse(r#"{"colour": "red"}"#).unwrap()).is_err());
+}
+
+#[wasm_bindgen_test]
+fn test_parse() {
+    let result = parse("α/(b").unwrap();
+
+    let diagnostics = Array::from(&get(&result, "diagnostics"));
+    assert_eq!(diagnostics.length(), 1);
+    let diagnostic = diagnostics.get(0);
+    assert_eq!(get(&diagnostic, "start"), 2);
+    assert_eq!(get(&diagnostic, "end"), 3);
+    assert_eq!(get(&diagnostic, "message"), "unclosed bracket `(`");
+
+    let ast = get(&result, "ast");
+    assert_eq!(get(&ast, "type"), "expressions");
+    let frac = Array::from(&get(&ast, "children")).get(0);
+    assert_eq!(get(&frac, "type"), "frac");
+    assert_eq!(
+        JSON::stringify(&get(&get(&frac, "numerator"), "token")).unwrap(),
+        r#"{"text":"α","kind":"symbol","name":null,"span":{"start":0,"end":1}}"#
+    );
 }
@@ -0,0 +1,49 @@
+use std::{fs, path::Path};
+
+use clap::Subcommand;
+use color_eyre::{eyre::eyre, Result};
+
+const CRATE_DIR: &str = "polymath-c";
+const HEADER: &str = "polymath-c/polymath.h";
+
+#[derive(Subcommand, Debug)]
+pub enum PolymathCCommand {
+    /// Regenerate `polymath.h` from the sources of polymath-c
+    Header {
+        /// Fail if the checked in header is out of date instead of writing it
+        #[arg(long)]
+        check: bool,
+    },
+}
+
+pub fn exec(cmd: PolymathCCommand) -> Result<()> {
+    match cmd {
+        PolymathCCommand::Header { check: false } => {
+            fs::write(HEADER, generate_header()?)?;
+            Ok(())
+        }
+        PolymathCCommand::Header { check: true } => {
+            match fs::read_to_string(HEADER)? == generate_header()? {
+                true => Ok(()),
+                false => Err(eyre!(
+                    "{HEADER} is out of date, run `cargo xtask polymath-c header`"
+                )),
+            }
+        }
+    }
+}
+
+fn generate_header() -> Result<String> {
+    let crate_dir = Path::new(CRATE_DIR);
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml"))
+        .map_err(|error| eyre!(error))?;
+
+    let mut header = Vec::new();
+    cbindgen::Builder::new()
+        .with_crate(crate_dir)
+        .with_config(config)
+        .generate()?
+        .write(&mut header);
+
+    Ok(String::from_utf8(header)?)
+}
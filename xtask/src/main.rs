@@ -1,6 +1,8 @@
+use c::PolymathCCommand;
 use clap::{Parser, Subcommand};
 use java::PolymathJavaCommand;
 
+mod c;
 mod java;
 
 #[derive(Parser, Debug)]
@@ -13,6 +15,8 @@ struct Args {
 enum Command {
     #[command(subcommand)]
     PolymathJava(PolymathJavaCommand),
+    #[command(subcommand)]
+    PolymathC(PolymathCCommand),
 }
 
 fn main() -> color_eyre::Result<()> {
@@ -21,5 +25,6 @@ fn main() -> color_eyre::Result<()> {
 
     match args.command {
         Command::PolymathJava(java_cmd) => java::exec(java_cmd),
+        Command::PolymathC(c_cmd) => c::exec(c_cmd),
     }
 }